@@ -0,0 +1,377 @@
+use anyhow::{anyhow, Context, Result};
+use rusqlite::{Connection, OpenFlags};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+use walkdir::WalkDir;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use super::provider::get_claude_dir;
+use super::relay_stations::{clear_station_caches, current_manager, RelayStationManager};
+
+/// Identifies archives written by `backup_claude_dir`
+pub const BACKUP_FORMAT: &str = "claude-suite-backup";
+
+/// Archive layout version; restores refuse archives newer than this
+pub const BACKUP_VERSION: u32 = 1;
+
+/// Marker entry holding the format and version
+const MARKER_ENTRY: &str = "claude-suite-backup.json";
+
+/// Prefix for files copied from `~/.claude`
+const CLAUDE_PREFIX: &str = "claude/";
+
+/// Entry holding the relay station database snapshot
+const RELAY_DB_ENTRY: &str = "relay/agents.db";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BackupMarker {
+    format: String,
+    version: u32,
+    created_at: i64,
+    includes_relay_db: bool,
+}
+
+/// Where a backup was written and how big it is
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupResult {
+    pub path: String,
+    pub size_bytes: u64,
+    pub files: usize,
+}
+
+/// What a restore brought back
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RestoreResult {
+    pub files_restored: usize,
+    pub stations_restored: usize,
+    pub tokens_restored: usize,
+}
+
+/// Whether `path` is one of `skip`. Only paths with a matching file name are canonicalized.
+fn is_skipped(path: &Path, skip: &[PathBuf]) -> bool {
+    skip.iter().any(|skipped| skipped.file_name() == path.file_name())
+        && path.canonicalize().is_ok_and(|path| skip.contains(&path))
+}
+
+/// Zips every regular file under `claude_dir`, plus a snapshot of the relay database when a
+/// manager is given, into `archive_path`. Returns the number of `~/.claude` files archived.
+///
+/// The archive is written to a temp file next to `archive_path` and renamed into place when
+/// complete; both are left out of the archive when they sit inside `claude_dir`.
+pub fn write_backup(claude_dir: &Path, manager: Option<&RelayStationManager>, archive_path: &Path) -> Result<usize> {
+    let dir = archive_path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or_else(|| Path::new("."));
+    let file = tempfile::NamedTempFile::new_in(dir).with_context(|| format!("Failed to create {}", archive_path.display()))?;
+    let canonical_dir = dir.canonicalize()?;
+    let skip = [canonical_dir.join(archive_path.file_name().unwrap_or_default()), file.path().canonicalize()?];
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    let marker = BackupMarker {
+        format: BACKUP_FORMAT.to_string(),
+        version: BACKUP_VERSION,
+        created_at: chrono::Utc::now().timestamp(),
+        includes_relay_db: manager.is_some(),
+    };
+    zip.start_file(MARKER_ENTRY, options)?;
+    zip.write_all(serde_json::to_string_pretty(&marker)?.as_bytes())?;
+
+    let mut files = 0;
+    for entry in WalkDir::new(claude_dir).follow_links(false) {
+        let entry = entry?;
+        if !entry.file_type().is_file() || is_skipped(entry.path(), &skip) {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(claude_dir)?;
+        let name = relative.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/");
+        zip.start_file(format!("{}{}", CLAUDE_PREFIX, name), options)?;
+        io::copy(&mut File::open(entry.path())?, &mut zip)?;
+        files += 1;
+    }
+
+    if let Some(manager) = manager {
+        let dir = tempfile::tempdir()?;
+        let snapshot = dir.path().join("agents.db");
+        manager.snapshot_to(&snapshot).context("Failed to snapshot relay station database")?;
+        zip.start_file(RELAY_DB_ENTRY, options)?;
+        io::copy(&mut File::open(&snapshot)?, &mut zip)?;
+    }
+
+    zip.finish()?.persist(archive_path).map_err(|e| e.error).with_context(|| format!("Failed to create {}", archive_path.display()))?;
+    Ok(files)
+}
+
+/// Checks that a relay database snapshot opens, passes SQLite's quick check and has stations
+fn validate_snapshot(path: &Path) -> Result<()> {
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let check: String = conn.query_row("PRAGMA quick_check", [], |row| row.get(0))?;
+    if check != "ok" {
+        return Err(anyhow!("Relay database snapshot is corrupt: {}", check));
+    }
+    conn.query_row("SELECT COUNT(*) FROM relay_stations", [], |row| row.get::<_, i64>(0))?;
+    Ok(())
+}
+
+/// Restored files moved into `~/.claude` so far, with where the file each one replaced was moved
+#[derive(Default)]
+struct PlacedFiles {
+    moves: Vec<(PathBuf, Option<PathBuf>)>,
+}
+
+impl PlacedFiles {
+    /// Renames `staged` over `target`, first moving an existing `target` to `previous`
+    fn place(&mut self, staged: &Path, target: &Path, previous: &Path) -> Result<()> {
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let replaced = if target.exists() {
+            if let Some(parent) = previous.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::rename(target, previous)?;
+            Some(previous.to_path_buf())
+        } else {
+            None
+        };
+        self.moves.push((target.to_path_buf(), replaced));
+        fs::rename(staged, target).with_context(|| format!("Failed to restore {}", target.display()))
+    }
+
+    /// Puts every replaced file back, newest move first
+    fn roll_back(self) {
+        for (target, replaced) in self.moves.into_iter().rev() {
+            let undone = match replaced {
+                Some(previous) => fs::rename(&previous, &target),
+                None => fs::remove_file(&target).or_else(|e| if e.kind() == io::ErrorKind::NotFound { Ok(()) } else { Err(e) }),
+            };
+            if let Err(e) = undone {
+                log::error!("Failed to roll back {}: {}", target.display(), e);
+            }
+        }
+    }
+}
+
+/// Restores an archive written by `write_backup`, overwriting files in `claude_dir` and
+/// replacing relay stations and tokens. Files not in the archive are left untouched.
+pub fn read_backup(archive_path: &Path, claude_dir: &Path, manager: Option<&RelayStationManager>) -> Result<RestoreResult> {
+    let file = File::open(archive_path).with_context(|| format!("Failed to open {}", archive_path.display()))?;
+    let mut zip = ZipArchive::new(file)?;
+
+    let marker: BackupMarker = {
+        let mut entry = zip.by_name(MARKER_ENTRY).map_err(|_| anyhow!("Not a Claude Suite backup: missing {}", MARKER_ENTRY))?;
+        let mut content = String::new();
+        entry.read_to_string(&mut content)?;
+        serde_json::from_str(&content).context("Invalid backup marker")?
+    };
+    if marker.format != BACKUP_FORMAT {
+        return Err(anyhow!("Not a Claude Suite backup: unknown format '{}'", marker.format));
+    }
+    if marker.version > BACKUP_VERSION {
+        return Err(anyhow!("Backup version {} is newer than the supported version {}", marker.version, BACKUP_VERSION));
+    }
+
+    // Stage inside claude_dir so files can be renamed into place without crossing filesystems
+    fs::create_dir_all(claude_dir)?;
+    let staging = tempfile::Builder::new().prefix(".claude-suite-restore-").tempdir_in(claude_dir)?;
+    let staged_dir = staging.path().join("new");
+    let previous_dir = staging.path().join("previous");
+    let mut staged_files = Vec::new();
+    let mut snapshot: Option<PathBuf> = None;
+
+    for index in 0..zip.len() {
+        let mut entry = zip.by_index(index)?;
+        if entry.is_dir() {
+            continue;
+        }
+        // enclosed_name rejects absolute paths and `..` components
+        let Some(name) = entry.enclosed_name() else { continue };
+
+        let target = if let Ok(relative) = name.strip_prefix(CLAUDE_PREFIX.trim_end_matches('/')) {
+            staged_files.push(relative.to_path_buf());
+            staged_dir.join(relative)
+        } else if entry.name() == RELAY_DB_ENTRY {
+            let path = staging.path().join("agents.db");
+            snapshot = Some(path.clone());
+            path
+        } else {
+            continue;
+        };
+
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        io::copy(&mut entry, &mut File::create(&target)?).with_context(|| format!("Failed to extract {}", entry.name()))?;
+    }
+
+    let snapshot = snapshot.filter(|_| manager.is_some());
+    if let Some(snapshot) = &snapshot {
+        validate_snapshot(snapshot).context("Invalid relay station database in backup")?;
+    }
+
+    // Nothing in claude_dir has changed so far; from here a failure puts the replaced files back
+    let mut placed = PlacedFiles::default();
+    let restored = (|| -> Result<RestoreResult> {
+        let mut result = RestoreResult::default();
+        for relative in &staged_files {
+            placed.place(&staged_dir.join(relative), &claude_dir.join(relative), &previous_dir.join(relative))?;
+            result.files_restored += 1;
+        }
+        if let (Some(manager), Some(snapshot)) = (manager, &snapshot) {
+            let (stations, tokens) = manager.restore_from_snapshot(snapshot).context("Failed to restore relay station database")?;
+            result.stations_restored = stations;
+            result.tokens_restored = tokens;
+            clear_station_caches();
+        }
+        Ok(result)
+    })();
+
+    if restored.is_err() {
+        placed.roll_back();
+    }
+    restored
+}
+
+/// Zips `~/.claude` and the relay station database into `claude-backup-<timestamp>.zip` under `dest_path`.
+/// The walk and compression run on a blocking thread so large directories don't stall the runtime.
+#[tauri::command]
+pub async fn backup_claude_dir(dest_path: String, app: AppHandle) -> Result<BackupResult, String> {
+    let claude_dir = get_claude_dir()?;
+    let dest_dir = PathBuf::from(dest_path);
+    fs::create_dir_all(&dest_dir).map_err(|e| format!("Failed to create backup directory: {}", e))?;
+    let archive_path = dest_dir.join(format!("claude-backup-{}.zip", chrono::Local::now().format("%Y%m%d-%H%M%S")));
+
+    let manager = current_manager(&app).ok();
+    let task_archive_path = archive_path.clone();
+    let files = tauri::async_runtime::spawn_blocking(move || write_backup(&claude_dir, manager.as_deref(), &task_archive_path))
+        .await
+        .map_err(|e| format!("Backup task failed: {}", e))?
+        .map_err(|e| format!("Failed to back up Claude directory: {:#}", e))?;
+    let size_bytes = fs::metadata(&archive_path).map(|m| m.len()).unwrap_or(0);
+
+    log::info!("Backed up {} files to {} ({} bytes)", files, archive_path.display(), size_bytes);
+    Ok(BackupResult { path: archive_path.to_string_lossy().to_string(), size_bytes, files })
+}
+
+/// Restores a backup written by `backup_claude_dir` after checking its version marker.
+/// Extraction runs on a blocking thread, like the backup itself.
+#[tauri::command]
+pub async fn restore_claude_dir(archive_path: String, app: AppHandle) -> Result<RestoreResult, String> {
+    let claude_dir = get_claude_dir()?;
+    let manager = current_manager(&app).ok();
+    let task_archive_path = archive_path.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || read_backup(Path::new(&task_archive_path), &claude_dir, manager.as_deref()))
+        .await
+        .map_err(|e| format!("Restore task failed: {}", e))?
+        .map_err(|e| format!("Failed to restore Claude directory: {:#}", e))?;
+
+    log::info!("Restored {} files and {} stations from {}", result.files_restored, result.stations_restored, archive_path);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::relay_stations::tests::{test_manager, test_station};
+
+    #[test]
+    fn test_backup_round_trip() {
+        let claude_dir = tempfile::tempdir().unwrap();
+        fs::write(claude_dir.path().join("settings.json"), r#"{"env": {}}"#).unwrap();
+        fs::create_dir_all(claude_dir.path().join("agents")).unwrap();
+        fs::write(claude_dir.path().join("agents").join("a.md"), "agent").unwrap();
+
+        let source = test_manager();
+        source.add_station(&test_station("a", "Relay", "https://a.example.com")).unwrap();
+
+        let out = tempfile::tempdir().unwrap();
+        let archive = out.path().join("backup.zip");
+        assert_eq!(write_backup(claude_dir.path(), Some(&source), &archive).unwrap(), 2);
+
+        let restore_dir = tempfile::tempdir().unwrap();
+        let target = test_manager();
+        target.add_station(&test_station("b", "Stale", "https://b.example.com")).unwrap();
+        let result = read_backup(&archive, restore_dir.path(), Some(&target)).unwrap();
+
+        assert_eq!((result.files_restored, result.stations_restored), (2, 1));
+        assert_eq!(fs::read_to_string(restore_dir.path().join("agents").join("a.md")).unwrap(), "agent");
+        let stations = target.list_stations().unwrap();
+        assert_eq!(stations.len(), 1);
+        assert_eq!(stations[0].id, "a");
+    }
+
+    #[test]
+    fn test_backup_inside_claude_dir_skips_itself() {
+        let claude_dir = tempfile::tempdir().unwrap();
+        fs::write(claude_dir.path().join("settings.json"), "{}").unwrap();
+        let archive = claude_dir.path().join("backups").join("backup.zip");
+        fs::create_dir_all(archive.parent().unwrap()).unwrap();
+
+        assert_eq!(write_backup(claude_dir.path(), None, &archive).unwrap(), 1);
+        let names: Vec<String> = ZipArchive::new(File::open(&archive).unwrap()).unwrap().file_names().map(String::from).collect();
+        assert!(names.iter().all(|name| !name.starts_with("claude/backups/")), "{:?}", names);
+        // No temp file is left behind next to the archive
+        assert_eq!(fs::read_dir(archive.parent().unwrap()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_failed_restore_leaves_claude_dir_untouched() {
+        let out = tempfile::tempdir().unwrap();
+        let archive = out.path().join("broken.zip");
+        let mut zip = ZipWriter::new(File::create(&archive).unwrap());
+        let options = SimpleFileOptions::default();
+        let marker = BackupMarker { format: BACKUP_FORMAT.to_string(), version: BACKUP_VERSION, created_at: 0, includes_relay_db: true };
+        zip.start_file(MARKER_ENTRY, options).unwrap();
+        zip.write_all(serde_json::to_string(&marker).unwrap().as_bytes()).unwrap();
+        zip.start_file(format!("{}settings.json", CLAUDE_PREFIX), options).unwrap();
+        zip.write_all(b"{\"env\": {\"FROM\": \"backup\"}}").unwrap();
+        zip.start_file(RELAY_DB_ENTRY, options).unwrap();
+        zip.write_all(b"not a sqlite database").unwrap();
+        zip.finish().unwrap();
+
+        let claude_dir = tempfile::tempdir().unwrap();
+        fs::write(claude_dir.path().join("settings.json"), "{}").unwrap();
+        let target = test_manager();
+        target.add_station(&test_station("b", "Local", "https://b.example.com")).unwrap();
+
+        let err = read_backup(&archive, claude_dir.path(), Some(&target)).unwrap_err();
+        assert!(format!("{:#}", err).contains("Invalid relay station database"), "{:#}", err);
+        assert_eq!(fs::read_to_string(claude_dir.path().join("settings.json")).unwrap(), "{}");
+        assert_eq!(target.list_stations().unwrap().len(), 1);
+        // The staging directory is cleaned up
+        assert_eq!(fs::read_dir(claude_dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_placed_files_roll_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let existing = dir.path().join("settings.json");
+        fs::write(&existing, "old").unwrap();
+        let (staged_a, staged_b) = (dir.path().join("a.new"), dir.path().join("b.new"));
+        fs::write(&staged_a, "new").unwrap();
+        fs::write(&staged_b, "added").unwrap();
+
+        let mut placed = PlacedFiles::default();
+        placed.place(&staged_a, &existing, &dir.path().join("previous").join("settings.json")).unwrap();
+        placed.place(&staged_b, &dir.path().join("agents").join("b.md"), &dir.path().join("previous").join("b.md")).unwrap();
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "new");
+
+        placed.roll_back();
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "old");
+        assert!(!dir.path().join("agents").join("b.md").exists());
+    }
+
+    #[test]
+    fn test_restore_rejects_archive_without_marker() {
+        let out = tempfile::tempdir().unwrap();
+        let archive = out.path().join("other.zip");
+        let mut zip = ZipWriter::new(File::create(&archive).unwrap());
+        zip.start_file("readme.txt", SimpleFileOptions::default()).unwrap();
+        zip.finish().unwrap();
+
+        let err = read_backup(&archive, out.path(), None).unwrap_err();
+        assert!(err.to_string().contains("Not a Claude Suite backup"));
+    }
+}
@@ -7,4 +7,7 @@ pub mod slash_commands;
 pub mod clipboard;
 pub mod provider;
 pub mod relay_stations;
+pub mod relay_pool;
 pub mod relay_adapters;
+pub mod config_bundle;
+pub mod backup;
@@ -1,19 +1,40 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::env;
 use std::fs;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use once_cell::sync::Lazy;
+use tempfile::NamedTempFile;
 use tauri::{command, AppHandle, Manager};
 use crate::process::ProcessRegistryState;
 use crate::i18n;
+use crate::paths;
+use crate::redact::{redact, redact_opt, scrub};
+use super::relay_stations::{get_station_models, with_manager, RelayError};
+use uuid::Uuid;
 
 #[command]
 pub fn set_backend_language(language: String) -> Result<String, String> {
     let lang = i18n::Language::from_str(&language);
-    i18n::set_language(lang);
+    // 用户显式选择的语言需要持久化，保存失败不影响本次切换
+    if let Err(e) = i18n::set_language_persisted(lang) {
+        log::warn!("Failed to save language preference: {}", e);
+    }
     Ok(format!("Backend language set to: {}", language))
 }
 
+// 切换后端语言并写入偏好文件，重启后自动恢复；保存失败时返回错误
+#[command]
+pub fn set_language_persisted(lang: String) -> Result<String, String> {
+    let language = i18n::Language::from_str(&lang);
+    let code = language.code();
+    i18n::set_language_persisted(language)?;
+    Ok(code.to_string())
+}
+
 #[command]
 pub fn get_backend_language() -> Result<String, String> {
     let lang = i18n::get_language();
@@ -23,7 +44,49 @@ pub fn get_backend_language() -> Result<String, String> {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[command]
+pub fn reload_translations() -> Result<String, String> {
+    let count = i18n::reload_translations();
+    Ok(i18n::t_with_args("i18n.reloaded", &[("count", &count.to_string())]))
+}
+
+#[command]
+pub fn get_missing_translations() -> Result<Vec<String>, String> {
+    Ok(i18n::get_missing_translations())
+}
+
+// 导出所有翻译（含外部文件覆盖）供翻译人员编辑，format 为 json 或 po
+#[command]
+pub fn export_translations(format: i18n::TranslationFormat, path: String) -> Result<String, String> {
+    let (content, count) = i18n::export_translations(format)?;
+    write_file_atomic(Path::new(&path), &content)
+        .map_err(|e| i18n::t_with_args("i18n.io_failed", &[("path", &path), ("error", &e.to_string())]))?;
+    Ok(i18n::t_with_args("i18n.exported", &[("count", &count.to_string()), ("path", &path)]))
+}
+
+// 导入编辑后的翻译文件（按扩展名识别 .po，其余按 JSON），合并进外部翻译文件并立即生效
+#[command]
+pub fn import_translations(path: String) -> Result<String, String> {
+    let content = fs::read_to_string(&path)
+        .map_err(|e| i18n::t_with_args("i18n.io_failed", &[("path", &path), ("error", &e.to_string())]))?;
+    let count = i18n::import_translations(&content, i18n::TranslationFormat::from_path(Path::new(&path)))
+        .map_err(|e| i18n::t_with_args("i18n.parse_failed", &[("error", &e)]))?;
+    Ok(i18n::t_with_args("i18n.imported", &[("count", &count.to_string())]))
+}
+
+// 按模块前缀分组列出所有翻译 key，供翻译工具统计各模块覆盖率
+#[command]
+pub fn list_all_translation_keys() -> Result<BTreeMap<String, Vec<String>>, String> {
+    Ok(i18n::list_translation_keys())
+}
+
+#[command]
+pub fn clear_missing_translations() -> Result<(), String> {
+    i18n::clear_missing_translations();
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize, Clone)]
 pub struct ProviderConfig {
     pub id: String,
     pub name: String,
@@ -32,6 +95,46 @@ pub struct ProviderConfig {
     pub auth_token: Option<String>,
     pub api_key: Option<String>,
     pub model: Option<String>,
+    pub small_fast_model: Option<String>,
+    pub extra_env: Option<HashMap<String, String>>, // 额外写入 settings env 的环境变量
+    pub fallback_chain: Option<Vec<String>>, // 按顺序尝试的备用代理商 ID
+}
+
+// 手动实现 Debug，避免日志中出现完整的 auth_token / api_key
+impl std::fmt::Debug for ProviderConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProviderConfig")
+            .field("id", &self.id)
+            .field("name", &self.name)
+            .field("description", &self.description)
+            .field("base_url", &self.base_url)
+            .field("auth_token", &redact_opt(&self.auth_token))
+            .field("api_key", &redact_opt(&self.api_key))
+            .field("model", &self.model)
+            .field("small_fast_model", &self.small_fast_model)
+            // extra_env 的值可能包含密钥，只输出键名
+            .field("extra_env", &self.extra_env.as_ref().map(|env| env.keys().collect::<Vec<_>>()))
+            .field("fallback_chain", &self.fallback_chain)
+            .finish()
+    }
+}
+
+impl ProviderConfig {
+    // 去掉字符串字段首尾空白，空的可选字段视为未填写；校验和保存都使用处理后的值
+    fn trimmed(mut self) -> Self {
+        fn trim_opt(value: Option<String>) -> Option<String> {
+            value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+        }
+        self.id = self.id.trim().to_string();
+        self.name = self.name.trim().to_string();
+        self.description = self.description.trim().to_string();
+        self.base_url = self.base_url.trim().to_string();
+        self.auth_token = trim_opt(self.auth_token);
+        self.api_key = trim_opt(self.api_key);
+        self.model = trim_opt(self.model);
+        self.small_fast_model = trim_opt(self.small_fast_model);
+        self
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -40,22 +143,151 @@ pub struct CurrentConfig {
     pub anthropic_auth_token: Option<String>,
     pub anthropic_api_key: Option<String>,
     pub anthropic_model: Option<String>,
+    pub anthropic_small_fast_model: Option<String>,
+}
+
+/// 代理商切换历史记录
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProviderHistoryEntry {
+    pub timestamp: i64,
+    pub provider_id: String,
+    pub provider_name: String,
+    pub previous_base_url: Option<String>,
+    // 由 select_provider_weighted 选出时记录所属的组
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+}
+
+/// 代理商切换历史文件内容
+#[derive(Debug, Serialize, Deserialize)]
+struct ProviderHistory {
+    max_entries: usize,
+    entries: Vec<ProviderHistoryEntry>,
+}
+
+impl Default for ProviderHistory {
+    fn default() -> Self {
+        Self {
+            max_entries: DEFAULT_HISTORY_MAX_ENTRIES,
+            entries: Vec::new(),
+        }
+    }
+}
+
+// 默认保留的切换历史条数
+const DEFAULT_HISTORY_MAX_ENTRIES: usize = 100;
+
+/// 命名的环境配置：任意一组写入 settings env 的键值（代理、遥测等），不局限于 ANTHROPIC 变量
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ProviderProfile {
+    pub name: String,
+    pub env: HashMap<String, String>,
+}
+
+// 值可能包含密钥，Debug 只输出键名
+impl std::fmt::Debug for ProviderProfile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProviderProfile")
+            .field("name", &self.name)
+            .field("env", &self.env.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// profiles.json 文件内容
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ProfilesFile {
+    profiles: Vec<ProviderProfile>,
+    // 当前应用的配置及其写入的键，下次应用其他配置时据此清理
+    active: Option<String>,
+    #[serde(default)]
+    owned_keys: Vec<String>,
+}
+
+/// 代理商组成员及其权重，权重为 0 的成员不会被选中
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ProviderGroupMember {
+    pub provider_id: String,
+    pub weight: u32,
+}
+
+/// 命名的代理商组：在一组等价的代理商之间按权重轮换
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ProviderGroup {
+    pub name: String,
+    pub members: Vec<ProviderGroupMember>,
+}
+
+/// provider_groups.json 文件内容
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ProviderGroupsFile {
+    groups: Vec<ProviderGroup>,
+}
+
+/// 被跳过的代理商及原因
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SkippedProvider {
+    pub id: String,
+    pub name: Option<String>,
+    pub reason: String,
+}
+
+/// 按备用链切换代理商的结果
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FallbackSwitchResult {
+    pub applied_id: String,
+    pub applied_name: String,
+    pub skipped: Vec<SkippedProvider>,
+    pub message: String,
 }
 
+/// 代理商连接测试的分类结果
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionStatus {
+    Authorized,
+    Unauthorized,
+    Reachable, // 端点可达，但请求被限流或被拒绝，无法确认可用
+    NotFound, // 404/405，通常是 base_url 配置错误
+    UnexpectedStatus,
+    ServerError,
+    DnsFailure,
+    Timeout,
+    Unreachable,
+}
+
+/// 代理商连接测试结果
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProviderConnectionTestResult {
+    pub success: bool,
+    pub status: ConnectionStatus,
+    pub status_code: Option<u16>,
+    pub latency_ms: Option<u64>,
+    pub message: String,
+}
+
+// 使用保持插入顺序的 Map，避免往返读写时打乱用户手动设置的键
 #[derive(Debug, Serialize, Deserialize)]
 struct ClaudeSettings {
-    env: Option<HashMap<String, serde_json::Value>>,
+    env: Option<serde_json::Map<String, serde_json::Value>>,
     #[serde(flatten)]
-    other: HashMap<String, serde_json::Value>,
+    other: serde_json::Map<String, serde_json::Value>,
 }
 
-// 获取 Claude 配置目录路径
-fn get_claude_dir() -> Result<PathBuf, String> {
-    let home_dir = dirs::home_dir()
+// 由代理商配置管理的环境变量
+const PROVIDER_ENV_VARS: &[&str] = &[
+    "ANTHROPIC_API_KEY",
+    "ANTHROPIC_AUTH_TOKEN",
+    "ANTHROPIC_BASE_URL",
+    "ANTHROPIC_MODEL",
+    "ANTHROPIC_SMALL_FAST_MODEL",
+];
+
+// 获取 Claude 配置目录路径（~/.claude，可由 CLAUDE_SUITE_CONFIG_DIR 覆盖）
+pub(crate) fn get_claude_dir() -> Result<PathBuf, String> {
+    let config_dir = paths::claude_dir()
         .ok_or_else(|| i18n::t("provider.home_dir_not_found"))?;
     
-    let config_dir = home_dir.join(".claude");
-    
     // 确保配置目录存在
     if !config_dir.exists() {
         fs::create_dir_all(&config_dir)
@@ -65,6 +297,22 @@ fn get_claude_dir() -> Result<PathBuf, String> {
     Ok(config_dir)
 }
 
+// 在目标文件所在目录写入临时文件，尚未替换目标文件
+fn stage_atomic_write(path: &Path, content: &str) -> std::io::Result<NamedTempFile> {
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let mut temp_file = NamedTempFile::new_in(dir)?;
+    temp_file.write_all(content.as_bytes())?;
+    temp_file.as_file().sync_all()?;
+    Ok(temp_file)
+}
+
+// 原子写入：先写临时文件，再通过 rename 替换目标文件，避免写入中断导致文件损坏
+pub(crate) fn write_file_atomic(path: &Path, content: &str) -> std::io::Result<()> {
+    let temp_file = stage_atomic_write(path, content)?;
+    temp_file.persist(path).map_err(|e| e.error)?;
+    Ok(())
+}
+
 // 获取 Claude Raw Settings 文件路径
 fn get_claude_settings_path() -> Result<PathBuf, String> {
     let claude_dir = get_claude_dir()?;
@@ -78,7 +326,7 @@ fn read_claude_settings() -> Result<ClaudeSettings, String> {
     if !settings_path.exists() {
         return Ok(ClaudeSettings {
             env: None,
-            other: HashMap::new(),
+            other: serde_json::Map::new(),
         });
     }
     
@@ -98,34 +346,155 @@ fn write_claude_settings(settings: &ClaudeSettings) -> Result<(), String> {
     let content = serde_json::to_string_pretty(settings)
         .map_err(|e| i18n::t_with_args("provider.serialize_claude_settings_failed", &[("error", &e.to_string())]))?;
     
-    fs::write(&settings_path, content)
+    // 覆盖前备份现有文件，便于从错误的序列化结果中恢复
+    if settings_path.exists() {
+        fs::copy(&settings_path, settings_path.with_extension("json.bak"))
+            .map_err(|e| i18n::t_with_args("provider.backup_claude_settings_failed", &[("error", &e.to_string())]))?;
+    }
+    
+    write_file_atomic(&settings_path, &content)
         .map_err(|e| i18n::t_with_args("provider.write_claude_settings_failed", &[("error", &e.to_string())]))?;
     
     Ok(())
 }
 
-// 更新 Raw Settings 中的环境变量
-fn update_settings_env(key: &str, value: Option<&str>) -> Result<(), String> {
-    let mut settings = read_claude_settings()?;
-    
-    // 初始化 env 如果不存在
-    if settings.env.is_none() {
-        settings.env = Some(HashMap::new());
+/// settings.json 校验结果
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SettingsValidation {
+    pub exists: bool,
+    pub valid: bool,
+    pub error: Option<String>, // 解析失败的原因（含行列号）
+    pub backup_valid: bool,    // settings.json.bak 存在且可解析
+}
+
+/// repair_settings_json 采取的操作
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SettingsRepairAction {
+    None,           // 文件不存在或本身有效，未做修改
+    RestoredBackup, // 从 settings.json.bak 恢复
+    Salvaged,       // 没有可用备份，保留了能解析出的顶层键
+}
+
+/// settings.json 修复结果
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SettingsRepairReport {
+    pub action: SettingsRepairAction,
+    pub recovered_keys: Vec<String>,
+    pub corrupt_copy_path: Option<String>, // 修复前损坏文件的副本
+}
+
+// 解析失败时返回错误描述
+fn parse_settings_error(content: &str) -> Option<String> {
+    serde_json::from_str::<ClaudeSettings>(content).err().map(|e| e.to_string())
+}
+
+// 读取 settings.json.bak，仅在其可解析时返回内容
+fn read_valid_settings_backup(settings_path: &Path) -> Option<String> {
+    let content = fs::read_to_string(settings_path.with_extension("json.bak")).ok()?;
+    parse_settings_error(&content).is_none().then_some(content)
+}
+
+// 从损坏的 settings.json 中按顺序解析顶层键值，遇到无法解析的位置即停止
+fn salvage_settings_keys(content: &str) -> serde_json::Map<String, serde_json::Value> {
+    let mut salvaged = serde_json::Map::new();
+    let Some(start) = content.find('{') else {
+        return salvaged;
+    };
+    let mut rest = &content[start + 1..];
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+        if !rest.starts_with('"') {
+            break;
+        }
+        let mut keys = serde_json::Deserializer::from_str(rest).into_iter::<String>();
+        let Some(Ok(key)) = keys.next() else { break };
+        let Some(value_start) = rest[keys.byte_offset()..].trim_start().strip_prefix(':') else { break };
+        let mut values = serde_json::Deserializer::from_str(value_start).into_iter::<serde_json::Value>();
+        let Some(Ok(value)) = values.next() else { break };
+        rest = &value_start[values.byte_offset()..];
+        // env 必须是对象，否则所有代理商命令仍会解析失败
+        if key == "env" && !value.is_object() {
+            continue;
+        }
+        salvaged.insert(key, value);
     }
-    
-    let env_vars = settings.env.as_mut().unwrap();
+    salvaged
+}
+
+// 检查 settings.json 能否解析，不修改任何文件
+#[command]
+pub fn validate_settings_json() -> Result<SettingsValidation, String> {
+    let settings_path = get_claude_settings_path()?;
+    let backup_valid = read_valid_settings_backup(&settings_path).is_some();
+    if !settings_path.exists() {
+        return Ok(SettingsValidation { exists: false, valid: true, error: None, backup_valid });
+    }
+    let content = fs::read_to_string(&settings_path)
+        .map_err(|e| i18n::t_with_args("provider.read_claude_settings_failed", &[("error", &e.to_string())]))?;
+    let error = parse_settings_error(&content);
+    Ok(SettingsValidation { exists: true, valid: error.is_none(), error, backup_valid })
+}
+
+// 修复无法解析的 settings.json：优先从 settings.json.bak 恢复，否则保留能解析出的顶层键。
+// 损坏的原文件会先复制一份；无法恢复 env 块时拒绝修复，避免静默丢失环境变量
+#[command]
+pub fn repair_settings_json() -> Result<SettingsRepairReport, String> {
+    let settings_path = get_claude_settings_path()?;
+    let unchanged = SettingsRepairReport { action: SettingsRepairAction::None, recovered_keys: Vec::new(), corrupt_copy_path: None };
+    if !settings_path.exists() {
+        return Ok(unchanged);
+    }
+    let content = fs::read_to_string(&settings_path)
+        .map_err(|e| i18n::t_with_args("provider.read_claude_settings_failed", &[("error", &e.to_string())]))?;
+    if parse_settings_error(&content).is_none() {
+        return Ok(unchanged);
+    }
+
+    let (action, repaired) = match read_valid_settings_backup(&settings_path) {
+        Some(backup) => (SettingsRepairAction::RestoredBackup, serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(&backup).unwrap_or_default()),
+        None => {
+            let salvaged = salvage_settings_keys(&content);
+            if content.contains("\"env\"") && !salvaged.contains_key("env") {
+                return Err(i18n::t("provider.settings_env_unrecoverable"));
+            }
+            (SettingsRepairAction::Salvaged, salvaged)
+        }
+    };
+
+    let corrupt_copy_path = settings_path.with_file_name(format!(
+        "settings.corrupt-{}.json",
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    ));
+    write_file_atomic(&corrupt_copy_path, &content)
+        .map_err(|e| i18n::t_with_args("provider.write_claude_settings_failed", &[("error", &e.to_string())]))?;
+    let repaired_content = serde_json::to_string_pretty(&repaired)
+        .map_err(|e| i18n::t_with_args("provider.serialize_claude_settings_failed", &[("error", &e.to_string())]))?;
+    // 不经过 write_claude_settings：它会用损坏的文件覆盖 .bak
+    write_file_atomic(&settings_path, &repaired_content)
+        .map_err(|e| i18n::t_with_args("provider.write_claude_settings_failed", &[("error", &e.to_string())]))?;
+
+    let recovered_keys: Vec<String> = repaired.keys().cloned().collect();
+    log::info!("Repaired settings.json ({:?}), recovered keys: {:?}", action, recovered_keys);
+    Ok(SettingsRepairReport {
+        action,
+        recovered_keys,
+        corrupt_copy_path: Some(corrupt_copy_path.to_string_lossy().to_string()),
+    })
+}
+
+// 在内存中设置或移除 settings 的环境变量，保持其他键的位置不变
+fn set_settings_env(settings: &mut ClaudeSettings, key: &str, value: Option<&str>) {
+    let env_vars = settings.env.get_or_insert_with(serde_json::Map::new);
     
     match value {
         Some(val) => {
             env_vars.insert(key.to_string(), serde_json::Value::String(val.to_string()));
         }
         None => {
-            env_vars.remove(key);
+            env_vars.shift_remove(key);
         }
     }
-    
-    write_claude_settings(&settings)?;
-    Ok(())
 }
 
 // 从 Raw Settings 中获取环境变量值
@@ -145,8 +514,25 @@ fn get_providers_config_path() -> Result<PathBuf, String> {
     let claude_dir = get_claude_dir()?;
     Ok(claude_dir.join("providers.json"))
 }
+// 进程内串行化 providers.json 的“读取-修改-写入”，避免并发写入互相覆盖
+static PROVIDERS_LOCK: Mutex<()> = Mutex::new(());
+
+// provider_groups.json、profiles.json、provider_history.json 各自的“读取-修改-写入”锁，用法同上
+static PROVIDER_GROUPS_LOCK: Mutex<()> = Mutex::new(());
+static PROFILES_LOCK: Mutex<()> = Mutex::new(());
+static PROVIDER_HISTORY_LOCK: Mutex<()> = Mutex::new(());
+
+// 修改对应文件前获取，持有到保存完成；锁中毒时照常继续，文件本身由原子写入保护
+fn lock_file(lock: &'static Mutex<()>) -> MutexGuard<'static, ()> {
+    lock.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+pub(crate) fn lock_providers() -> MutexGuard<'static, ()> {
+    lock_file(&PROVIDERS_LOCK)
+}
+
 // 从文件加载代理商配置
-fn load_providers_from_file() -> Result<Vec<ProviderConfig>, String> {
+pub(crate) fn load_providers_from_file() -> Result<Vec<ProviderConfig>, String> {
     let config_path = get_providers_config_path()?;
     
     if !config_path.exists() {
@@ -161,25 +547,293 @@ fn load_providers_from_file() -> Result<Vec<ProviderConfig>, String> {
         return Ok(vec![]);
     }
     
-    let providers: Vec<ProviderConfig> = serde_json::from_str(&content)
+    match serde_json::from_str::<Vec<ProviderConfig>>(&content) {
+        Ok(providers) => Ok(providers),
+        Err(e) => {
+            // 旧版本格式：能无损升级时直接使用，有记录无法恢复时仍然报错，避免下次保存时丢失
+            match repair_provider_records(&content) {
+                Ok(repaired) if repaired.dropped.is_empty() => {
+                    log::warn!("providers.json needed migration ({} records); run repair_providers_config to rewrite it", repaired.migrated);
+                    Ok(repaired.providers)
+                }
+                _ => Err(i18n::t_with_args("provider.parse_config_failed", &[("error", &e.to_string())])),
+            }
+        }
+    }
+}
+
+/// providers.json 修复结果
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct ProvidersRepairReport {
+    pub total: usize,
+    pub migrated: usize, // 补全字段、生成 id 等修改后保留的记录
+    pub dropped: usize,  // 无法恢复、移入隔离文件的记录
+    pub quarantine_path: Option<String>,
+}
+
+struct RepairedProviders {
+    providers: Vec<ProviderConfig>,
+    migrated: usize,
+    dropped: Vec<serde_json::Value>,
+}
+
+// 将单条旧格式记录升级为当前结构：接受驼峰字段名，补全可选字段；缺少 base_url 的记录无法恢复
+fn upgrade_provider_record(record: &serde_json::Value) -> Option<ProviderConfig> {
+    let obj = record.as_object()?;
+    let field = |keys: &[&str]| keys.iter().find_map(|key| obj.get(*key)).filter(|v| !v.is_null());
+    let text = |keys: &[&str]| field(keys)
+        .and_then(|v| v.as_str())
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty());
+    
+    let base_url = text(&["base_url", "baseUrl"])?;
+    let id = text(&["id"]).unwrap_or_default();
+    let name = text(&["name"]).unwrap_or_else(|| base_url.clone());
+    let extra_env = field(&["extra_env", "extraEnv"]).and_then(|v| v.as_object()).map(|env| {
+        env.iter()
+            .map(|(key, value)| (key.clone(), value.as_str().map(|s| s.to_string()).unwrap_or_else(|| value.to_string())))
+            .collect::<HashMap<_, _>>()
+    });
+    let fallback_chain = field(&["fallback_chain", "fallbackChain"]).and_then(|v| v.as_array()).map(|ids| {
+        ids.iter().filter_map(|id| id.as_str().map(|s| s.to_string())).collect()
+    });
+    
+    Some(ProviderConfig {
+        id,
+        name,
+        description: text(&["description"]).unwrap_or_default(),
+        base_url,
+        auth_token: text(&["auth_token", "authToken"]),
+        api_key: text(&["api_key", "apiKey"]),
+        model: text(&["model"]),
+        small_fast_model: text(&["small_fast_model", "smallFastModel"]),
+        extra_env,
+        fallback_chain,
+    })
+}
+
+// 宽松解析 providers.json：先解析为 JSON 值，逐条升级，缺失或重复的 id 重新生成
+fn repair_provider_records(content: &str) -> Result<RepairedProviders, String> {
+    let value: serde_json::Value = serde_json::from_str(content)
         .map_err(|e| i18n::t_with_args("provider.parse_config_failed", &[("error", &e.to_string())]))?;
+    let records = match value {
+        serde_json::Value::Array(records) => records,
+        serde_json::Value::Object(mut obj) => match obj.remove("providers") {
+            Some(serde_json::Value::Array(records)) => records,
+            _ => return Err(i18n::t("provider.repair_unsupported_shape")),
+        },
+        _ => return Err(i18n::t("provider.repair_unsupported_shape")),
+    };
+    
+    let mut repaired = RepairedProviders { providers: Vec::new(), migrated: 0, dropped: Vec::new() };
+    for record in records {
+        let Some(mut config) = upgrade_provider_record(&record) else {
+            repaired.dropped.push(record);
+            continue;
+        };
+        if config.id.is_empty() || repaired.providers.iter().any(|p| p.id == config.id) {
+            config.id = format!("migrated-{}", &Uuid::new_v4().simple().to_string()[..8]);
+        }
+        if serde_json::to_value(&config).ok().as_ref() != Some(&record) {
+            repaired.migrated += 1;
+        }
+        repaired.providers.push(config);
+    }
+    Ok(repaired)
+}
+
+// 修复旧版本的 providers.json：升级可恢复的记录并重写文件，无法恢复的记录写入隔离文件
+#[command]
+pub fn repair_providers_config() -> Result<ProvidersRepairReport, String> {
+    let _lock = lock_providers();
+    let config_path = get_providers_config_path()?;
+    if !config_path.exists() {
+        return Ok(ProvidersRepairReport::default());
+    }
+    let content = fs::read_to_string(&config_path)
+        .map_err(|e| i18n::t_with_args("provider.read_config_failed", &[("error", &e.to_string())]))?;
+    if content.trim().is_empty() {
+        return Ok(ProvidersRepairReport::default());
+    }
+    
+    let repaired = repair_provider_records(&content)?;
+    let mut report = ProvidersRepairReport {
+        total: repaired.providers.len() + repaired.dropped.len(),
+        migrated: repaired.migrated,
+        dropped: repaired.dropped.len(),
+        quarantine_path: None,
+    };
+    
+    if !repaired.dropped.is_empty() {
+        let quarantine_path = config_path.with_file_name(format!(
+            "providers.quarantine-{}.json",
+            chrono::Local::now().format("%Y%m%d-%H%M%S")
+        ));
+        let content = serde_json::to_string_pretty(&repaired.dropped)
+            .map_err(|e| i18n::t_with_args("provider.serialize_config_failed", &[("error", &e.to_string())]))?;
+        write_file_atomic(&quarantine_path, &content)
+            .map_err(|e| i18n::t_with_args("provider.write_config_failed", &[("error", &e.to_string())]))?;
+        report.quarantine_path = Some(quarantine_path.to_string_lossy().to_string());
+    }
+    if report.migrated > 0 || report.dropped > 0 {
+        save_providers_to_file(&repaired.providers)?;
+    }
     
-    Ok(providers)
+    log::info!("Repaired providers.json: {} records, {} migrated, {} dropped", report.total, report.migrated, report.dropped);
+    Ok(report)
 }
 
-// 保存代理商配置到文件
-fn save_providers_to_file(providers: &Vec<ProviderConfig>) -> Result<(), String> {
+// 保存代理商配置到文件；调用方需持有 lock_providers，且在加载前获取
+pub(crate) fn save_providers_to_file(providers: &Vec<ProviderConfig>) -> Result<(), String> {
     let config_path = get_providers_config_path()?;
     
     let content = serde_json::to_string_pretty(providers)
         .map_err(|e| i18n::t_with_args("provider.serialize_config_failed", &[("error", &e.to_string())]))?;
     
-    fs::write(&config_path, content)
+    write_file_atomic(&config_path, &content)
         .map_err(|e| i18n::t_with_args("provider.write_config_failed", &[("error", &e.to_string())]))?;
     
     Ok(())
 }
 
+// 获取切换历史文件路径
+fn get_provider_history_path() -> Result<PathBuf, String> {
+    let claude_dir = get_claude_dir()?;
+    Ok(claude_dir.join("provider_history.json"))
+}
+
+// 加载切换历史，文件不存在时返回默认值
+fn load_provider_history() -> Result<ProviderHistory, String> {
+    let history_path = get_provider_history_path()?;
+    
+    if !history_path.exists() {
+        return Ok(ProviderHistory::default());
+    }
+    
+    let content = fs::read_to_string(&history_path)
+        .map_err(|e| i18n::t_with_args("history.read_failed", &[("error", &e.to_string())]))?;
+    
+    if content.trim().is_empty() {
+        return Ok(ProviderHistory::default());
+    }
+    
+    serde_json::from_str(&content)
+        .map_err(|e| i18n::t_with_args("history.parse_failed", &[("error", &e.to_string())]))
+}
+
+// 保存切换历史，超出上限时丢弃最旧的记录
+fn save_provider_history(history: &mut ProviderHistory) -> Result<(), String> {
+    if history.entries.len() > history.max_entries {
+        let overflow = history.entries.len() - history.max_entries;
+        history.entries.drain(..overflow);
+    }
+    
+    let history_path = get_provider_history_path()?;
+    let content = serde_json::to_string_pretty(history)
+        .map_err(|e| i18n::t_with_args("provider.serialize_config_failed", &[("error", &e.to_string())]))?;
+    
+    write_file_atomic(&history_path, &content)
+        .map_err(|e| i18n::t_with_args("history.write_failed", &[("error", &e.to_string())]))
+}
+
+// 记录一次切换，须在修改 env 之前调用以捕获切换前的 API 地址
+fn record_provider_switch(config: &ProviderConfig, group: Option<&str>) -> Result<(), String> {
+    let _lock = lock_file(&PROVIDER_HISTORY_LOCK);
+    let mut history = load_provider_history()?;
+    history.entries.push(ProviderHistoryEntry {
+        timestamp: chrono::Utc::now().timestamp(),
+        provider_id: config.id.clone(),
+        provider_name: config.name.clone(),
+        previous_base_url: get_settings_env("ANTHROPIC_BASE_URL"),
+        group: group.map(|g| g.to_string()),
+    });
+    save_provider_history(&mut history)
+}
+
+// 获取代理商组文件路径
+fn get_provider_groups_path() -> Result<PathBuf, String> {
+    let claude_dir = get_claude_dir()?;
+    Ok(claude_dir.join("provider_groups.json"))
+}
+
+// 加载代理商组，文件不存在时返回默认值
+fn load_provider_groups() -> Result<ProviderGroupsFile, String> {
+    let groups_path = get_provider_groups_path()?;
+    
+    if !groups_path.exists() {
+        return Ok(ProviderGroupsFile::default());
+    }
+    
+    let content = fs::read_to_string(&groups_path)
+        .map_err(|e| i18n::t_with_args("provider.read_config_failed", &[("error", &e.to_string())]))?;
+    
+    if content.trim().is_empty() {
+        return Ok(ProviderGroupsFile::default());
+    }
+    
+    serde_json::from_str(&content)
+        .map_err(|e| i18n::t_with_args("provider.parse_config_failed", &[("error", &e.to_string())]))
+}
+
+// 保存代理商组
+fn save_provider_groups(groups: &ProviderGroupsFile) -> Result<(), String> {
+    let groups_path = get_provider_groups_path()?;
+    let content = serde_json::to_string_pretty(groups)
+        .map_err(|e| i18n::t_with_args("provider.serialize_config_failed", &[("error", &e.to_string())]))?;
+    
+    write_file_atomic(&groups_path, &content)
+        .map_err(|e| i18n::t_with_args("provider.write_config_failed", &[("error", &e.to_string())]))
+}
+
+// 获取环境配置文件路径
+fn get_profiles_path() -> Result<PathBuf, String> {
+    let claude_dir = get_claude_dir()?;
+    Ok(claude_dir.join("profiles.json"))
+}
+
+// 加载环境配置，文件不存在时返回默认值
+fn load_profiles() -> Result<ProfilesFile, String> {
+    let profiles_path = get_profiles_path()?;
+    
+    if !profiles_path.exists() {
+        return Ok(ProfilesFile::default());
+    }
+    
+    let content = fs::read_to_string(&profiles_path)
+        .map_err(|e| i18n::t_with_args("provider.read_config_failed", &[("error", &e.to_string())]))?;
+    
+    if content.trim().is_empty() {
+        return Ok(ProfilesFile::default());
+    }
+    
+    serde_json::from_str(&content)
+        .map_err(|e| i18n::t_with_args("provider.parse_config_failed", &[("error", &e.to_string())]))
+}
+
+// 保存环境配置
+fn save_profiles(profiles: &ProfilesFile) -> Result<(), String> {
+    let profiles_path = get_profiles_path()?;
+    let content = serde_json::to_string_pretty(profiles)
+        .map_err(|e| i18n::t_with_args("provider.serialize_config_failed", &[("error", &e.to_string())]))?;
+    
+    write_file_atomic(&profiles_path, &content)
+        .map_err(|e| i18n::t_with_args("provider.write_config_failed", &[("error", &e.to_string())]))
+}
+
+// 移除上一个配置写入的键，再写入新配置的全部键；返回新配置拥有的键（已排序）
+fn apply_profile_env(settings: &mut ClaudeSettings, profile: &ProviderProfile, previous_keys: &[String]) -> Vec<String> {
+    for key in previous_keys {
+        set_settings_env(settings, key, None);
+    }
+    
+    let mut keys: Vec<String> = profile.env.keys().cloned().collect();
+    keys.sort();
+    for key in &keys {
+        set_settings_env(settings, key, Some(&profile.env[key]));
+    }
+    keys
+}
+
 // CRUD 操作 - 获取所有代理商配置
 #[command]
 pub fn get_provider_presets() -> Result<Vec<ProviderConfig>, String> {
@@ -198,17 +852,93 @@ pub fn get_provider_presets() -> Result<Vec<ProviderConfig>, String> {
     Ok(configs)
 }
 
+// 字段级校验错误，前端据此高亮对应的输入框
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProviderFieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl ProviderFieldError {
+    fn new(field: &str, message: String) -> Self {
+        Self { field: field.to_string(), message }
+    }
+}
+
+// 校验代理商配置；exclude_id 是正在编辑的配置 ID，不参与重复检查
+fn validate_provider(config: &ProviderConfig, providers: &[ProviderConfig], exclude_id: Option<&str>) -> Vec<ProviderFieldError> {
+    let mut errors = Vec::new();
+
+    let id = config.id.as_str();
+    if id.is_empty() {
+        errors.push(ProviderFieldError::new("id", i18n::t("provider.validation_id_empty")));
+    } else if providers.iter().any(|p| p.id == id && Some(p.id.as_str()) != exclude_id) {
+        errors.push(ProviderFieldError::new("id", i18n::t_with_args("provider.id_already_exists", &[("id", id)])));
+    }
+
+    let base_url = config.base_url.as_str();
+    let url_ok = reqwest::Url::parse(base_url)
+        .map(|url| matches!(url.scheme(), "http" | "https") && url.host_str().is_some_and(|host| !host.is_empty()))
+        .unwrap_or(false);
+    if !url_ok {
+        errors.push(ProviderFieldError::new("base_url", i18n::t_with_args("provider.validation_invalid_url", &[("url", base_url)])));
+    }
+
+    let has_credential = [&config.auth_token, &config.api_key]
+        .iter()
+        .any(|value| value.as_deref().is_some_and(|v| !v.trim().is_empty()));
+    if !has_credential {
+        let message = i18n::t("provider.validation_missing_credentials");
+        errors.push(ProviderFieldError::new("auth_token", message.clone()));
+        errors.push(ProviderFieldError::new("api_key", message));
+    }
+
+    errors
+}
+
+// 把校验错误合并为一条消息，供保存类命令返回
+fn reject_invalid(errors: Vec<ProviderFieldError>) -> Result<(), String> {
+    if errors.is_empty() {
+        return Ok(());
+    }
+    let mut messages: Vec<String> = errors.into_iter().map(|e| e.message).collect();
+    messages.dedup();
+    Err(messages.join("; "))
+}
+
+// 保存前的服务端校验；编辑已有配置时传入 original_id，空列表表示通过
+#[command]
+pub fn validate_provider_config(config: ProviderConfig, original_id: Option<String>) -> Result<Vec<ProviderFieldError>, String> {
+    let config = config.trimmed();
+    let providers = load_providers_from_file()?;
+    Ok(validate_provider(&config, &providers, original_id.as_deref()))
+}
+
 #[command]
 pub fn add_provider_config(config: ProviderConfig) -> Result<String, String> {
-    let mut providers = load_providers_from_file()?;
+    insert_provider_config(config).map_err(|e| match e {
+        AddProviderError::DuplicateId(message) | AddProviderError::Other(message) => message,
+    })
+}
+
+// 新增失败的原因；ID 重复单独区分，调用方可据此返回冲突而不是参数错误
+pub(crate) enum AddProviderError {
+    DuplicateId(String),
+    Other(String),
+}
+
+pub(crate) fn insert_provider_config(config: ProviderConfig) -> Result<String, AddProviderError> {
+    let config = config.trimmed();
+    let _lock = lock_providers();
+    let mut providers = load_providers_from_file().map_err(AddProviderError::Other)?;
     
-    // 检查ID是否已存在
     if providers.iter().any(|p| p.id == config.id) {
-        return Err(i18n::t_with_args("provider.id_already_exists", &[("id", &config.id)]));
+        return Err(AddProviderError::DuplicateId(i18n::t_with_args("provider.id_already_exists", &[("id", &config.id)])));
     }
+    reject_invalid(validate_provider(&config, &providers, None)).map_err(AddProviderError::Other)?;
     
     providers.push(config.clone());
-    save_providers_to_file(&providers)?;
+    save_providers_to_file(&providers).map_err(AddProviderError::Other)?;
     
     Ok(i18n::t_with_args("provider.add_success", &[("name", &config.name)]))
 }
@@ -216,11 +946,15 @@ pub fn add_provider_config(config: ProviderConfig) -> Result<String, String> {
 // CRUD 操作 - 更新代理商配置
 #[command]
 pub fn update_provider_config(config: ProviderConfig) -> Result<String, String> {
+    let config = config.trimmed();
+    let _lock = lock_providers();
     let mut providers = load_providers_from_file()?;
     
     let index = providers.iter().position(|p| p.id == config.id)
         .ok_or_else(|| i18n::t_with_args("provider.config_not_found", &[("id", &config.id)]))?;
     
+    reject_invalid(validate_provider(&config, &providers, Some(&config.id)))?;
+    
     providers[index] = config.clone();
     save_providers_to_file(&providers)?;
     
@@ -230,6 +964,7 @@ pub fn update_provider_config(config: ProviderConfig) -> Result<String, String>
 // CRUD 操作 - 删除代理商配置
 #[command]
 pub fn delete_provider_config(id: String) -> Result<String, String> {
+    let _lock = lock_providers();
     let mut providers = load_providers_from_file()?;
     
     let index = providers.iter().position(|p| p.id == id)
@@ -262,98 +997,762 @@ pub fn get_current_provider_config() -> Result<CurrentConfig, String> {
             .or_else(|| env::var("ANTHROPIC_API_KEY").ok()),
         anthropic_model: get_settings_env("ANTHROPIC_MODEL")
             .or_else(|| env::var("ANTHROPIC_MODEL").ok()),
+        anthropic_small_fast_model: get_settings_env("ANTHROPIC_SMALL_FAST_MODEL")
+            .or_else(|| env::var("ANTHROPIC_SMALL_FAST_MODEL").ok()),
     })
 }
 
-#[command]
-pub async fn switch_provider_config(config: ProviderConfig) -> Result<String, String> {
-    // 更新 Raw Settings 中的环境变量
-    update_settings_env("ANTHROPIC_BASE_URL", Some(&config.base_url))?;
+// 将代理商配置应用到 settings 的环境变量
+fn apply_provider_env(settings: &mut ClaudeSettings, config: &ProviderConfig) {
+    set_settings_env(settings, "ANTHROPIC_BASE_URL", Some(&config.base_url));
+    set_settings_env(settings, "ANTHROPIC_AUTH_TOKEN", config.auth_token.as_deref());
+    set_settings_env(settings, "ANTHROPIC_API_KEY", config.api_key.as_deref());
+    set_settings_env(settings, "ANTHROPIC_MODEL", config.model.as_deref());
+    set_settings_env(settings, "ANTHROPIC_SMALL_FAST_MODEL", config.small_fast_model.as_deref());
     
-    if let Some(auth_token) = &config.auth_token {
-        update_settings_env("ANTHROPIC_AUTH_TOKEN", Some(auth_token))?;
-    } else {
-        update_settings_env("ANTHROPIC_AUTH_TOKEN", None)?;
+    // 只写入代理商声明的额外变量，不触碰其他键；排序以保证写入顺序稳定
+    if let Some(extra_env) = &config.extra_env {
+        let mut keys: Vec<&String> = extra_env.keys().collect();
+        keys.sort();
+        for key in keys {
+            set_settings_env(settings, key, Some(&extra_env[key]));
+        }
+    }
+}
+
+// 清理 settings 中由代理商配置管理的环境变量，包括已保存代理商声明的额外变量
+fn clear_provider_env(settings: &mut ClaudeSettings, providers: &[ProviderConfig]) {
+    for var_name in PROVIDER_ENV_VARS {
+        set_settings_env(settings, var_name, None);
     }
     
-    if let Some(api_key) = &config.api_key {
-        update_settings_env("ANTHROPIC_API_KEY", Some(api_key))?;
-    } else {
-        update_settings_env("ANTHROPIC_API_KEY", None)?;
+    for provider in providers {
+        for key in provider.extra_env.iter().flat_map(|env| env.keys()) {
+            set_settings_env(settings, key, None);
+        }
     }
+}
+
+// 将代理商配置写入 Raw Settings 中的环境变量
+fn apply_provider_config(config: &ProviderConfig) -> Result<(), String> {
+    let mut settings = read_claude_settings()?;
+    apply_provider_env(&mut settings, config);
+    write_claude_settings(&settings)
+}
+
+/// 切换前检查模型是否由对应中转站提供：warn 只在结果消息中提示，error 拒绝切换
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelCheckMode {
+    Warn,
+    Error,
+}
+
+// 找到 base_url 指向的中转站并确认其模型列表包含 config.model；
+// 不是中转站、未设置模型或无法获取列表时视为无法校验，返回 None
+async fn unserved_model_message(app: &AppHandle, config: &ProviderConfig) -> Option<String> {
+    let model = config.model.as_deref().filter(|m| !m.is_empty())?;
+    let base_url = config.base_url.trim().trim_end_matches('/');
     
-    if let Some(model) = &config.model {
-        update_settings_env("ANTHROPIC_MODEL", Some(model))?;
+    let station = with_manager(app, |manager| {
+        manager.list_stations().map_err(|e| RelayError::database(e.to_string()))
+    })
+    .ok()?
+    .into_iter()
+    .find(|station| station.api_url.trim_end_matches('/') == base_url)?;
+    
+    let models = match get_station_models(station.id.clone(), app.clone()).await {
+        Ok(models) => models,
+        Err(e) => {
+            log::warn!("Skipping model check for {}: {}", station.name, e);
+            return None;
+        }
+    };
+    
+    if models.iter().any(|m| m == model) {
+        None
     } else {
-        update_settings_env("ANTHROPIC_MODEL", None)?;
+        Some(i18n::t_with_args("provider.model_not_served", &[("model", model), ("station", &station.name)]))
     }
+}
+
+// 记录切换历史并写入 Raw Settings
+fn apply_provider_switch(config: &ProviderConfig) -> Result<String, String> {
+    record_provider_switch(config, None)?;
+    
+    // 更新 Raw Settings 中的环境变量
+    apply_provider_config(config)?;
     
     Ok(i18n::t_with_args("provider.switch_success", &[("name", &config.name), ("description", &config.description)]))
 }
 
+// model_check 为可选项：不传时不做任何网络请求，离线也能切换
 #[command]
-pub async fn clear_provider_config() -> Result<String, String> {
-    // 清理所有 ANTHROPIC 相关环境变量在 Raw Settings 中
-    let vars_to_clear = vec![
-        "ANTHROPIC_API_KEY",
-        "ANTHROPIC_AUTH_TOKEN", 
-        "ANTHROPIC_BASE_URL",
-        "ANTHROPIC_MODEL"
-    ];
+pub async fn switch_provider_config(config: ProviderConfig, model_check: Option<ModelCheckMode>, app: AppHandle) -> Result<String, String> {
+    let warning = match model_check {
+        Some(mode) => match unserved_model_message(&app, &config).await {
+            Some(message) if mode == ModelCheckMode::Error => return Err(message),
+            warning => warning,
+        },
+        None => None,
+    };
     
-    for var_name in &vars_to_clear {
-        update_settings_env(var_name, None)?;
+    let message = apply_provider_switch(&config)?;
+    match warning {
+        Some(warning) => {
+            log::warn!("{}", warning);
+            Ok(format!("{}\n{}", message, warning))
+        }
+        None => Ok(message),
     }
+}
+
+// 代理商连接测试超时时间（秒）
+const PROBE_TIMEOUT_SECS: u64 = 10;
+
+// 连接测试使用的默认模型
+const PROBE_DEFAULT_MODEL: &str = "claude-3-5-haiku-20241022";
+
+// 连接测试共用的 HTTP 客户端，复用连接池
+static PROBE_CLIENT: Lazy<reqwest::Client> = Lazy::new(|| {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(PROBE_TIMEOUT_SECS))
+        .build()
+        .unwrap_or_else(|_| reqwest::Client::new())
+});
+
+// 根据状态码（400 时参考响应体）对连接测试结果分类，只有 2xx 视为成功
+fn classify_probe_response(status_code: u16, body: Option<&serde_json::Value>) -> (ConnectionStatus, &'static str) {
+    // Anthropic 格式的错误响应说明请求到达了真实的 API，只是参数被拒绝
+    let anthropic_error = body.is_some_and(|b| b["type"] == "error" && b["error"]["type"].is_string());
+    match status_code {
+        200..=299 => (ConnectionStatus::Authorized, "provider.connection_authorized"),
+        401 | 403 => (ConnectionStatus::Unauthorized, "provider.connection_unauthorized"),
+        429 => (ConnectionStatus::Reachable, "provider.connection_rate_limited"),
+        400 if anthropic_error => (ConnectionStatus::Reachable, "provider.connection_reachable"),
+        404 | 405 => (ConnectionStatus::NotFound, "provider.connection_not_found"),
+        500..=599 => (ConnectionStatus::ServerError, "provider.connection_server_error"),
+        _ => (ConnectionStatus::UnexpectedStatus, "provider.connection_unexpected_status"),
+    }
+}
+
+// 判断 reqwest 错误是否由 DNS 解析失败引起
+fn is_dns_error(error: &reqwest::Error) -> bool {
+    let mut source: Option<&dyn std::error::Error> = Some(error);
+    while let Some(err) = source {
+        let text = err.to_string().to_lowercase();
+        if text.contains("dns error") || text.contains("failed to lookup address") {
+            return true;
+        }
+        source = err.source();
+    }
+    false
+}
+
+// 向 {base_url}/v1/messages 发送一个最小化的带认证请求并对结果分类
+async fn probe_provider(config: &ProviderConfig) -> ProviderConnectionTestResult {
+    let test_url = format!("{}/v1/messages", config.base_url.trim_end_matches('/'));
+    // 错误信息中如果回显了密钥，替换为脱敏形式
+    let secrets = [config.auth_token.as_deref().unwrap_or(""), config.api_key.as_deref().unwrap_or("")];
     
-    Ok(i18n::t("provider.clear_success"))
+    let request_body = serde_json::json!({
+        "model": config.model.as_deref().unwrap_or(PROBE_DEFAULT_MODEL),
+        "max_tokens": 1,
+        "messages": [{ "role": "user", "content": "ping" }]
+    });
+    
+    let mut request = PROBE_CLIENT
+        .post(&test_url)
+        .header("anthropic-version", "2023-06-01")
+        .header("Content-Type", "application/json")
+        .json(&request_body);
+    
+    if let Some(auth_token) = config.auth_token.as_deref().filter(|s| !s.is_empty()) {
+        request = request.header("Authorization", format!("Bearer {}", auth_token));
+    }
+    if let Some(api_key) = config.api_key.as_deref().filter(|s| !s.is_empty()) {
+        request = request.header("x-api-key", api_key);
+    }
+    
+    let start_time = std::time::Instant::now();
+    match request.send().await {
+        Ok(response) => {
+            let latency_ms = start_time.elapsed().as_millis() as u64;
+            let status_code = response.status().as_u16();
+            let body = if status_code == 400 { response.json::<serde_json::Value>().await.ok() } else { None };
+            let (status, key) = classify_probe_response(status_code, body.as_ref());
+            
+            ProviderConnectionTestResult {
+                success: status == ConnectionStatus::Authorized,
+                status,
+                status_code: Some(status_code),
+                latency_ms: Some(latency_ms),
+                message: i18n::t_with_args(key, &[
+                    ("url", &test_url),
+                    ("status", &status_code.to_string()),
+                    ("latency", &latency_ms.to_string()),
+                ]),
+            }
+        }
+        Err(e) => {
+            let (status, key) = if e.is_timeout() {
+                (ConnectionStatus::Timeout, "provider.connection_timeout")
+            } else if is_dns_error(&e) {
+                (ConnectionStatus::DnsFailure, "provider.connection_dns_failure")
+            } else {
+                (ConnectionStatus::Unreachable, "provider.probe_failed")
+            };
+            
+            ProviderConnectionTestResult {
+                success: false,
+                status,
+                status_code: None,
+                latency_ms: None,
+                message: i18n::t_with_args(key, &[("url", &test_url), ("error", &scrub(&e.to_string(), &secrets))]),
+            }
+        }
+    }
 }
 
-// 检测当前应用的代理商（基于 Raw Settings 中的 API 地址和 Token）
+// 健康检查结果的有效期（秒），期间内加权选择直接采信，不再重复探测
+const PROVIDER_HEALTH_TTL_SECS: u64 = 300;
+
+// 最近一次健康检查的结果，按代理商 ID 记录
+static PROVIDER_HEALTH: Lazy<Mutex<HashMap<String, (Instant, bool)>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+// 探测代理商并记录结果，供加权选择参考
+async fn probe_and_remember(config: &ProviderConfig) -> ProviderConnectionTestResult {
+    let probe = probe_provider(config).await;
+    if let Ok(mut health) = PROVIDER_HEALTH.lock() {
+        health.insert(config.id.clone(), (Instant::now(), probe.success));
+    }
+    probe
+}
+
+// 有效期内最近一次健康检查是否成功；没有记录或已过期时返回 None
+fn recent_provider_health(id: &str) -> Option<bool> {
+    let health = PROVIDER_HEALTH.lock().ok()?;
+    health
+        .get(id)
+        .filter(|(checked_at, _)| checked_at.elapsed() < Duration::from_secs(PROVIDER_HEALTH_TTL_SECS))
+        .map(|(_, healthy)| *healthy)
+}
+
+// 按主代理商及其备用链顺序探测，应用第一个健康的代理商
 #[command]
-pub fn detect_current_provider() -> Result<Option<String>, String> {
-    let settings = read_claude_settings()?;
+pub async fn switch_with_fallback(primary_id: String) -> Result<FallbackSwitchResult, String> {
+    let providers = load_providers_from_file()?;
     
-    if let Some(env_vars) = settings.env {
-        // 检查是否有 ANTHROPIC_BASE_URL 和认证信息
-        let base_url = env_vars.get("ANTHROPIC_BASE_URL")
-            .and_then(|v| v.as_str());
-        let current_auth_token = env_vars.get("ANTHROPIC_AUTH_TOKEN")
-            .and_then(|v| v.as_str())
-            .filter(|s| !s.is_empty());
-        let current_api_key = env_vars.get("ANTHROPIC_API_KEY")
-            .and_then(|v| v.as_str())
-            .filter(|s| !s.is_empty());
+    let primary = providers.iter()
+        .find(|p| p.id == primary_id)
+        .ok_or_else(|| i18n::t_with_args("provider.config_not_found", &[("id", &primary_id)]))?;
+    
+    let mut chain = vec![primary.id.clone()];
+    for id in primary.fallback_chain.iter().flatten() {
+        if !chain.contains(id) {
+            chain.push(id.clone());
+        }
+    }
+    
+    let mut skipped = Vec::new();
+    for id in chain {
+        let candidate = match providers.iter().find(|p| p.id == id) {
+            Some(candidate) => candidate,
+            None => {
+                skipped.push(SkippedProvider {
+                    id: id.clone(),
+                    name: None,
+                    reason: i18n::t_with_args("provider.config_not_found", &[("id", &id)]),
+                });
+                continue;
+            }
+        };
         
-        let has_auth = current_auth_token.is_some() || current_api_key.is_some();
+        let probe = probe_and_remember(candidate).await;
+        if !probe.success {
+            log::warn!("Provider {} failed health probe: {}", candidate.id, probe.message);
+            skipped.push(SkippedProvider {
+                id: candidate.id.clone(),
+                name: Some(candidate.name.clone()),
+                reason: probe.message,
+            });
+            continue;
+        }
         
-        if let Some(url) = base_url {
-            if has_auth {
-                // 尝试匹配已知的代理商配置
-                if let Ok(providers) = load_providers_from_file() {
-                    for provider in providers {
-                        // 检查API地址和认证信息是否都匹配
-                        let base_url_matches = provider.base_url == url;
-                        let auth_matches = 
-                            (provider.auth_token.as_deref() == current_auth_token) ||
-                            (provider.api_key.as_deref() == current_api_key);
-                        
-                        if base_url_matches && auth_matches {
-                            return Ok(Some(provider.id));
-                        }
-                    }
-                }
-                
-                // 如果没有匹配到预设代理商，返回通用描述
-                if url == "https://api.anthropic.com" {
-                    return Ok(Some("official".to_string()));
-                } else {
-                    return Ok(Some("custom".to_string()));
-                }
+        record_provider_switch(candidate, None)?;
+        apply_provider_config(candidate)?;
+        let message = if skipped.is_empty() {
+            i18n::t_with_args("provider.switch_success", &[("name", &candidate.name), ("description", &candidate.description)])
+        } else {
+            i18n::t_with_args("provider.fallback_switch_success", &[("name", &candidate.name), ("count", &skipped.len().to_string())])
+        };
+        return Ok(FallbackSwitchResult {
+            applied_id: candidate.id.clone(),
+            applied_name: candidate.name.clone(),
+            skipped,
+            message,
+        });
+    }
+    
+    Err(i18n::t_with_args("provider.fallback_all_failed", &[("count", &skipped.len().to_string())]))
+}
+
+// 按权重选择下标：roll 对总权重取模后落在哪个成员的区间；总权重为 0 时返回 None
+fn pick_weighted(weights: &[u32], roll: u64) -> Option<usize> {
+    let total: u64 = weights.iter().map(|&w| w as u64).sum();
+    if total == 0 {
+        return None;
+    }
+    let mut target = roll % total;
+    for (index, &weight) in weights.iter().enumerate() {
+        if target < weight as u64 {
+            return Some(index);
+        }
+        target -= weight as u64;
+    }
+    None
+}
+
+// 保存代理商组，同名的组会被覆盖
+#[command]
+pub fn save_provider_group(group: ProviderGroup) -> Result<String, String> {
+    let name = group.name.trim().to_string();
+    if name.is_empty() {
+        return Err(i18n::t("group.name_empty"));
+    }
+    if group.members.iter().all(|m| m.weight == 0) {
+        return Err(i18n::t("group.no_weight"));
+    }
+    
+    let _lock = lock_file(&PROVIDER_GROUPS_LOCK);
+    let mut groups = load_provider_groups()?;
+    let group = ProviderGroup { name: name.clone(), members: group.members };
+    match groups.groups.iter().position(|g| g.name == name) {
+        Some(index) => groups.groups[index] = group,
+        None => groups.groups.push(group),
+    }
+    save_provider_groups(&groups)?;
+    
+    Ok(i18n::t_with_args("group.saved", &[("name", &name)]))
+}
+
+// 获取所有代理商组
+#[command]
+pub fn list_provider_groups() -> Result<Vec<ProviderGroup>, String> {
+    Ok(load_provider_groups()?.groups)
+}
+
+// 删除代理商组
+#[command]
+pub fn delete_provider_group(name: String) -> Result<String, String> {
+    let _lock = lock_file(&PROVIDER_GROUPS_LOCK);
+    let mut groups = load_provider_groups()?;
+    let index = groups.groups.iter()
+        .position(|g| g.name == name)
+        .ok_or_else(|| i18n::t_with_args("group.not_found", &[("name", &name)]))?;
+    
+    groups.groups.remove(index);
+    save_provider_groups(&groups)?;
+    
+    Ok(i18n::t_with_args("group.deleted", &[("name", &name)]))
+}
+
+// 在组内按权重随机选择一个代理商并应用：最近健康检查失败的成员直接跳过，
+// 没有近期记录的成员先探测，失败则从剩余成员中重新选择；选择结果写入切换历史
+#[command]
+pub async fn select_provider_weighted(group: String) -> Result<FallbackSwitchResult, String> {
+    let group = load_provider_groups()?
+        .groups
+        .into_iter()
+        .find(|g| g.name == group)
+        .ok_or_else(|| i18n::t_with_args("group.not_found", &[("name", &group)]))?;
+    let providers = load_providers_from_file()?;
+    
+    let mut skipped = Vec::new();
+    let mut candidates: Vec<(&ProviderConfig, u32)> = Vec::new();
+    for member in group.members.iter().filter(|m| m.weight > 0) {
+        match providers.iter().find(|p| p.id == member.provider_id) {
+            None => skipped.push(SkippedProvider {
+                id: member.provider_id.clone(),
+                name: None,
+                reason: i18n::t_with_args("provider.config_not_found", &[("id", &member.provider_id)]),
+            }),
+            Some(config) if recent_provider_health(&config.id) == Some(false) => skipped.push(SkippedProvider {
+                id: config.id.clone(),
+                name: Some(config.name.clone()),
+                reason: i18n::t("group.recently_unhealthy"),
+            }),
+            Some(config) => candidates.push((config, member.weight)),
+        }
+    }
+    
+    while !candidates.is_empty() {
+        let weights: Vec<u32> = candidates.iter().map(|(_, weight)| *weight).collect();
+        let roll = Uuid::new_v4().as_u128() as u64;
+        let Some(index) = pick_weighted(&weights, roll) else { break };
+        let (candidate, _) = candidates.remove(index);
+        
+        if recent_provider_health(&candidate.id).is_none() {
+            let probe = probe_and_remember(candidate).await;
+            if !probe.success {
+                log::warn!("Provider {} in group {} failed health probe: {}", candidate.id, group.name, probe.message);
+                skipped.push(SkippedProvider {
+                    id: candidate.id.clone(),
+                    name: Some(candidate.name.clone()),
+                    reason: probe.message,
+                });
+                continue;
+            }
+        }
+        
+        record_provider_switch(candidate, Some(&group.name))?;
+        apply_provider_config(candidate)?;
+        return Ok(FallbackSwitchResult {
+            applied_id: candidate.id.clone(),
+            applied_name: candidate.name.clone(),
+            skipped,
+            message: i18n::t_with_args("group.switch_success", &[("name", &candidate.name), ("group", &group.name)]),
+        });
+    }
+    
+    Err(i18n::t_with_args("group.all_failed", &[("group", &group.name)]))
+}
+
+// 获取代理商切换历史，最新的记录在前
+#[command]
+pub fn get_provider_history(limit: Option<usize>) -> Result<Vec<ProviderHistoryEntry>, String> {
+    let history = load_provider_history()?;
+    let limit = limit.unwrap_or(history.entries.len());
+    
+    Ok(history.entries.into_iter().rev().take(limit).collect())
+}
+
+// 设置切换历史保留的最大条数
+#[command]
+pub fn set_provider_history_limit(max_entries: usize) -> Result<(), String> {
+    let _lock = lock_file(&PROVIDER_HISTORY_LOCK);
+    let mut history = load_provider_history()?;
+    history.max_entries = max_entries.max(2);
+    save_provider_history(&mut history)
+}
+
+// 重新应用上一个代理商（历史中倒数第二条记录）
+#[command]
+pub async fn restore_previous_provider() -> Result<String, String> {
+    let history = load_provider_history()?;
+    
+    let previous = history.entries.iter().rev().nth(1)
+        .ok_or_else(|| i18n::t("history.no_previous_provider"))?;
+    
+    let config = load_providers_from_file()?
+        .into_iter()
+        .find(|p| p.id == previous.provider_id)
+        .ok_or_else(|| i18n::t_with_args("provider.config_not_found", &[("id", &previous.provider_id)]))?;
+    
+    apply_provider_switch(&config)
+}
+
+// 保存环境配置，同名配置会被覆盖
+#[command]
+pub fn save_profile(profile: ProviderProfile) -> Result<String, String> {
+    let name = profile.name.trim().to_string();
+    if name.is_empty() {
+        return Err(i18n::t("profile.name_empty"));
+    }
+    
+    let _lock = lock_file(&PROFILES_LOCK);
+    let mut profiles = load_profiles()?;
+    let profile = ProviderProfile { name: name.clone(), env: profile.env };
+    match profiles.profiles.iter().position(|p| p.name == name) {
+        Some(index) => profiles.profiles[index] = profile,
+        None => profiles.profiles.push(profile),
+    }
+    save_profiles(&profiles)?;
+    
+    Ok(i18n::t_with_args("profile.saved", &[("name", &name)]))
+}
+
+// 获取所有环境配置
+#[command]
+pub fn list_profiles() -> Result<Vec<ProviderProfile>, String> {
+    Ok(load_profiles()?.profiles)
+}
+
+// 应用环境配置：先清理上一个配置写入的键，再写入本配置的全部键
+#[command]
+pub fn apply_profile(name: String) -> Result<String, String> {
+    let _lock = lock_file(&PROFILES_LOCK);
+    let mut profiles = load_profiles()?;
+    let profile = profiles.profiles.iter()
+        .find(|p| p.name == name)
+        .cloned()
+        .ok_or_else(|| i18n::t_with_args("profile.not_found", &[("name", &name)]))?;
+    
+    let mut settings = read_claude_settings()?;
+    let owned_keys = apply_profile_env(&mut settings, &profile, &profiles.owned_keys);
+    write_claude_settings(&settings)?;
+    
+    profiles.active = Some(profile.name.clone());
+    profiles.owned_keys = owned_keys;
+    save_profiles(&profiles)?;
+    
+    Ok(i18n::t_with_args("profile.applied", &[("name", &profile.name), ("count", &profile.env.len().to_string())]))
+}
+
+// 删除环境配置；已写入 settings 的键仍被记录，下次应用其他配置时清理
+#[command]
+pub fn delete_profile(name: String) -> Result<String, String> {
+    let _lock = lock_file(&PROFILES_LOCK);
+    let mut profiles = load_profiles()?;
+    let index = profiles.profiles.iter()
+        .position(|p| p.name == name)
+        .ok_or_else(|| i18n::t_with_args("profile.not_found", &[("name", &name)]))?;
+    
+    profiles.profiles.remove(index);
+    save_profiles(&profiles)?;
+    
+    Ok(i18n::t_with_args("profile.deleted", &[("name", &name)]))
+}
+
+/// 清理时会被移除的环境变量（凭据的值已脱敏）
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ClearedEnvVar {
+    pub key: String,
+    pub value: String,
+}
+
+// 列出 clear_provider_env 会移除且当前存在的变量，按 settings 中的顺序
+fn provider_env_to_clear(settings: &ClaudeSettings, providers: &[ProviderConfig]) -> Vec<ClearedEnvVar> {
+    let Some(env_vars) = &settings.env else {
+        return Vec::new();
+    };
+    // 在 env 的副本上模拟清理，原 settings 保持不变
+    let mut cleared = ClaudeSettings { env: Some(env_vars.clone()), other: serde_json::Map::new() };
+    clear_provider_env(&mut cleared, providers);
+    let remaining = cleared.env.unwrap_or_default();
+    
+    env_vars.iter()
+        .filter(|(key, _)| !remaining.contains_key(*key))
+        .map(|(key, value)| {
+            let value = value.as_str().map(|s| s.to_string()).unwrap_or_else(|| value.to_string());
+            let value = if key == "ANTHROPIC_AUTH_TOKEN" || key == "ANTHROPIC_API_KEY" { redact(&value) } else { value };
+            ClearedEnvVar { key: key.clone(), value }
+        })
+        .collect()
+}
+
+// 预览清理结果，不修改 settings.json
+#[command]
+pub fn clear_provider_config_preview() -> Result<Vec<ClearedEnvVar>, String> {
+    let providers = load_providers_from_file()?;
+    let settings = read_claude_settings()?;
+    Ok(provider_env_to_clear(&settings, &providers))
+}
+
+// dry_run 为 true 时只返回将被移除的变量名，不写入文件
+#[command]
+pub async fn clear_provider_config(dry_run: Option<bool>) -> Result<String, String> {
+    // 清理所有 ANTHROPIC 相关环境变量在 Raw Settings 中
+    let providers = load_providers_from_file()?;
+    let mut settings = read_claude_settings()?;
+    
+    if dry_run.unwrap_or(false) {
+        let keys: Vec<String> = provider_env_to_clear(&settings, &providers).into_iter().map(|var| var.key).collect();
+        return Ok(i18n::t_with_args("provider.clear_dry_run", &[("keys", &keys.join(", "))]));
+    }
+    
+    clear_provider_env(&mut settings, &providers);
+    write_claude_settings(&settings)?;
+    
+    Ok(i18n::t("provider.clear_success"))
+}
+
+// Raw Settings 中当前生效的代理商地址与凭据（没有地址或凭据时为 None）
+fn current_provider_settings(settings: &ClaudeSettings) -> Option<ProviderConfig> {
+    let env_vars = settings.env.as_ref()?;
+    let get = |key: &str| env_vars.get(key)
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_string());
+    
+    let base_url = get("ANTHROPIC_BASE_URL")?;
+    let auth_token = get("ANTHROPIC_AUTH_TOKEN");
+    let api_key = get("ANTHROPIC_API_KEY");
+    if auth_token.is_none() && api_key.is_none() {
+        return None;
+    }
+    
+    Some(ProviderConfig {
+        id: String::new(),
+        name: String::new(),
+        description: String::new(),
+        base_url,
+        auth_token,
+        api_key,
+        model: get("ANTHROPIC_MODEL"),
+        small_fast_model: get("ANTHROPIC_SMALL_FAST_MODEL"),
+        extra_env: None,
+        fallback_chain: None,
+    })
+}
+
+// 从任意 settings.json 内容中提取 ANTHROPIC_* 环境变量，构造一个新的代理商配置
+fn provider_from_settings_json(content: &str, name: &str) -> Result<ProviderConfig, String> {
+    let settings: ClaudeSettings = serde_json::from_str(content)
+        .map_err(|e| i18n::t_with_args("provider.import_invalid_settings", &[("error", &e.to_string())]))?;
+    let mut config = current_provider_settings(&settings)
+        .ok_or_else(|| i18n::t("provider.import_no_provider_env"))?;
+
+    // 受管变量以外的 ANTHROPIC_* 值放入 extra_env
+    let extra_env: HashMap<String, String> = settings.env.iter()
+        .flatten()
+        .filter(|(key, _)| key.starts_with("ANTHROPIC_") && !PROVIDER_ENV_VARS.contains(&key.as_str()))
+        .filter_map(|(key, value)| value.as_str().map(|v| (key.clone(), v.to_string())))
+        .collect();
+
+    let name = name.trim();
+    config.id = format!("imported-{}", &Uuid::new_v4().simple().to_string()[..8]);
+    config.name = if name.is_empty() { config.base_url.clone() } else { name.to_string() };
+    config.extra_env = (!extra_env.is_empty()).then_some(extra_env);
+    Ok(config)
+}
+
+// 从同事的 settings.json（文件路径或 JSON 内容）导入一个代理商配置，不会覆盖已有配置
+#[command]
+pub fn import_providers_from_settings_json(path_or_content: String, name: String) -> Result<ProviderConfig, String> {
+    let input = path_or_content.trim();
+    let content = if input.starts_with('{') {
+        input.to_string()
+    } else {
+        fs::read_to_string(input)
+            .map_err(|e| i18n::t_with_args("provider.read_claude_settings_failed", &[("error", &e.to_string())]))?
+    };
+
+    let config = provider_from_settings_json(&content, &name)?.trimmed();
+    let _lock = lock_providers();
+    let mut providers = load_providers_from_file()?;
+    if let Some(existing) = providers.iter().find(|p| {
+        p.base_url == config.base_url
+            && ((config.auth_token.is_some() && p.auth_token == config.auth_token)
+                || (config.api_key.is_some() && p.api_key == config.api_key))
+    }) {
+        return Err(i18n::t_with_args("provider.import_conflict", &[("name", &existing.name)]));
+    }
+    reject_invalid(validate_provider(&config, &providers, None))?;
+
+    providers.push(config.clone());
+    save_providers_to_file(&providers)?;
+    log::info!("Imported provider {} ({}) from settings", config.name, config.id);
+    Ok(config)
+}
+
+// 按 API 地址和认证信息匹配已知代理商，未匹配时返回 official 或 custom
+fn match_current_provider(current: &ProviderConfig) -> String {
+    if let Ok(providers) = load_providers_from_file() {
+        for provider in providers {
+            // 检查API地址和认证信息是否都匹配
+            let base_url_matches = provider.base_url == current.base_url;
+            let auth_matches = 
+                (provider.auth_token == current.auth_token) ||
+                (provider.api_key == current.api_key);
+            
+            if base_url_matches && auth_matches {
+                return provider.id;
             }
         }
     }
     
-    Ok(None)
+    // 如果没有匹配到预设代理商，返回通用描述
+    if current.base_url == "https://api.anthropic.com" {
+        "official".to_string()
+    } else {
+        "custom".to_string()
+    }
+}
+
+// 检测当前应用的代理商（基于 Raw Settings 中的 API 地址和 Token）
+#[command]
+pub fn detect_current_provider() -> Result<Option<String>, String> {
+    let settings = read_claude_settings()?;
+    Ok(current_provider_settings(&settings).map(|current| match_current_provider(&current)))
+}
+
+/// 经过网络验证的当前代理商检测结果
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct VerifiedProviderDetection {
+    pub id: String,
+    pub verified: bool,  // 端点接受了当前凭据
+    pub reachable: bool, // 端点返回了 HTTP 响应（无论是否认证成功）
+    pub status: ConnectionStatus,
+    pub message: String,
+}
+
+// 在字符串匹配的基础上实际请求当前端点，确认地址可达且凭据有效；涉及网络请求，按需调用
+#[command]
+pub async fn detect_current_provider_verified() -> Result<Option<VerifiedProviderDetection>, String> {
+    let settings = read_claude_settings()?;
+    let Some(current) = current_provider_settings(&settings) else {
+        return Ok(None);
+    };
+    
+    let id = match_current_provider(&current);
+    // 探测使用 settings 中实际生效的值，而不是匹配到的预设
+    let probe = probe_provider(&current).await;
+    
+    Ok(Some(VerifiedProviderDetection {
+        id,
+        verified: probe.success,
+        reachable: probe.status_code.is_some(),
+        status: probe.status,
+        message: probe.message,
+    }))
+}
+
+/// settings.json 中某个环境变量与代理商配置的比较结果（凭据已脱敏）
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ProviderEnvDiff {
+    pub key: String,
+    pub current: Option<String>,  // settings.json 中的值
+    pub provider: Option<String>, // 切换到该代理商后会写入的值
+    pub matches: bool,
+}
+
+// 逐个比较代理商管理的变量和它声明的额外变量；除地址和模型外的值都可能是密钥，一律脱敏
+fn diff_provider_env(settings: &ClaudeSettings, config: &ProviderConfig) -> Vec<ProviderEnvDiff> {
+    // 在空 settings 上模拟切换，得到代理商实际会写入的值
+    let mut applied = ClaudeSettings { env: None, other: serde_json::Map::new() };
+    apply_provider_env(&mut applied, config);
+    let applied = applied.env.unwrap_or_default();
+    let current = settings.env.clone().unwrap_or_default();
+    let value_of = |env: &serde_json::Map<String, serde_json::Value>, key: &str| env.get(key)
+        .map(|v| v.as_str().map(|s| s.to_string()).unwrap_or_else(|| v.to_string()))
+        .filter(|s| !s.is_empty());
+    
+    let mut extra_keys: Vec<&String> = config.extra_env.iter().flat_map(|env| env.keys()).collect();
+    extra_keys.sort();
+    let keys = PROVIDER_ENV_VARS.iter().copied().chain(extra_keys.into_iter().map(|k| k.as_str()));
+    
+    keys.map(|key| {
+        let current = value_of(&current, key);
+        let provider = value_of(&applied, key);
+        let matches = current == provider;
+        let mask = |value: Option<String>| {
+            if matches!(key, "ANTHROPIC_BASE_URL" | "ANTHROPIC_MODEL" | "ANTHROPIC_SMALL_FAST_MODEL") {
+                value
+            } else {
+                value.map(|v| redact(&v))
+            }
+        };
+        ProviderEnvDiff { key: key.to_string(), current: mask(current), provider: mask(provider), matches }
+    }).collect()
+}
+
+// 对比 settings.json 当前的环境变量与指定代理商会写入的值，用于排查 detect_current_provider 返回 custom 的原因
+#[command]
+pub fn diff_provider_against_current(provider_id: String) -> Result<Vec<ProviderEnvDiff>, String> {
+    let config = get_provider_config(provider_id)?;
+    let settings = read_claude_settings()?;
+    Ok(diff_provider_env(&settings, &config))
 }
 
 // 检查是否已应用代理商（检查 Raw Settings 中是否有非默认的 API 配置）
@@ -384,18 +1783,10 @@ pub fn is_provider_applied() -> Result<bool, String> {
 }
 
 #[command]
-pub fn test_provider_connection(base_url: String) -> Result<String, String> {
-    // 简单的连接测试 - 尝试访问 API 端点
-    let test_url = if base_url.ends_with('/') {
-        format!("{}v1/messages", base_url)
-    } else {
-        format!("{}/v1/messages", base_url)
-    };
-    
-    // 这里可以实现实际的 HTTP 请求测试
-    // 目前返回一个简单的成功消息
-    Ok(i18n::t_with_args("provider.connection_test_complete", &[("url", &test_url)]))
+pub async fn test_provider_connection(config: ProviderConfig) -> Result<ProviderConnectionTestResult, String> {
+    Ok(probe_and_remember(&config).await)
 }
+
 // async fn terminate_claude_processes(app: &AppHandle) {
 //     log::info!("{}", i18n::t("process.terminating_claude_processes"));
     
@@ -446,4 +1837,557 @@ pub fn test_provider_connection(base_url: String) -> Result<String, String> {
 //     }
     
 //     log::info!("{}", i18n::t("process.termination_complete"));
-// }
\ No newline at end of file
+// }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::paths::with_config_dir;
+    use tempfile::TempDir;
+
+    /// A provider config for tests; `config_bundle` tests reuse it
+    pub(crate) fn test_provider(id: &str, base_url: &str, auth_token: Option<&str>) -> ProviderConfig {
+        ProviderConfig {
+            id: id.to_string(),
+            name: id.to_string(),
+            description: String::new(),
+            base_url: base_url.to_string(),
+            auth_token: auth_token.map(|s| s.to_string()),
+            api_key: None,
+            model: None,
+            small_fast_model: None,
+            extra_env: None,
+            fallback_chain: None,
+        }
+    }
+
+    #[test]
+    fn test_only_2xx_probe_counts_as_authorized() {
+        let status = |code: u16, body: Option<serde_json::Value>| classify_probe_response(code, body.as_ref()).0;
+        assert_eq!(status(200, None), ConnectionStatus::Authorized);
+        assert_eq!(status(401, None), ConnectionStatus::Unauthorized);
+        assert_eq!(status(429, None), ConnectionStatus::Reachable);
+        let anthropic = serde_json::json!({"type": "error", "error": {"type": "invalid_request_error", "message": "bad model"}});
+        assert_eq!(status(400, Some(anthropic)), ConnectionStatus::Reachable);
+        assert_eq!(status(400, None), ConnectionStatus::UnexpectedStatus);
+        // A wrong base_url usually lands on a 404 or a static site's 405
+        assert_eq!(status(404, None), ConnectionStatus::NotFound);
+        assert_eq!(status(405, None), ConnectionStatus::NotFound);
+        assert_eq!(status(302, None), ConnectionStatus::UnexpectedStatus);
+        assert_eq!(status(503, None), ConnectionStatus::ServerError);
+    }
+
+    #[test]
+    fn test_provider_from_settings_json() {
+        let content = r#"{
+            "env": {
+                "ANTHROPIC_BASE_URL": "https://relay.example.com",
+                "ANTHROPIC_AUTH_TOKEN": "sk-shared",
+                "ANTHROPIC_MODEL": "claude-sonnet-4",
+                "ANTHROPIC_CUSTOM_HEADERS": "x-team: infra",
+                "DISABLE_TELEMETRY": "1"
+            },
+            "permissions": {"allow": []}
+        }"#;
+        let config = provider_from_settings_json(content, " Team relay ").unwrap();
+        assert!(config.id.starts_with("imported-"));
+        assert_eq!(config.name, "Team relay");
+        assert_eq!(config.base_url, "https://relay.example.com");
+        assert_eq!(config.auth_token.as_deref(), Some("sk-shared"));
+        assert_eq!(config.model.as_deref(), Some("claude-sonnet-4"));
+        // Only ANTHROPIC_* variables are carried over
+        let extra_env = config.extra_env.unwrap();
+        assert_eq!(extra_env.len(), 1);
+        assert_eq!(extra_env["ANTHROPIC_CUSTOM_HEADERS"], "x-team: infra");
+
+        // Settings without a base URL and credential have nothing to import
+        assert!(provider_from_settings_json(r#"{"env": {"ANTHROPIC_MODEL": "x"}}"#, "x").is_err());
+        assert!(provider_from_settings_json("not json", "x").is_err());
+    }
+
+
+    #[test]
+    fn test_config_dir_override_keeps_crud_out_of_home() {
+        with_config_dir(|config_dir| {
+            assert_eq!(get_claude_dir().unwrap(), config_dir);
+            let mut config = test_provider("portable", "https://portable.example.com", Some("sk-portable"));
+            add_provider_config(config.clone()).unwrap();
+            assert!(config_dir.join("providers.json").exists());
+
+            config.name = "Renamed".to_string();
+            update_provider_config(config.clone()).unwrap();
+            assert_eq!(get_provider_config("portable".to_string()).unwrap().name, "Renamed");
+
+            apply_provider_config(&config).unwrap();
+            let settings = fs::read_to_string(config_dir.join("settings.json")).unwrap();
+            assert!(settings.contains("https://portable.example.com"));
+
+            delete_provider_config("portable".to_string()).unwrap();
+            assert!(load_providers_from_file().unwrap().is_empty());
+        });
+    }
+
+    #[test]
+    fn test_insert_provider_config_reports_duplicate_id() {
+        with_config_dir(|_| {
+            assert!(insert_provider_config(test_provider("dup", "https://dup.example.com", Some("sk-dup"))).is_ok());
+            assert!(matches!(insert_provider_config(test_provider("dup", "https://dup.example.com", Some("sk-dup"))), Err(AddProviderError::DuplicateId(_))));
+
+            let mut invalid = test_provider("fresh", "https://fresh.example.com", Some("sk-fresh"));
+            invalid.base_url = "not a url".to_string();
+            assert!(matches!(insert_provider_config(invalid), Err(AddProviderError::Other(_))));
+        });
+    }
+
+    #[test]
+    fn test_repair_settings_json_prefers_backup_then_salvages() {
+        with_config_dir(|config_dir| {
+            fs::create_dir_all(config_dir).unwrap();
+            let settings_path = config_dir.join("settings.json");
+            assert!(validate_settings_json().unwrap().valid);
+
+            // Truncated mid-write: the keys before the cut survive
+            let truncated = r#"{"env": {"ANTHROPIC_BASE_URL": "https://relay.example.com"}, "model": "opus", "permissions": {"allow": ["#;
+            fs::write(&settings_path, truncated).unwrap();
+            let validation = validate_settings_json().unwrap();
+            assert!(!validation.valid && !validation.backup_valid);
+            assert!(validation.error.is_some());
+
+            let report = repair_settings_json().unwrap();
+            assert_eq!(report.action, SettingsRepairAction::Salvaged);
+            assert_eq!(report.recovered_keys, ["env", "model"]);
+            assert_eq!(fs::read_to_string(report.corrupt_copy_path.unwrap()).unwrap(), truncated);
+            assert_eq!(get_settings_env("ANTHROPIC_BASE_URL").as_deref(), Some("https://relay.example.com"));
+            assert_eq!(repair_settings_json().unwrap().action, SettingsRepairAction::None);
+
+            // A valid backup wins over salvaging
+            fs::write(config_dir.join("settings.json.bak"), r#"{"env": {"ANTHROPIC_MODEL": "backup"}}"#).unwrap();
+            fs::write(&settings_path, "{ not json").unwrap();
+            assert_eq!(repair_settings_json().unwrap().action, SettingsRepairAction::RestoredBackup);
+            assert_eq!(get_settings_env("ANTHROPIC_MODEL").as_deref(), Some("backup"));
+
+            // An env block that can't be recovered is never dropped silently
+            fs::remove_file(config_dir.join("settings.json.bak")).unwrap();
+            let broken_env = r#"{"model": "opus", "env": {"ANTHROPIC_MODEL": "x",,}}"#;
+            fs::write(&settings_path, broken_env).unwrap();
+            assert!(repair_settings_json().is_err());
+            assert_eq!(fs::read_to_string(&settings_path).unwrap(), broken_env);
+        });
+    }
+
+    #[test]
+    fn test_repair_providers_config_migrates_and_quarantines() {
+        with_config_dir(|config_dir| {
+            fs::create_dir_all(config_dir).unwrap();
+            let current = serde_json::to_value(test_provider("current", "https://current.example.com", Some("sk-current"))).unwrap();
+            let records = serde_json::json!([
+                current,
+                {"name": "Legacy", "baseUrl": "https://legacy.example.com", "authToken": "sk-legacy"},
+                {"id": "current", "base_url": "https://dup.example.com"},
+                {"id": "broken", "name": "No URL"},
+                "not a record"
+            ]);
+            fs::write(config_dir.join("providers.json"), records.to_string()).unwrap();
+            // Strict parsing fails and records would be lost, so a plain load still errors
+            assert!(load_providers_from_file().is_err());
+
+            let report = repair_providers_config().unwrap();
+            assert_eq!((report.total, report.migrated, report.dropped), (5, 2, 2));
+            let quarantined: Vec<serde_json::Value> =
+                serde_json::from_str(&fs::read_to_string(report.quarantine_path.unwrap()).unwrap()).unwrap();
+            assert_eq!(quarantined.len(), 2);
+
+            let providers = load_providers_from_file().unwrap();
+            assert_eq!(providers.len(), 3);
+            assert_eq!(providers[0].id, "current");
+            assert_eq!(providers[1].auth_token.as_deref(), Some("sk-legacy"));
+            assert!(providers[1].id.starts_with("migrated-"));
+            assert_ne!(providers[2].id, "current");
+
+            // A clean file needs no further changes
+            assert_eq!(repair_providers_config().unwrap().migrated, 0);
+        });
+    }
+
+    #[test]
+    fn test_concurrent_adds_are_not_lost() {
+        with_config_dir(|_| {
+            let handles: Vec<_> = (0..16)
+                .map(|i| {
+                    let id = format!("p{}", i);
+                    let base_url = format!("https://{}.example.com", id);
+                    std::thread::spawn(move || add_provider_config(test_provider(&id, &base_url, Some("sk-test"))).unwrap())
+                })
+                .collect();
+            for handle in handles {
+                handle.join().unwrap();
+            }
+            let mut ids: Vec<String> = load_providers_from_file().unwrap().into_iter().map(|p| p.id).collect();
+            ids.sort();
+            let mut expected: Vec<String> = (0..16).map(|i| format!("p{}", i)).collect();
+            expected.sort();
+            assert_eq!(ids, expected);
+        });
+    }
+
+    #[test]
+    fn test_concurrent_group_and_profile_saves_are_not_lost() {
+        with_config_dir(|_| {
+            let handles: Vec<_> = (0..8)
+                .map(|i| {
+                    std::thread::spawn(move || {
+                        let members = vec![ProviderGroupMember { provider_id: "p".to_string(), weight: 1 }];
+                        save_provider_group(ProviderGroup { name: format!("g{}", i), members }).unwrap();
+                        save_profile(ProviderProfile { name: format!("e{}", i), env: HashMap::new() }).unwrap();
+                    })
+                })
+                .collect();
+            for handle in handles {
+                handle.join().unwrap();
+            }
+            assert_eq!(load_provider_groups().unwrap().groups.len(), 8);
+            assert_eq!(load_profiles().unwrap().profiles.len(), 8);
+        });
+    }
+
+    #[test]
+    fn test_atomic_write_leaves_original_until_rename() {
+        let temp_dir = TempDir::new().unwrap();
+        let target = temp_dir.path().join("providers.json");
+        fs::write(&target, "[]").unwrap();
+
+        // Serialize a large provider list
+        let providers: Vec<ProviderConfig> = (0..5000)
+            .map(|i| ProviderConfig {
+                id: format!("provider-{}", i),
+                name: format!("Provider {}", i),
+                description: "x".repeat(256),
+                base_url: format!("https://relay-{}.example.com", i),
+                auth_token: Some(format!("sk-{}", i)),
+                api_key: None,
+                model: None,
+                small_fast_model: None,
+                extra_env: None,
+                fallback_chain: None,
+            })
+            .collect();
+        let content = serde_json::to_string_pretty(&providers).unwrap();
+
+        // Staged content must not touch the original file
+        let staged = stage_atomic_write(&target, &content).unwrap();
+        assert_eq!(fs::read_to_string(&target).unwrap(), "[]");
+        assert_eq!(staged.path().parent(), target.parent());
+
+        staged.persist(&target).unwrap();
+        assert_eq!(fs::read_to_string(&target).unwrap(), content);
+
+        // No temporary files are left behind
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_switch_clear_cycle_preserves_unrelated_env() {
+        let original = r#"{
+  "env": {
+    "HTTP_PROXY": "http://127.0.0.1:7890",
+    "CLAUDE_CODE_ENABLE_TELEMETRY": "0",
+    "ANTHROPIC_BASE_URL": "https://old.example.com",
+    "DISABLE_TELEMETRY": "1",
+    "NO_PROXY": "localhost"
+  },
+  "permissions": {
+    "deny": [],
+    "allow": ["Bash(ls:*)"]
+  },
+  "model": "sonnet"
+}"#;
+        let mut settings: ClaudeSettings = serde_json::from_str(original).unwrap();
+
+        let config = ProviderConfig {
+            id: "relay".to_string(),
+            name: "Relay".to_string(),
+            description: "Relay".to_string(),
+            base_url: "https://relay.example.com".to_string(),
+            auth_token: Some("sk-relay".to_string()),
+            api_key: None,
+            model: Some("claude-sonnet-4".to_string()),
+            small_fast_model: None,
+            extra_env: None,
+            fallback_chain: None,
+        };
+        apply_provider_env(&mut settings, &config);
+        clear_provider_env(&mut settings, &[]);
+
+        let round_tripped: serde_json::Value =
+            serde_json::from_str(&serde_json::to_string_pretty(&settings).unwrap()).unwrap();
+        let env = round_tripped["env"].as_object().unwrap();
+        let keys: Vec<&str> = env.keys().map(|k| k.as_str()).collect();
+        assert_eq!(keys, vec!["HTTP_PROXY", "CLAUDE_CODE_ENABLE_TELEMETRY", "DISABLE_TELEMETRY", "NO_PROXY"]);
+
+        let expected: serde_json::Value = serde_json::from_str(original).unwrap();
+        for key in keys {
+            assert_eq!(env[key], expected["env"][key]);
+        }
+
+        // Nested top-level objects keep their key order
+        let permissions: Vec<&str> = round_tripped["permissions"].as_object().unwrap().keys().map(|k| k.as_str()).collect();
+        assert_eq!(permissions, vec!["deny", "allow"]);
+        assert_eq!(round_tripped["model"], "sonnet");
+    }
+
+    #[test]
+    fn test_extra_env_round_trip_and_clear() {
+        let mut extra_env = HashMap::new();
+        extra_env.insert("API_TIMEOUT_MS".to_string(), "600000".to_string());
+        let config = ProviderConfig {
+            id: "relay".to_string(),
+            name: "Relay".to_string(),
+            description: "Relay".to_string(),
+            base_url: "https://relay.example.com".to_string(),
+            auth_token: Some("sk-relay".to_string()),
+            api_key: None,
+            model: None,
+            small_fast_model: Some("claude-3-5-haiku".to_string()),
+            extra_env: Some(extra_env),
+            fallback_chain: None,
+        };
+
+        // extra_env survives providers.json serialization
+        let saved = serde_json::to_string_pretty(&vec![config.clone()]).unwrap();
+        let loaded: Vec<ProviderConfig> = serde_json::from_str(&saved).unwrap();
+        assert_eq!(loaded[0].extra_env, config.extra_env);
+
+        // Older providers.json files without the new fields still load
+        let legacy: Vec<ProviderConfig> = serde_json::from_str(
+            r#"[{"id": "a", "name": "A", "description": "", "base_url": "https://a.example.com"}]"#,
+        ).unwrap();
+        assert!(legacy[0].extra_env.is_none());
+
+        let mut settings: ClaudeSettings = serde_json::from_str(r#"{"env": {"HTTP_PROXY": "a"}}"#).unwrap();
+        apply_provider_env(&mut settings, &config);
+        let env = settings.env.as_ref().unwrap();
+        assert_eq!(env["ANTHROPIC_SMALL_FAST_MODEL"], "claude-3-5-haiku");
+        assert_eq!(env["API_TIMEOUT_MS"], "600000");
+        assert_eq!(env["HTTP_PROXY"], "a");
+
+        clear_provider_env(&mut settings, &loaded);
+        let keys: Vec<&str> = settings.env.as_ref().unwrap().keys().map(|k| k.as_str()).collect();
+        assert_eq!(keys, vec!["HTTP_PROXY"]);
+    }
+
+    #[test]
+    fn test_diff_provider_env_redacts_credentials() {
+        let settings: ClaudeSettings = serde_json::from_str(
+            r#"{"env": {"ANTHROPIC_BASE_URL": "https://relay.example.com", "ANTHROPIC_AUTH_TOKEN": "sk-old-token", "ANTHROPIC_MODEL": "claude-opus-4"}}"#,
+        ).unwrap();
+        let config = ProviderConfig {
+            id: "relay".to_string(),
+            name: "Relay".to_string(),
+            description: "Relay".to_string(),
+            base_url: "https://relay.example.com".to_string(),
+            auth_token: Some("sk-new-token".to_string()),
+            api_key: None,
+            model: None,
+            small_fast_model: None,
+            extra_env: Some(HashMap::from([("API_TIMEOUT_MS".to_string(), "600000".to_string())])),
+            fallback_chain: None,
+        };
+
+        let diff = diff_provider_env(&settings, &config);
+        let entry = |key: &str| diff.iter().find(|d| d.key == key).unwrap();
+        assert!(entry("ANTHROPIC_BASE_URL").matches);
+        assert_eq!(entry("ANTHROPIC_BASE_URL").current.as_deref(), Some("https://relay.example.com"));
+
+        let token = entry("ANTHROPIC_AUTH_TOKEN");
+        assert!(!token.matches);
+        assert_eq!(token.current.as_deref(), Some("********oken"));
+        assert!(!token.provider.as_deref().unwrap().contains("new"));
+
+        let model = entry("ANTHROPIC_MODEL");
+        assert_eq!((model.current.as_deref(), model.provider.as_deref(), model.matches), (Some("claude-opus-4"), None, false));
+        assert!(entry("ANTHROPIC_API_KEY").matches);
+        assert!(!entry("API_TIMEOUT_MS").matches);
+    }
+
+    #[test]
+    fn test_switch_keeps_position_of_existing_key() {
+        let mut settings: ClaudeSettings = serde_json::from_str(
+            r#"{"env": {"HTTP_PROXY": "a", "ANTHROPIC_BASE_URL": "old", "NO_PROXY": "b"}}"#,
+        ).unwrap();
+
+        set_settings_env(&mut settings, "ANTHROPIC_BASE_URL", Some("new"));
+
+        let env = settings.env.unwrap();
+        let keys: Vec<&str> = env.keys().map(|k| k.as_str()).collect();
+        assert_eq!(keys, vec!["HTTP_PROXY", "ANTHROPIC_BASE_URL", "NO_PROXY"]);
+        assert_eq!(env["ANTHROPIC_BASE_URL"], "new");
+    }
+
+    #[test]
+    fn test_write_file_atomic_replaces_content() {
+        let temp_dir = TempDir::new().unwrap();
+        let target = temp_dir.path().join("settings.json");
+        fs::write(&target, "{\"env\": {}}").unwrap();
+
+        write_file_atomic(&target, "{}").unwrap();
+        assert_eq!(fs::read_to_string(&target).unwrap(), "{}");
+    }
+
+    #[test]
+    fn test_provider_config_debug_masks_secrets() {
+        let config = ProviderConfig {
+            id: "relay".to_string(),
+            name: "Relay".to_string(),
+            description: String::new(),
+            base_url: "https://relay.example.com".to_string(),
+            auth_token: Some("sk-auth-secret-9876".to_string()),
+            api_key: Some("sk-api-secret-5432".to_string()),
+            model: None,
+            small_fast_model: None,
+            extra_env: Some(HashMap::from([("CUSTOM_SECRET".to_string(), "hidden-value".to_string())])),
+            fallback_chain: None,
+        };
+
+        let debug = format!("{:?}", config);
+        assert!(!debug.contains("sk-auth-secret-9876"));
+        assert!(!debug.contains("sk-api-secret-5432"));
+        assert!(!debug.contains("hidden-value"));
+        assert!(debug.contains("9876"));
+    }
+
+
+    #[test]
+    fn test_current_provider_settings_requires_url_and_credentials() {
+        let settings: ClaudeSettings = serde_json::from_str(
+            r#"{"env": {"ANTHROPIC_BASE_URL": "https://relay.example.com", "ANTHROPIC_AUTH_TOKEN": "sk-a", "ANTHROPIC_MODEL": "m"}}"#
+        ).unwrap();
+        let current = current_provider_settings(&settings).unwrap();
+        assert_eq!(current.base_url, "https://relay.example.com");
+        assert_eq!(current.auth_token.as_deref(), Some("sk-a"));
+        assert_eq!(current.model.as_deref(), Some("m"));
+
+        let no_auth: ClaudeSettings = serde_json::from_str(
+            r#"{"env": {"ANTHROPIC_BASE_URL": "https://relay.example.com", "ANTHROPIC_API_KEY": ""}}"#
+        ).unwrap();
+        assert!(current_provider_settings(&no_auth).is_none());
+    }
+
+    #[test]
+    fn test_pick_weighted_follows_weights() {
+        let weights = [3, 0, 1];
+        let picks: Vec<Option<usize>> = (0..4).map(|roll| pick_weighted(&weights, roll)).collect();
+        assert_eq!(picks, vec![Some(0), Some(0), Some(0), Some(2)]);
+        assert_eq!(pick_weighted(&weights, 7), Some(2));
+        assert_eq!(pick_weighted(&[0, 0], 1), None);
+        assert_eq!(pick_weighted(&[], 1), None);
+    }
+
+    #[test]
+    fn test_weighted_selection_skips_unhealthy_and_records_group() {
+        with_config_dir(|_| {
+            add_provider_config(test_provider("lb-healthy", "https://lb-healthy.example.com", Some("sk-lb-healthy"))).unwrap();
+            add_provider_config(test_provider("lb-down", "https://lb-down.example.com", Some("sk-lb-down"))).unwrap();
+            save_provider_group(ProviderGroup {
+                name: "pool".to_string(),
+                members: vec![
+                    ProviderGroupMember { provider_id: "lb-down".to_string(), weight: 100 },
+                    ProviderGroupMember { provider_id: "lb-healthy".to_string(), weight: 1 },
+                    ProviderGroupMember { provider_id: "lb-missing".to_string(), weight: 1 },
+                ],
+            })
+            .unwrap();
+            {
+                let mut health = PROVIDER_HEALTH.lock().unwrap();
+                health.insert("lb-down".to_string(), (Instant::now(), false));
+                health.insert("lb-healthy".to_string(), (Instant::now(), true));
+            }
+
+            let runtime = tokio::runtime::Runtime::new().unwrap();
+            let result = runtime.block_on(select_provider_weighted("pool".to_string())).unwrap();
+            assert_eq!(result.applied_id, "lb-healthy");
+            let skipped: Vec<&str> = result.skipped.iter().map(|s| s.id.as_str()).collect();
+            assert_eq!(skipped, vec!["lb-down", "lb-missing"]);
+
+            let history = get_provider_history(Some(1)).unwrap();
+            assert_eq!(history[0].group.as_deref(), Some("pool"));
+            assert!(runtime.block_on(select_provider_weighted("nope".to_string())).is_err());
+        });
+    }
+
+    #[test]
+    fn test_apply_profile_replaces_previous_profile_keys() {
+        let mut settings: ClaudeSettings = serde_json::from_str(r#"{"env": {"USER_VAR": "keep"}}"#).unwrap();
+        let work = ProviderProfile {
+            name: "work".to_string(),
+            env: HashMap::from([
+                ("ANTHROPIC_BASE_URL".to_string(), "https://work.example.com".to_string()),
+                ("HTTPS_PROXY".to_string(), "http://proxy:8080".to_string()),
+            ]),
+        };
+        let home = ProviderProfile {
+            name: "home".to_string(),
+            env: HashMap::from([("DISABLE_TELEMETRY".to_string(), "1".to_string())]),
+        };
+
+        let owned = apply_profile_env(&mut settings, &work, &[]);
+        assert_eq!(owned, vec!["ANTHROPIC_BASE_URL", "HTTPS_PROXY"]);
+
+        let owned = apply_profile_env(&mut settings, &home, &owned);
+        assert_eq!(owned, vec!["DISABLE_TELEMETRY"]);
+        let env = settings.env.unwrap();
+        let keys: Vec<&String> = env.keys().collect();
+        assert_eq!(keys, vec!["USER_VAR", "DISABLE_TELEMETRY"]);
+    }
+
+    #[test]
+    fn test_clear_preview_lists_present_keys_without_modifying() {
+        let settings: ClaudeSettings = serde_json::from_str(
+            r#"{"env": {"HTTP_PROXY": "a", "ANTHROPIC_BASE_URL": "https://relay.example.com", "ANTHROPIC_AUTH_TOKEN": "sk-secret-1234", "EXTRA": "x"}}"#
+        ).unwrap();
+        let mut with_extra = test_provider("relay", "https://relay.example.com", None);
+        with_extra.extra_env = Some(HashMap::from([("EXTRA".to_string(), "x".to_string())]));
+
+        let preview = provider_env_to_clear(&settings, &[with_extra]);
+        let keys: Vec<&str> = preview.iter().map(|var| var.key.as_str()).collect();
+        assert_eq!(keys, vec!["ANTHROPIC_BASE_URL", "ANTHROPIC_AUTH_TOKEN", "EXTRA"]);
+        assert_eq!(preview[0].value, "https://relay.example.com");
+        assert!(!preview[1].value.contains("sk-secret"));
+        assert_eq!(settings.env.unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_validate_provider_reports_field_errors() {
+        let existing = vec![test_provider("relay", "https://relay.example.com", Some("sk-a"))];
+
+        let valid = test_provider("other", "https://other.example.com", Some("sk-b"));
+        assert!(validate_provider(&valid, &existing, None).is_empty());
+
+        let invalid = test_provider("relay", "ftp://relay", Some("  "));
+        let fields: Vec<String> = validate_provider(&invalid, &existing, None).into_iter().map(|e| e.field).collect();
+        assert_eq!(fields, vec!["id", "base_url", "auth_token", "api_key"]);
+
+        // Editing an entry doesn't conflict with its own id
+        let edited = test_provider("relay", "http://localhost:8080", Some("sk-c"));
+        assert!(validate_provider(&edited, &existing, Some("relay")).is_empty());
+        assert_eq!(validate_provider(&test_provider("", "not a url", Some("sk")), &existing, None).len(), 2);
+    }
+
+    #[test]
+    fn test_provider_fields_are_trimmed_before_saving() {
+        with_config_dir(|_| {
+            let mut padded = test_provider("foo", "https://foo.example.com", Some("sk-foo"));
+            padded.id = " foo ".to_string();
+            padded.base_url = " https://foo.example.com ".to_string();
+            padded.model = Some("  ".to_string());
+            add_provider_config(padded).unwrap();
+
+            let saved = load_providers_from_file().unwrap();
+            assert_eq!(saved[0].id, "foo");
+            assert_eq!(saved[0].base_url, "https://foo.example.com");
+            assert_eq!(saved[0].model, None);
+            // The trimmed id now collides with the saved one instead of being stored beside it
+            assert!(add_provider_config(test_provider("foo", "https://foo.example.com", Some("sk-foo"))).is_err());
+            assert_eq!(load_providers_from_file().unwrap().len(), 1);
+        });
+    }
+}
@@ -1,11 +1,12 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use anyhow::{Result, anyhow};
+use std::time::Duration;
+use anyhow::Result;
 
 use crate::commands::relay_stations::{
     RelayStation, RelayStationToken, StationInfo, UserInfo, StationLogEntry, 
     LogPaginationResponse, TokenPaginationResponse, ConnectionTestResult, CreateTokenRequest, UpdateTokenRequest,
-    StationAdapter
+    StationAdapter, StationCapabilities, RelayError, TokenTestResult, GroupInfo
 };
 
 /// Custom adapter implementation - minimal functionality for simple provider configurations
@@ -14,6 +15,11 @@ pub struct CustomAdapter;
 
 #[async_trait::async_trait]
 impl StationAdapter for CustomAdapter {
+    // Only the URL and key are known, so none of the management APIs are available
+    fn capabilities(&self) -> StationCapabilities {
+        StationCapabilities { supports_tokens: false, supports_logs: false, supports_user_info: false, supports_models: false }
+    }
+
     async fn get_station_info(&self, station: &RelayStation) -> Result<StationInfo> {
         // Return minimal station info without making API calls
         Ok(StationInfo {
@@ -28,18 +34,19 @@ impl StationAdapter for CustomAdapter {
                 map
             }),
             quota_per_unit: None,
+            is_secure: station.is_secure(),
         })
     }
 
     async fn get_user_info(&self, _station: &RelayStation, _user_id: &str) -> Result<UserInfo> {
-        Err(anyhow!("User info not available for custom configurations"))
+        Err(RelayError::unsupported("User info not available for custom configurations").into())
     }
 
-    async fn get_logs(&self, _station: &RelayStation, _page: Option<usize>, _page_size: Option<usize>, _filters: Option<serde_json::Value>) -> Result<LogPaginationResponse> {
-        Err(anyhow!("Logs not available for custom configurations"))
+    async fn get_logs(&self, _station: &RelayStation, _page: Option<usize>, _page_size: Option<usize>, _cursor: Option<&str>, _filters: Option<serde_json::Value>) -> Result<LogPaginationResponse> {
+        Err(RelayError::unsupported("Logs not available for custom configurations").into())
     }
 
-    async fn test_connection(&self, _station: &RelayStation) -> Result<ConnectionTestResult> {
+    async fn test_connection(&self, _station: &RelayStation, _timeout: Duration) -> Result<ConnectionTestResult> {
         // For custom adapters, we don't test connections
         Ok(ConnectionTestResult {
             success: true,
@@ -50,27 +57,47 @@ impl StationAdapter for CustomAdapter {
         })
     }
 
+    async fn test_token(&self, _station: &RelayStation, _token_key: &str) -> Result<TokenTestResult> {
+        Err(RelayError::unsupported("Token testing not available for custom configurations").into())
+    }
+
     async fn list_tokens(&self, _station: &RelayStation, _page: Option<usize>, _size: Option<usize>) -> Result<TokenPaginationResponse> {
-        Err(anyhow!("Token management not available for custom configurations"))
+        Err(RelayError::unsupported("Token management not available for custom configurations").into())
     }
 
     async fn create_token(&self, _station: &RelayStation, _token_data: &CreateTokenRequest) -> Result<RelayStationToken> {
-        Err(anyhow!("Token management not available for custom configurations"))
+        Err(RelayError::unsupported("Token management not available for custom configurations").into())
+    }
+
+    fn preview_create_token(&self, _station: &RelayStation, _token_data: &CreateTokenRequest) -> Result<serde_json::Value> {
+        Err(RelayError::unsupported("Token management not available for custom configurations").into())
     }
 
     async fn update_token(&self, _station: &RelayStation, _token_id: &str, _token_data: &UpdateTokenRequest) -> Result<RelayStationToken> {
-        Err(anyhow!("Token management not available for custom configurations"))
+        Err(RelayError::unsupported("Token management not available for custom configurations").into())
     }
 
     async fn delete_token(&self, _station: &RelayStation, _token_id: &str) -> Result<()> {
-        Err(anyhow!("Token management not available for custom configurations"))
+        Err(RelayError::unsupported("Token management not available for custom configurations").into())
     }
 
-    async fn toggle_token(&self, _station: &RelayStation, _token_id: &str, _enabled: bool) -> Result<RelayStationToken> {
-        Err(anyhow!("Token management not available for custom configurations"))
+    async fn set_token_enabled(&self, _station: &RelayStation, _token_id: &str, _enabled: bool) -> Result<RelayStationToken> {
+        Err(RelayError::unsupported("Token management not available for custom configurations").into())
     }
 
     async fn get_user_groups(&self, _station: &RelayStation) -> Result<serde_json::Value> {
-        Err(anyhow!("User groups not available for custom configurations"))
+        Err(RelayError::unsupported("User groups not available for custom configurations").into())
+    }
+
+    async fn get_models(&self, _station: &RelayStation) -> Result<Vec<String>> {
+        Err(RelayError::unsupported("Model listing not available for custom configurations").into())
+    }
+
+    async fn get_station_groups(&self, _station: &RelayStation) -> Result<Vec<GroupInfo>> {
+        Err(RelayError::unsupported("User groups not available for custom configurations").into())
+    }
+
+    async fn validate_system_token(&self, _station: &RelayStation) -> Result<()> {
+        Err(RelayError::unsupported("Token validation not available for custom configurations").into())
     }
 }
\ No newline at end of file
@@ -1,7 +1,13 @@
+pub mod http;
 pub mod newapi;
 pub mod yourapi;
+pub mod oneapi;
 pub mod custom;
+pub mod timing;
+pub mod rate_limit;
+pub mod paging;
 
 pub use newapi::NewApiAdapter;
 pub use yourapi::YourApiAdapter;
+pub use oneapi::OneApiAdapter;
 pub use custom::CustomAdapter;
\ No newline at end of file
@@ -1,65 +1,294 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::time::Duration;
 use anyhow::{Result, anyhow};
 use reqwest;
 use chrono;
 
 use crate::commands::relay_stations::{
-    RelayStation, RelayStationToken, StationInfo, UserInfo, StationLogEntry, 
+    RelayStation, RelayStationAdapter, RelayStationToken, StationInfo, UserInfo, StationLogEntry, 
     LogPaginationResponse, TokenPaginationResponse, ConnectionTestResult, CreateTokenRequest, UpdateTokenRequest,
-    StationAdapter
+    StationAdapter, StationCapabilities, RelayError, TokenTestResult, DailyUsage, aggregate_daily_usage, TokenScope,
+    GroupInfo
 };
 
+use super::http::{self, StationRequestExt};
+use super::paging;
+use crate::i18n;
+use crate::redact::scrub;
+
 /// NewAPI adapter implementation
 pub struct NewApiAdapter;
 
+/// NewAPI's default quota units per dollar, used when a station doesn't report its own
+const DEFAULT_QUOTA_PER_UNIT: f64 = 500000.0;
+
+impl NewApiAdapter {
+    /// Quota units per dollar for a station: `quota_per_unit` from adapter_config if set,
+    /// otherwise the value reported by `/api/status`, otherwise NewAPI's default
+    pub async fn quota_per_unit(&self, station: &RelayStation) -> f64 {
+        let configured = station
+            .adapter_config
+            .as_ref()
+            .and_then(|config| config.get("quota_per_unit"))
+            .and_then(|v| v.as_f64())
+            .filter(|&q| q > 0.0);
+        if let Some(quota_per_unit) = configured {
+            return quota_per_unit;
+        }
+
+        match self.get_station_info(station).await {
+            Ok(info) => info.quota_per_unit.filter(|&q| q > 0).map(|q| q as f64).unwrap_or(DEFAULT_QUOTA_PER_UNIT),
+            Err(_) => DEFAULT_QUOTA_PER_UNIT,
+        }
+    }
+}
+
+/// Resolves the `New-API-User` header value for a station.
+///
+/// NewAPI stations must set `user_id`: guessing one returns another user's data (or a 403) on
+/// multi-user gateways. Single-user setups can opt into a fallback with `default_user_id` in
+/// adapter_config. Other adapters served by this implementation keep the historical default of "1".
+pub(super) fn station_user_id(station: &RelayStation) -> Result<&str> {
+    if let Some(user_id) = station.user_id.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+        return Ok(user_id);
+    }
+    if let Some(user_id) = config_str(station, "default_user_id").map(str::trim).filter(|s| !s.is_empty()) {
+        return Ok(user_id);
+    }
+    match station.adapter {
+        RelayStationAdapter::Newapi => Err(RelayError::invalid_input(format!(
+            "Station {} requires a user_id (or set default_user_id in its adapter config)",
+            station.name
+        ))
+        .into()),
+        _ => Ok("1"),
+    }
+}
+
+/// Reads a string setting from the station's adapter_config
+fn config_str<'a>(station: &'a RelayStation, key: &str) -> Option<&'a str> {
+    station
+        .adapter_config
+        .as_ref()
+        .and_then(|config| config.get(key))
+        .and_then(|v| v.as_str())
+}
+
+/// Liveness probes, cheapest first. `/api/status` returns a sizeable payload, so it's the fallback
+/// for stations that answer the lighter probes with 404 or 405.
+pub const HEALTH_PROBES: [&str; 3] = ["HEAD /api/status", "GET /api/", "GET /api/status"];
+
+/// adapter_config key remembering which of `HEALTH_PROBES` a station answers
+pub const HEALTH_PROBE_KEY: &str = "health_probe";
+
+/// `remain_quota` for new tokens when neither the request nor `default_remain_quota` sets one
+const DEFAULT_REMAIN_QUOTA: i64 = 500000;
+
+/// Probes to try for a station: the remembered one first, then the rest in order
+fn health_probe_order(station: &RelayStation) -> Vec<&'static str> {
+    let stored = config_str(station, HEALTH_PROBE_KEY).and_then(|p| HEALTH_PROBES.iter().copied().find(|probe| *probe == p));
+    stored.into_iter().chain(HEALTH_PROBES.iter().copied().filter(|probe| Some(*probe) != stored)).collect()
+}
+
+/// Builds the JSON body POSTed to `/api/token/`, filling unset fields with the station's defaults.
+/// `group` falls back to `default_token_group` (empty means the user's own group on the station)
+/// and `remain_quota` to `default_remain_quota` (500000 when unset).
+pub fn build_create_token_body(station: &RelayStation, token_data: &CreateTokenRequest) -> serde_json::Value {
+    let default_group = config_str(station, "default_token_group").unwrap_or("");
+    let default_remain_quota = station
+        .adapter_config
+        .as_ref()
+        .and_then(|config| config.get("default_remain_quota"))
+        .and_then(|v| v.as_i64())
+        .unwrap_or(DEFAULT_REMAIN_QUOTA);
+
+    let (model_limits_enabled, model_limits) = model_limits_fields(
+        token_data.model_limits_list.as_deref(),
+        token_data.model_limits.as_deref(),
+        token_data.model_limits_enabled,
+    );
+
+    serde_json::json!({
+        "name": token_data.name,
+        "remain_quota": token_data.remain_quota.unwrap_or(default_remain_quota),
+        "expired_time": token_data.expired_time.unwrap_or(-1),
+        "unlimited_quota": token_data.unlimited_quota.unwrap_or(true),
+        "model_limits_enabled": model_limits_enabled.unwrap_or(false),
+        "model_limits": model_limits.unwrap_or_default(),
+        "group": token_data.group.as_deref().unwrap_or(default_group),
+        "allow_ips": token_data.allow_ips.as_deref().unwrap_or("")
+    })
+}
+
+/// Resolves the `model_limits_enabled`/`model_limits` pair NewAPI expects.
+///
+/// A model list wins over the raw comma-joined string: it's trimmed, deduplicated and joined
+/// with commas, and a non-empty list turns `model_limits_enabled` on.
+pub fn model_limits_fields(list: Option<&[String]>, raw: Option<&str>, enabled: Option<bool>) -> (Option<bool>, Option<String>) {
+    let Some(list) = list else {
+        return (enabled, raw.map(|s| s.to_string()));
+    };
+    let mut models: Vec<&str> = Vec::new();
+    for model in list.iter().map(|m| m.trim()).filter(|m| !m.is_empty()) {
+        if !models.contains(&model) {
+            models.push(model);
+        }
+    }
+    let enabled = if models.is_empty() { enabled } else { Some(true) };
+    (enabled, Some(models.join(",")))
+}
+
+/// Extracts model ids from a models response, accepting both `{"data": [...]}` and a bare
+/// array, where each entry is either a string or an object with an `id`
+fn parse_model_list(data: &serde_json::Value) -> Option<Vec<String>> {
+    let entries = data.get("data").unwrap_or(data).as_array()?;
+    Some(
+        entries
+            .iter()
+            .filter_map(|entry| entry.as_str().or_else(|| entry.get("id").and_then(|v| v.as_str())))
+            .map(|s| s.to_string())
+            .collect(),
+    )
+}
+
+/// Parses `/api/user/self/groups`: an object of `{"<group>": {"ratio": .., "desc": ..}}` on NewAPI,
+/// or a bare list of group names on older deployments
+fn parse_group_list(data: &serde_json::Value) -> Option<Vec<GroupInfo>> {
+    let entries = data.get("data").unwrap_or(data);
+    let mut groups: Vec<GroupInfo> = if let Some(object) = entries.as_object() {
+        object
+            .iter()
+            .map(|(name, group)| GroupInfo {
+                name: name.clone(),
+                ratio: group["ratio"].as_f64().or_else(|| group.as_f64()).unwrap_or(1.0),
+                description: group["desc"].as_str().filter(|d| !d.is_empty()).map(|d| d.to_string()),
+            })
+            .collect()
+    } else {
+        entries
+            .as_array()?
+            .iter()
+            .filter_map(|name| name.as_str())
+            .map(|name| GroupInfo { name: name.to_string(), ratio: 1.0, description: None })
+            .collect()
+    };
+    groups.sort_by(|a, b| a.name.cmp(&b.name));
+    Some(groups)
+}
+
+/// Endpoints tried by `test_token`, in order. `/api/usage/token` reports the token's quota;
+/// stations that predate it fall back to listing models.
+const TOKEN_TEST_PATHS: [&str; 2] = ["/api/usage/token", "/v1/models"];
+
+/// Interprets a `test_token` response; quota fields come from `/api/usage/token`
+fn token_test_result(status: reqwest::StatusCode, body: Option<&serde_json::Value>, response_time: u64) -> TokenTestResult {
+    let rejected = body.is_some_and(|b| b["success"].as_bool() == Some(false) || b["code"].as_bool() == Some(false));
+    let valid = status.is_success() && !rejected;
+    let message = if valid {
+        "Token accepted".to_string()
+    } else {
+        body.and_then(|b| b["message"].as_str().or_else(|| b["error"]["message"].as_str()))
+            .map(|m| m.to_string())
+            .unwrap_or_else(|| format!("HTTP {}", status.as_u16()))
+    };
+    let data = body.map(|b| &b["data"]);
+    TokenTestResult {
+        valid,
+        response_time: Some(response_time),
+        status_code: Some(status.as_u16()),
+        message,
+        remain_quota: data.and_then(|d| d["total_available"].as_i64()).filter(|_| valid),
+        unlimited_quota: data.and_then(|d| d["unlimited_quota"].as_bool()).filter(|_| valid),
+    }
+}
+
+/// Builds a log entry's summary line in the current UI language
+fn log_message(log_obj: &serde_json::Map<String, serde_json::Value>) -> String {
+    let int = |key: &str| log_obj.get(key).and_then(|v| v.as_i64()).unwrap_or(0).to_string();
+    let model = log_obj.get("model_name").and_then(|v| v.as_str()).unwrap_or("unknown");
+    i18n::t_with_args(
+        "relay.log_message",
+        &[
+            ("model", model),
+            ("prompt_tokens", &int("prompt_tokens")),
+            ("completion_tokens", &int("completion_tokens")),
+            ("quota", &int("quota")),
+        ],
+    )
+}
+
+/// Classifies the response to the admin-only user list. NewAPI answers 200 with `success: false`
+/// when the token's role is too low, and 401 when the token isn't accepted at all.
+fn token_scope_from_response(status: u16, body: Option<&serde_json::Value>) -> TokenScope {
+    match status {
+        200..=299 if body.is_some_and(|b| b["success"].as_bool() == Some(true)) => TokenScope::Admin,
+        200..=299 if body.is_some_and(|b| b["success"].as_bool() == Some(false)) => TokenScope::User,
+        403 => TokenScope::User,
+        _ => TokenScope::Unknown,
+    }
+}
+
+/// Maps `/api/data/self` rows (hourly, per model) into per-day totals
+fn parse_usage_stats(data: &serde_json::Value) -> Option<Vec<DailyUsage>> {
+    let rows = data["data"].as_array()?;
+    Some(aggregate_daily_usage(rows.iter().map(|row| {
+        (
+            row["created_at"].as_i64().unwrap_or(0),
+            row["model_name"].as_str().unwrap_or_default().to_string(),
+            row["count"].as_i64().unwrap_or(0),
+            row["token_used"].as_i64().unwrap_or(0),
+            row["quota"].as_i64().unwrap_or(0),
+        )
+    })))
+}
+
 #[async_trait::async_trait]
 impl StationAdapter for NewApiAdapter {
+    fn capabilities(&self) -> StationCapabilities {
+        StationCapabilities { supports_tokens: true, supports_logs: true, supports_user_info: true, supports_models: true }
+    }
+
     async fn get_station_info(&self, station: &RelayStation) -> Result<StationInfo> {
-        let client = reqwest::Client::new();
-        let user_id = station.user_id.as_deref().unwrap_or("1"); // Default to "1" if no user_id configured
-        let response = client
-            .get(&format!("{}/api/status", station.api_url))
-            .header("New-API-User", user_id)
-            .send()
-            .await?;
+        let client = http::client_for(station)?;
+        let user_id = station_user_id(station)?;
+        let url = format!("{}/api/status", station.api_url);
+        let request = client.get(&url).header("New-API-User", user_id);
 
-        if response.status().is_success() {
-            let data: serde_json::Value = response.json().await?;
-            let data_obj = data["data"].as_object().ok_or_else(|| anyhow!("Invalid response format"))?;
-            
-            Ok(StationInfo {
-                name: data_obj.get("system_name")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or(&station.name)
-                    .to_string(),
-                announcement: data_obj.get("announcements")
-                    .and_then(|v| v.as_array())
-                    .and_then(|arr| arr.first())
-                    .and_then(|first| first.get("content"))
-                    .and_then(|v| v.as_str())
-                    .map(|s| s.to_string()),
-                api_url: station.api_url.clone(),
-                version: data_obj.get("version")
-                    .and_then(|v| v.as_str())
-                    .map(|s| s.to_string()),
-                quota_per_unit: data_obj.get("quota_per_unit")
-                    .and_then(|v| v.as_i64()),
-                metadata: Some({
-                    let mut map = HashMap::new();
-                    map.insert("response".to_string(), data["data"].clone());
-                    map
-                }),
-            })
-        } else {
-            Err(anyhow!("Failed to get station info: {}", response.status()))
-        }
+        // Polled often, so revalidate with ETag/Last-Modified when the station supports it
+        let data = http::get_json_conditional(request, station, &url, "Failed to get station info").await?;
+        let data_obj = data["data"].as_object().ok_or_else(|| anyhow!("Invalid response format"))?;
+
+        Ok(StationInfo {
+            name: data_obj.get("system_name")
+                .and_then(|v| v.as_str())
+                .unwrap_or(&station.name)
+                .to_string(),
+            announcement: data_obj.get("announcements")
+                .and_then(|v| v.as_array())
+                .and_then(|arr| arr.first())
+                .and_then(|first| first.get("content"))
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string()),
+            api_url: station.api_url.clone(),
+            version: data_obj.get("version")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string()),
+            quota_per_unit: data_obj.get("quota_per_unit")
+                .and_then(|v| v.as_i64()),
+            metadata: Some({
+                let mut map = HashMap::new();
+                map.insert("response".to_string(), data["data"].clone());
+                map
+            }),
+            is_secure: station.is_secure(),
+        })
     }
 
     async fn get_user_info(&self, station: &RelayStation, user_id: &str) -> Result<UserInfo> {
-        let client = reqwest::Client::new();
+        let client = http::client_for(station)?;
         let actual_user_id = if user_id.is_empty() {
-            station.user_id.as_deref().unwrap_or("1")
+            station_user_id(station)?
         } else {
             user_id
         };
@@ -68,12 +297,13 @@ impl StationAdapter for NewApiAdapter {
             .get(&format!("{}/api/user/self", station.api_url))
             .header("Authorization", &format!("Bearer {}", station.system_token))
             .header("New-API-User", actual_user_id)
-            .send()
+            .send_for(station)
             .await?;
 
         if response.status().is_success() {
-            let data: serde_json::Value = response.json().await?;
+            let data = http::json_body(response).await?;
             let user_data = data["data"].as_object().ok_or_else(|| anyhow!("Invalid response format"))?;
+            let quota_per_unit = self.quota_per_unit(station).await;
             
             Ok(UserInfo {
                 user_id: user_data.get("id")
@@ -89,10 +319,10 @@ impl StationAdapter for NewApiAdapter {
                     .map(|s| s.to_string()),
                 balance_remaining: user_data.get("quota")
                     .and_then(|v| v.as_i64())
-                    .map(|q| q as f64 / 500000.0), // Convert to dollars (quota_per_unit from status)
+                    .map(|q| q as f64 / quota_per_unit), // Convert to dollars
                 amount_used: user_data.get("used_quota")
                     .and_then(|v| v.as_i64())
-                    .map(|q| q as f64 / 500000.0), // Convert to dollars
+                    .map(|q| q as f64 / quota_per_unit), // Convert to dollars
                 request_count: user_data.get("request_count")
                     .and_then(|v| v.as_i64()),
                 status: match user_data.get("status").and_then(|v| v.as_i64()) {
@@ -103,25 +333,35 @@ impl StationAdapter for NewApiAdapter {
                 metadata: Some({
                     let mut map = HashMap::new();
                     map.insert("response".to_string(), data["data"].clone());
+                    map.insert("quota_per_unit".to_string(), serde_json::json!(quota_per_unit));
                     map
                 }),
             })
         } else {
-            Err(anyhow!("Failed to get user info: {}", response.status()))
+            Err(RelayError::upstream(response.status(), "Failed to get user info").into())
         }
     }
 
-    async fn get_logs(&self, station: &RelayStation, page: Option<usize>, page_size: Option<usize>, filters: Option<serde_json::Value>) -> Result<LogPaginationResponse> {
-        let client = reqwest::Client::new();
+    async fn get_logs(&self, station: &RelayStation, page: Option<usize>, page_size: Option<usize>, cursor: Option<&str>, filters: Option<serde_json::Value>) -> Result<LogPaginationResponse> {
+        let client = http::client_for(station)?;
         let page = page.unwrap_or(1);
-        let page_size = page_size.unwrap_or(10);
-        let user_id = station.user_id.as_deref().unwrap_or("1");
+        let page_size = paging::page_size(page_size);
+        let user_id = station_user_id(station)?;
+
+        // Deployments with keyset paging name their "logs older than this id" parameter in
+        // `log_cursor_param`; everything else uses offset paging
+        let cursor_param = config_str(station, "log_cursor_param");
+        let position = match (cursor_param, cursor) {
+            (Some(param), Some(cursor)) => format!("{}={}", param, urlencoding::encode(cursor)),
+            _ => format!("p={}", page),
+        };
         
         // Parse filters if provided
         let mut start_timestamp = 0i64;
         let mut end_timestamp = chrono::Utc::now().timestamp();
         let mut model_name = String::new();
         let mut group = String::new();
+        let mut token_name = String::new();
         
         if let Some(filters_obj) = filters {
             if let Some(start_time) = filters_obj.get("startTime").and_then(|v| v.as_str()) {
@@ -147,13 +387,18 @@ impl StationAdapter for NewApiAdapter {
             if let Some(g) = filters_obj.get("group").and_then(|v| v.as_str()) {
                 group = g.to_string();
             }
+            
+            if let Some(name) = filters_obj.get("tokenName").and_then(|v| v.as_str()) {
+                token_name = name.to_string();
+            }
         }
         
         let url = format!(
-            "{}/api/log/self?p={}&page_size={}&type=0&token_name=&model_name={}&start_timestamp={}&end_timestamp={}&group={}",
+            "{}/api/log/self?{}&page_size={}&type=0&token_name={}&model_name={}&start_timestamp={}&end_timestamp={}&group={}",
             station.api_url,
-            page,
+            position,
             page_size,
+            urlencoding::encode(&token_name),
             urlencoding::encode(&model_name),
             start_timestamp,
             end_timestamp,
@@ -164,11 +409,11 @@ impl StationAdapter for NewApiAdapter {
             .get(&url)
             .header("Authorization", &format!("Bearer {}", station.system_token))
             .header("New-API-User", user_id)
-            .send()
+            .send_for(station)
             .await?;
 
         if response.status().is_success() {
-            let data: serde_json::Value = response.json().await?;
+            let data = http::json_body(response).await?;
             let log_data = data["data"].as_object().ok_or_else(|| anyhow!("Invalid response format"))?;
             let empty_vec = vec![];
             let logs = log_data.get("items").and_then(|v| v.as_array()).unwrap_or(&empty_vec);
@@ -198,13 +443,7 @@ impl StationAdapter for NewApiAdapter {
                         Some(4) => "error".to_string(),
                         _ => "info".to_string(),
                     },
-                    message: format!(
-                        "API调用 - 模型: {} | 提示: {} | 补全: {} | 花费: {}",
-                        log_obj.get("model_name").and_then(|v| v.as_str()).unwrap_or("unknown"),
-                        log_obj.get("prompt_tokens").and_then(|v| v.as_i64()).unwrap_or(0),
-                        log_obj.get("completion_tokens").and_then(|v| v.as_i64()).unwrap_or(0),
-                        log_obj.get("quota").and_then(|v| v.as_i64()).unwrap_or(0)
-                    ),
+                    message: log_message(log_obj),
                     user_id: log_obj.get("user_id")
                         .and_then(|v| v.as_i64())
                         .map(|id| id.to_string()),
@@ -234,33 +473,61 @@ impl StationAdapter for NewApiAdapter {
                         map
                     }),
                 }
-            }).collect();
+            }).collect::<Vec<_>>();
+
+            // A full page means there may be older entries to continue from
+            let next_cursor = match cursor_param {
+                Some(_) if items.len() >= page_size => items.last().map(|entry| entry.id.clone()),
+                _ => None,
+            };
 
             Ok(LogPaginationResponse {
                 items,
                 page,
                 page_size,
                 total: log_data.get("total").and_then(|v| v.as_i64()).unwrap_or(0),
+                next_cursor,
             })
         } else {
-            Err(anyhow!("Failed to get logs: {}", response.status()))
+            Err(RelayError::upstream(response.status(), "Failed to get logs").into())
         }
     }
 
-    async fn test_connection(&self, station: &RelayStation) -> Result<ConnectionTestResult> {
-        let start_time = std::time::Instant::now();
-        let client = reqwest::Client::new();
-        let user_id = station.user_id.as_deref().unwrap_or("1");
-        
-        match client
-            .get(&format!("{}/api/status", station.api_url))
-            .header("New-API-User", user_id)
-            .timeout(std::time::Duration::from_secs(10))
-            .send()
-            .await
-        {
-            Ok(response) => {
+    async fn test_connection(&self, station: &RelayStation, timeout: Duration) -> Result<ConnectionTestResult> {
+        let client = http::client_for(station)?;
+
+        let probes = async {
+            let order = health_probe_order(station);
+            let mut outcome = None;
+            for (index, probe) in order.iter().enumerate() {
+                let (method, path) = probe.split_once(' ').unwrap_or(("GET", probe));
+                let method = if method == "HEAD" { reqwest::Method::HEAD } else { reqwest::Method::GET };
+                // The probe paths are public, so a missing user_id shouldn't fail the connectivity check
+                let mut request = client.request(method, &format!("{}{}", station.api_url, path));
+                if let Ok(user_id) = station_user_id(station) {
+                    request = request.header("New-API-User", user_id);
+                }
+
+                let start_time = std::time::Instant::now();
+                let result = request.send_with_timeout(station, timeout).await;
                 let response_time = start_time.elapsed().as_millis() as u64;
+                let unsupported = matches!(&result, Ok(response) if matches!(response.status().as_u16(), 404 | 405));
+                outcome = Some((*probe, result, response_time));
+                if !unsupported || index + 1 == order.len() {
+                    break;
+                }
+            }
+            outcome.expect("HEALTH_PROBES is not empty")
+        };
+
+        let (probe, result, response_time) = probes.await;
+        let details = HashMap::from([
+            ("probe".to_string(), serde_json::Value::from(probe)),
+            ("timeout_ms".to_string(), (timeout.as_millis() as u64).into()),
+        ]);
+
+        match result {
+            Ok(response) => {
                 let status_code = response.status().as_u16();
                 
                 if response.status().is_success() {
@@ -269,7 +536,7 @@ impl StationAdapter for NewApiAdapter {
                         response_time: Some(response_time),
                         message: "Connection successful".to_string(),
                         status_code: Some(status_code),
-                        details: None,
+                        details: Some(details),
                     })
                 } else {
                     Ok(ConnectionTestResult {
@@ -277,7 +544,7 @@ impl StationAdapter for NewApiAdapter {
                         response_time: Some(response_time),
                         message: format!("HTTP {}", status_code),
                         status_code: Some(status_code),
-                        details: None,
+                        details: Some(details),
                     })
                 }
             }
@@ -287,17 +554,59 @@ impl StationAdapter for NewApiAdapter {
                     response_time: None,
                     message: format!("Connection failed: {}", e),
                     status_code: None,
-                    details: None,
+                    details: Some(details),
                 })
             }
         }
     }
 
+    async fn test_token(&self, station: &RelayStation, token_key: &str) -> Result<TokenTestResult> {
+        let client = http::client_for(station)?;
+        let timeout = http::probe_timeout(station);
+
+        let mut outcome = None;
+        for path in TOKEN_TEST_PATHS {
+            let start_time = std::time::Instant::now();
+            let result = client
+                .get(&format!("{}{}", station.api_url, path))
+                .header("Authorization", &format!("Bearer {}", token_key))
+                .send_with_timeout(station, timeout)
+                .await;
+            let response_time = start_time.elapsed().as_millis() as u64;
+
+            let response = match result {
+                Ok(response) => response,
+                Err(e) => {
+                    return Ok(TokenTestResult {
+                        valid: false,
+                        response_time: None,
+                        status_code: None,
+                        message: scrub(&format!("Connection failed: {}", e), &[token_key]),
+                        remain_quota: None,
+                        unlimited_quota: None,
+                    });
+                }
+            };
+            let status = response.status();
+            if status == reqwest::StatusCode::NOT_FOUND {
+                continue;
+            }
+            let body = http::json_body(response).await.ok();
+            outcome = Some(token_test_result(status, body.as_ref(), response_time));
+            break;
+        }
+
+        let mut result = outcome.ok_or_else(|| RelayError::unsupported("Station has no endpoint for testing tokens"))?;
+        // Upstream messages sometimes echo the key back
+        result.message = scrub(&result.message, &[token_key]);
+        Ok(result)
+    }
+
     async fn list_tokens(&self, station: &RelayStation, page: Option<usize>, size: Option<usize>) -> Result<TokenPaginationResponse> {
-        let client = reqwest::Client::new();
-        let user_id = station.user_id.as_deref().unwrap_or("1");
+        let client = http::client_for(station)?;
+        let user_id = station_user_id(station)?;
         let page = page.unwrap_or(1);
-        let size = size.unwrap_or(10);
+        let size = paging::page_size(size);
         
         let url = format!("{}/api/token/?p={}&size={}", station.api_url, page, size);
         
@@ -305,11 +614,11 @@ impl StationAdapter for NewApiAdapter {
             .get(&url)
             .header("Authorization", &format!("Bearer {}", station.system_token))
             .header("New-API-User", user_id)
-            .send()
+            .send_for(station)
             .await?;
 
         if response.status().is_success() {
-            let data: serde_json::Value = response.json().await?;
+            let data = http::json_body(response).await?;
             let token_data = data["data"].as_object().ok_or_else(|| anyhow!("Invalid response format"))?;
             let empty_vec = vec![];
             let tokens = token_data.get("items").and_then(|v| v.as_array()).unwrap_or(&empty_vec);
@@ -372,36 +681,29 @@ impl StationAdapter for NewApiAdapter {
                 total: token_data.get("total").and_then(|v| v.as_i64()).unwrap_or(0),
             })
         } else {
-            Err(anyhow!("Failed to list tokens: {}", response.status()))
+            Err(RelayError::upstream(response.status(), "Failed to list tokens").into())
         }
     }
 
     async fn create_token(&self, station: &RelayStation, token_data: &CreateTokenRequest) -> Result<RelayStationToken> {
-        let client = reqwest::Client::new();
-        let user_id = station.user_id.as_deref().unwrap_or("1");
+        let client = http::client_for(station)?;
+        let user_id = station_user_id(station)?;
         
-        let request_body = serde_json::json!({
-            "name": token_data.name,
-            "remain_quota": token_data.remain_quota.unwrap_or(500000),
-            "expired_time": token_data.expired_time.unwrap_or(-1),
-            "unlimited_quota": token_data.unlimited_quota.unwrap_or(true),
-            "model_limits_enabled": token_data.model_limits_enabled.unwrap_or(false),
-            "model_limits": token_data.model_limits.as_deref().unwrap_or(""),
-            "group": token_data.group.as_deref().unwrap_or("Claude Code专用"),
-            "allow_ips": token_data.allow_ips.as_deref().unwrap_or("")
-        });
+        let request_body = build_create_token_body(station, token_data);
 
-        let response = client
+        let mut request = client
             .post(&format!("{}/api/token/", station.api_url))
             .header("Authorization", &format!("Bearer {}", station.system_token))
             .header("New-API-User", user_id)
             .header("Content-Type", "application/json")
-            .json(&request_body)
-            .send()
-            .await?;
+            .json(&request_body);
+        if let (Some(header), Some(key)) = (http::idempotency_header(station), token_data.idempotency_key.as_deref()) {
+            request = request.header(header, key);
+        }
+        let response = request.send_for(station).await?;
 
         if response.status().is_success() {
-            let data: serde_json::Value = response.json().await?;
+            let data = http::json_body(response).await?;
             
             // Check if creation was successful
             if data.get("success").and_then(|v| v.as_bool()).unwrap_or(false) {
@@ -416,8 +718,8 @@ impl StationAdapter for NewApiAdapter {
                     user_id: Some(user_id.to_string()),
                     enabled: true,
                     expires_at: if token_data.expired_time.unwrap_or(-1) == -1 { None } else { token_data.expired_time },
-                    group: token_data.group.clone(),
-                    remain_quota: token_data.remain_quota,
+                    group: request_body["group"].as_str().map(|s| s.to_string()),
+                    remain_quota: request_body["remain_quota"].as_i64(),
                     unlimited_quota: token_data.unlimited_quota,
                     metadata: Some({
                         let mut map = HashMap::new();
@@ -434,13 +736,17 @@ impl StationAdapter for NewApiAdapter {
                 Err(anyhow!("Failed to create token: {}", message))
             }
         } else {
-            Err(anyhow!("Failed to create token: {}", response.status()))
+            Err(RelayError::upstream(response.status(), "Failed to create token").into())
         }
     }
 
+    fn preview_create_token(&self, station: &RelayStation, token_data: &CreateTokenRequest) -> Result<serde_json::Value> {
+        Ok(build_create_token_body(station, token_data))
+    }
+
     async fn update_token(&self, station: &RelayStation, token_id: &str, token_data: &UpdateTokenRequest) -> Result<RelayStationToken> {
-        let client = reqwest::Client::new();
-        let user_id = station.user_id.as_deref().unwrap_or("1");
+        let client = http::client_for(station)?;
+        let user_id = station_user_id(station)?;
         
         let mut request_body = serde_json::Map::new();
         request_body.insert("id".to_string(), serde_json::Value::Number(token_data.id.into()));
@@ -457,11 +763,16 @@ impl StationAdapter for NewApiAdapter {
         if let Some(unlimited) = token_data.unlimited_quota {
             request_body.insert("unlimited_quota".to_string(), serde_json::Value::Bool(unlimited));
         }
-        if let Some(enabled) = token_data.model_limits_enabled {
+        let (model_limits_enabled, model_limits) = model_limits_fields(
+            token_data.model_limits_list.as_deref(),
+            token_data.model_limits.as_deref(),
+            token_data.model_limits_enabled,
+        );
+        if let Some(enabled) = model_limits_enabled {
             request_body.insert("model_limits_enabled".to_string(), serde_json::Value::Bool(enabled));
         }
-        if let Some(limits) = &token_data.model_limits {
-            request_body.insert("model_limits".to_string(), serde_json::Value::String(limits.clone()));
+        if let Some(limits) = model_limits {
+            request_body.insert("model_limits".to_string(), serde_json::Value::String(limits));
         }
         if let Some(group) = &token_data.group {
             request_body.insert("group".to_string(), serde_json::Value::String(group.clone()));
@@ -479,11 +790,11 @@ impl StationAdapter for NewApiAdapter {
             .header("New-API-User", user_id)
             .header("Content-Type", "application/json")
             .json(&request_body)
-            .send()
+            .send_for(station)
             .await?;
 
         if response.status().is_success() {
-            let data: serde_json::Value = response.json().await?;
+            let data = http::json_body(response).await?;
             if let Some(token_obj) = data["data"].as_object() {
                 Ok(RelayStationToken {
                     id: token_obj.get("id")
@@ -529,34 +840,34 @@ impl StationAdapter for NewApiAdapter {
                 Err(anyhow!("Invalid response format"))
             }
         } else {
-            Err(anyhow!("Failed to update token: {}", response.status()))
+            Err(RelayError::upstream(response.status(), "Failed to update token").into())
         }
     }
 
     async fn delete_token(&self, station: &RelayStation, token_id: &str) -> Result<()> {
-        let client = reqwest::Client::new();
-        let user_id = station.user_id.as_deref().unwrap_or("1");
+        let client = http::client_for(station)?;
+        let user_id = station_user_id(station)?;
         
         let response = client
             .delete(&format!("{}/api/token/{}", station.api_url, token_id))
             .header("Authorization", &format!("Bearer {}", station.system_token))
             .header("New-API-User", user_id)
-            .send()
+            .send_for(station)
             .await?;
 
         if response.status().is_success() {
             Ok(())
         } else {
-            Err(anyhow!("Failed to delete token: {}", response.status()))
+            Err(RelayError::upstream(response.status(), "Failed to delete token").into())
         }
     }
 
-    async fn toggle_token(&self, station: &RelayStation, token_id: &str, enabled: bool) -> Result<RelayStationToken> {
-        let client = reqwest::Client::new();
-        let user_id = station.user_id.as_deref().unwrap_or("1");
+    async fn set_token_enabled(&self, station: &RelayStation, token_id: &str, enabled: bool) -> Result<RelayStationToken> {
+        let client = http::client_for(station)?;
+        let user_id = station_user_id(station)?;
         
         let request_body = serde_json::json!({
-            "id": token_id.parse::<i64>().map_err(|e| anyhow!("Invalid token ID: {}", e))?,
+            "id": token_id.parse::<i64>().map_err(|e| RelayError::invalid_input(format!("Invalid token ID: {}", e)))?,
             "status": if enabled { 1 } else { 2 }
         });
         
@@ -566,12 +877,16 @@ impl StationAdapter for NewApiAdapter {
             .header("New-API-User", user_id)
             .header("Content-Type", "application/json")
             .json(&request_body)
-            .send()
+            .send_for(station)
             .await?;
 
         if response.status().is_success() {
-            let data: serde_json::Value = response.json().await?;
-            
+            let data = http::json_body(response).await?;
+            if data["success"].as_bool() == Some(false) {
+                let message = data["message"].as_str().unwrap_or("Failed to set token status");
+                return Err(RelayError::Upstream { status: None, message: message.to_string() }.into());
+            }
+
             if let Some(token_obj) = data["data"].as_object() {
                 Ok(RelayStationToken {
                     id: token_obj.get("id")
@@ -617,26 +932,362 @@ impl StationAdapter for NewApiAdapter {
                 Err(anyhow!("Invalid response format"))
             }
         } else {
-            Err(anyhow!("Failed to toggle token: {}", response.status()))
+            Err(RelayError::upstream(response.status(), "Failed to set token status").into())
         }
     }
 
     async fn get_user_groups(&self, station: &RelayStation) -> Result<serde_json::Value> {
-        let client = reqwest::Client::new();
-        let user_id = station.user_id.as_deref().unwrap_or("1");
+        let client = http::client_for(station)?;
+        let user_id = station_user_id(station)?;
         
         let response = client
             .get(&format!("{}/api/user/self/groups", station.api_url))
             .header("Authorization", &format!("Bearer {}", station.system_token))
             .header("New-API-User", user_id)
-            .send()
+            .send_for(station)
             .await?;
 
         if response.status().is_success() {
-            let data: serde_json::Value = response.json().await?;
+            let data = http::json_body(response).await?;
             Ok(data)
         } else {
-            Err(anyhow!("API request failed with status: {}", response.status()))
+            Err(RelayError::upstream(response.status(), "API request failed with status").into())
         }
     }
+
+    async fn get_station_groups(&self, station: &RelayStation) -> Result<Vec<GroupInfo>> {
+        let data = self.get_user_groups(station).await?;
+        if data["success"].as_bool() == Some(false) {
+            let message = data["message"].as_str().unwrap_or("Failed to get groups");
+            return Err(RelayError::Upstream { status: None, message: message.to_string() }.into());
+        }
+        parse_group_list(&data).ok_or_else(|| anyhow!("Unexpected /api/user/self/groups response"))
+    }
+
+    async fn get_usage_dashboard(&self, station: &RelayStation, start: i64, end: i64) -> Result<Vec<DailyUsage>> {
+        let client = http::client_for(station)?;
+        let user_id = station_user_id(station)?;
+
+        let response = client
+            .get(&format!("{}/api/data/self?start_timestamp={}&end_timestamp={}", station.api_url, start, end))
+            .header("Authorization", &format!("Bearer {}", station.system_token))
+            .header("New-API-User", user_id)
+            .send_for(station)
+            .await?;
+
+        // Older deployments lack the endpoint; the caller falls back to logs
+        if matches!(response.status().as_u16(), 404 | 405) {
+            return Err(RelayError::unsupported("Station does not expose /api/data/self").into());
+        }
+        if !response.status().is_success() {
+            return Err(RelayError::upstream(response.status(), "Failed to get usage stats").into());
+        }
+
+        let data = http::json_body(response).await?;
+        if data["success"].as_bool() == Some(false) {
+            // Returned when the station has the data dashboard disabled
+            let message = data["message"].as_str().unwrap_or("Usage stats are disabled on this station");
+            return Err(RelayError::unsupported(message).into());
+        }
+        parse_usage_stats(&data).ok_or_else(|| anyhow!("Unexpected /api/data/self response"))
+    }
+
+    async fn check_token_scope(&self, station: &RelayStation) -> Result<TokenScope> {
+        let client = http::client_for(station)?;
+        let user_id = station_user_id(station)?;
+
+        let response = client
+            .get(&format!("{}/api/user/?p=1&page_size=1", station.api_url))
+            .header("Authorization", &format!("Bearer {}", station.system_token))
+            .header("New-API-User", user_id)
+            .send_for(station)
+            .await?;
+
+        let status = response.status().as_u16();
+        let body = http::json_body(response).await.ok();
+        let scope = token_scope_from_response(status, body.as_ref());
+        if scope != TokenScope::Admin {
+            let message = body.as_ref().and_then(|b| b["message"].as_str()).unwrap_or_default();
+            log::debug!("Token scope probe for {} returned {}: {}", station.name, status, scrub(message, &[&station.system_token]));
+        }
+        Ok(scope)
+    }
+
+    async fn get_models(&self, station: &RelayStation) -> Result<Vec<String>> {
+        let client = http::client_for(station)?;
+        let user_id = station_user_id(station)?;
+
+        // Deployments expose either the dashboard endpoint or the OpenAI-compatible one
+        let mut last_error = None;
+        for path in ["/api/models", "/v1/models"] {
+            // Any failure on one endpoint, including a non-JSON page, moves on to the next
+            let response = match client
+                .get(&format!("{}{}", station.api_url, path))
+                .header("Authorization", &format!("Bearer {}", station.system_token))
+                .header("New-API-User", user_id)
+                .send_for(station)
+                .await
+            {
+                Ok(response) => response,
+                Err(e) => {
+                    last_error = Some(RelayError::from(e));
+                    continue;
+                }
+            };
+
+            if !response.status().is_success() {
+                last_error = Some(RelayError::upstream(response.status(), "Failed to get models"));
+                continue;
+            }
+
+            let data = match http::json_body(response).await {
+                Ok(data) => data,
+                Err(e) => {
+                    last_error = Some(RelayError::from(e));
+                    continue;
+                }
+            };
+            match parse_model_list(&data) {
+                Some(models) => return Ok(models),
+                None => last_error = Some(RelayError::Upstream { status: None, message: format!("Unexpected models response from {}", path) }),
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| RelayError::Upstream { status: None, message: "Failed to get models".to_string() }).into())
+    }
+
+    async fn validate_system_token(&self, station: &RelayStation) -> Result<()> {
+        let client = http::client_for(station)?;
+        let mut request = client
+            .get(&format!("{}/api/status", station.api_url))
+            .header("Authorization", &format!("Bearer {}", station.system_token));
+        if let Ok(user_id) = station_user_id(station) {
+            request = request.header("New-API-User", user_id);
+        }
+
+        let response = request.send_for(station).await?;
+        if !response.status().is_success() {
+            return Err(RelayError::upstream(response.status(), "System token validation failed").into());
+        }
+
+        let data = http::json_body(response).await?;
+        if data["success"].as_bool() == Some(false) {
+            let message = data["message"].as_str().unwrap_or("System token validation failed");
+            return Err(RelayError::Upstream { status: None, message: message.to_string() }.into());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::relay_stations::AuthMethod;
+    use crate::commands::relay_adapters::http::tests::serve_http;
+
+    fn station_with_config(config: serde_json::Value) -> RelayStation {
+        RelayStation {
+            id: "s".to_string(),
+            name: "Test".to_string(),
+            description: None,
+            api_url: "https://relay.example.com".to_string(),
+            adapter: RelayStationAdapter::Newapi,
+            auth_method: AuthMethod::BearerToken,
+            system_token: "sk-test".to_string(),
+            user_id: None,
+            adapter_config: serde_json::from_value(config).ok(),
+            enabled: true,
+            tags: Vec::new(),
+            created_at: 0,
+            updated_at: 0,
+            deleted_at: None,
+            last_used_at: None,
+            last_tested_at: None,
+            config_parse_error: false,
+            is_secure: true,
+        }
+    }
+
+    fn token_request(group: Option<&str>) -> CreateTokenRequest {
+        CreateTokenRequest {
+            name: "token".to_string(),
+            remain_quota: None,
+            expired_time: None,
+            unlimited_quota: None,
+            model_limits_enabled: None,
+            model_limits: None,
+            model_limits_list: None,
+            group: group.map(|s| s.to_string()),
+            allow_ips: None,
+            idempotency_key: None,
+        }
+    }
+
+    #[test]
+    fn test_station_user_id_requires_explicit_user() {
+        let mut station = station_with_config(serde_json::json!({}));
+        assert!(station_user_id(&station).is_err());
+
+        station.adapter_config = serde_json::from_value(serde_json::json!({"default_user_id": "1"})).ok();
+        assert_eq!(station_user_id(&station).unwrap(), "1");
+
+        station.user_id = Some("42".to_string());
+        assert_eq!(station_user_id(&station).unwrap(), "42");
+
+        let mut oneapi = station_with_config(serde_json::json!({}));
+        oneapi.adapter = RelayStationAdapter::Oneapi;
+        assert_eq!(station_user_id(&oneapi).unwrap(), "1");
+    }
+
+    #[test]
+    fn test_token_scope_from_response() {
+        let ok = serde_json::json!({"success": true, "data": {"items": []}});
+        let denied = serde_json::json!({"success": false, "message": "无权进行此操作，权限不足"});
+        assert_eq!(token_scope_from_response(200, Some(&ok)), TokenScope::Admin);
+        assert_eq!(token_scope_from_response(200, Some(&denied)), TokenScope::User);
+        assert_eq!(token_scope_from_response(403, None), TokenScope::User);
+        assert_eq!(token_scope_from_response(401, Some(&denied)), TokenScope::Unknown);
+        assert_eq!(token_scope_from_response(200, None), TokenScope::Unknown);
+    }
+
+    #[test]
+    fn test_parse_usage_stats_sums_hours_into_days() {
+        let data = serde_json::json!({"success": true, "data": [
+            {"created_at": 1700000000, "model_name": "gpt-4o", "count": 2, "token_used": 300, "quota": 40},
+            {"created_at": 1700003600, "model_name": "gpt-4o", "count": 1, "token_used": 100, "quota": 10},
+        ]});
+        let days = parse_usage_stats(&data).unwrap();
+        assert_eq!(days.len(), 1);
+        assert_eq!((days[0].request_count, days[0].token_used, days[0].quota), (3, 400, 50));
+        assert!(parse_usage_stats(&serde_json::json!({"success": true})).is_none());
+    }
+
+    #[test]
+    fn test_create_token_body_uses_configured_defaults() {
+        let station = station_with_config(serde_json::json!({"default_token_group": "work", "default_remain_quota": 250000}));
+        let body = build_create_token_body(&station, &token_request(None));
+        assert_eq!(body["group"], "work");
+        assert_eq!(body["remain_quota"], 250000);
+
+        // An explicit group wins over the station default
+        let body = build_create_token_body(&station, &token_request(Some("vip")));
+        assert_eq!(body["group"], "vip");
+
+        let body = build_create_token_body(&station_with_config(serde_json::json!({})), &token_request(None));
+        assert_eq!(body["group"], "");
+        assert_eq!(body["remain_quota"], 500000);
+    }
+
+    #[test]
+    fn test_model_limits_list_preferred_over_raw_string() {
+        let station = station_with_config(serde_json::json!({}));
+        let mut request = token_request(None);
+        request.model_limits = Some("gpt-4o".to_string());
+        request.model_limits_list = Some(vec![" claude-sonnet-4 ".to_string(), "gpt-4o".to_string(), "claude-sonnet-4".to_string(), "".to_string()]);
+        let body = build_create_token_body(&station, &request);
+        assert_eq!(body["model_limits"], "claude-sonnet-4,gpt-4o");
+        assert_eq!(body["model_limits_enabled"], true);
+
+        // Without a list the raw string passes through unchanged
+        request.model_limits_list = None;
+        let body = build_create_token_body(&station, &request);
+        assert_eq!(body["model_limits"], "gpt-4o");
+        assert_eq!(body["model_limits_enabled"], false);
+
+        assert_eq!(model_limits_fields(Some(&[]), Some("gpt-4o"), None), (None, Some(String::new())));
+    }
+
+    #[test]
+    fn test_health_probe_order_starts_with_remembered_probe() {
+        assert_eq!(health_probe_order(&station_with_config(serde_json::json!({}))), HEALTH_PROBES.to_vec());
+
+        let station = station_with_config(serde_json::json!({ HEALTH_PROBE_KEY: "GET /api/status" }));
+        assert_eq!(health_probe_order(&station), vec!["GET /api/status", "HEAD /api/status", "GET /api/"]);
+
+        // Unknown values are ignored
+        let station = station_with_config(serde_json::json!({ HEALTH_PROBE_KEY: "DELETE /" }));
+        assert_eq!(health_probe_order(&station), HEALTH_PROBES.to_vec());
+    }
+
+    #[test]
+    fn test_parse_group_list_shapes() {
+        let newapi = serde_json::json!({"success": true, "data": {
+            "vip": {"ratio": 0.8, "desc": "VIP"},
+            "default": {"ratio": 1, "desc": ""},
+            "legacy": 2.5
+        }});
+        let groups = parse_group_list(&newapi).unwrap();
+        let names: Vec<_> = groups.iter().map(|g| g.name.as_str()).collect();
+        assert_eq!(names, vec!["default", "legacy", "vip"]);
+        assert_eq!(groups[0], GroupInfo { name: "default".to_string(), ratio: 1.0, description: None });
+        assert_eq!(groups[1].ratio, 2.5);
+        assert_eq!((groups[2].ratio, groups[2].description.as_deref()), (0.8, Some("VIP")));
+
+        let names_only = parse_group_list(&serde_json::json!({"data": ["vip", "default"]})).unwrap();
+        assert_eq!(names_only[1], GroupInfo { name: "vip".to_string(), ratio: 1.0, description: None });
+
+        assert!(parse_group_list(&serde_json::json!({"data": "default"})).is_none());
+    }
+
+    #[test]
+    fn test_get_models_falls_back_after_html_page() {
+        tokio::runtime::Runtime::new().unwrap().block_on(async {
+            let paths = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+            let requested = paths.clone();
+            let base_url = serve_http(move |path| {
+                requested.lock().unwrap().push(path.to_string());
+                // SPA deployments answer unknown API paths with their index page
+                if path == "/api/models" {
+                    ("text/html", "<!doctype html><html></html>".to_string())
+                } else {
+                    ("application/json", serde_json::json!({ "data": [{ "id": "gpt-4o" }] }).to_string())
+                }
+            })
+            .await;
+
+            let mut station = station_with_config(serde_json::json!({}));
+            station.api_url = base_url;
+            station.user_id = Some("1".to_string());
+            assert_eq!(NewApiAdapter.get_models(&station).await.unwrap(), vec!["gpt-4o"]);
+            assert_eq!(*paths.lock().unwrap(), vec!["/api/models", "/v1/models"]);
+        });
+    }
+
+    #[test]
+    fn test_parse_model_list_shapes() {
+        let wrapped = serde_json::json!({"success": true, "data": [{"id": "claude-sonnet-4"}, {"id": "gpt-4o"}]});
+        assert_eq!(parse_model_list(&wrapped).unwrap(), vec!["claude-sonnet-4", "gpt-4o"]);
+
+        let bare = serde_json::json!(["claude-sonnet-4", "gpt-4o"]);
+        assert_eq!(parse_model_list(&bare).unwrap(), vec!["claude-sonnet-4", "gpt-4o"]);
+
+        assert!(parse_model_list(&serde_json::json!({"data": {"1": ["gpt-4o"]}})).is_none());
+    }
+
+    #[test]
+    fn test_log_message_fills_placeholders() {
+        let log = serde_json::json!({ "model_name": "gpt-4o", "prompt_tokens": 12, "completion_tokens": 34, "quota": 560 });
+        let message = log_message(log.as_object().unwrap());
+        for part in ["gpt-4o", "12", "34", "560"] {
+            assert!(message.contains(part), "{}", message);
+        }
+        assert!(!message.contains('{'), "{}", message);
+    }
+
+    #[test]
+    fn test_token_test_result_reads_usage() {
+        let usage = serde_json::json!({"code": true, "data": {"total_available": 1500, "unlimited_quota": false}});
+        let result = token_test_result(reqwest::StatusCode::OK, Some(&usage), 42);
+        assert!(result.valid);
+        assert_eq!((result.remain_quota, result.unlimited_quota), (Some(1500), Some(false)));
+
+        let rejected = serde_json::json!({"error": {"message": "invalid token", "type": "new_api_error"}});
+        let result = token_test_result(reqwest::StatusCode::UNAUTHORIZED, Some(&rejected), 42);
+        assert!(!result.valid);
+        assert_eq!(result.message, "invalid token");
+        assert_eq!(result.remain_quota, None);
+
+        let result = token_test_result(reqwest::StatusCode::OK, Some(&serde_json::json!({"success": false, "message": "disabled"})), 42);
+        assert!(!result.valid);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,96 @@
+use std::time::Duration;
+use anyhow::Result;
+
+use crate::commands::relay_stations::{
+    RelayStation, RelayStationToken, StationInfo, UserInfo,
+    LogPaginationResponse, TokenPaginationResponse, ConnectionTestResult, CreateTokenRequest, UpdateTokenRequest,
+    StationAdapter, StationCapabilities, RelayError, TokenTestResult, GroupInfo, TokenScope
+};
+
+use super::newapi::NewApiAdapter;
+
+/// OneAPI adapter implementation - shares NewAPI's status, user and model endpoints.
+/// OneAPI returns token and log lists as bare arrays instead of NewAPI's paged objects and has
+/// no group endpoints, so those features are reported as unsupported rather than half-working.
+pub struct OneApiAdapter {
+    newapi: NewApiAdapter,
+}
+
+impl OneApiAdapter {
+    pub fn new() -> Self {
+        Self {
+            newapi: NewApiAdapter,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl StationAdapter for OneApiAdapter {
+    fn capabilities(&self) -> StationCapabilities {
+        StationCapabilities { supports_tokens: false, supports_logs: false, supports_user_info: true, supports_models: true }
+    }
+
+    async fn get_station_info(&self, station: &RelayStation) -> Result<StationInfo> {
+        self.newapi.get_station_info(station).await
+    }
+
+    async fn get_user_info(&self, station: &RelayStation, user_id: &str) -> Result<UserInfo> {
+        self.newapi.get_user_info(station, user_id).await
+    }
+
+    async fn get_logs(&self, _station: &RelayStation, _page: Option<usize>, _page_size: Option<usize>, _cursor: Option<&str>, _filters: Option<serde_json::Value>) -> Result<LogPaginationResponse> {
+        Err(RelayError::unsupported("Logs not available for OneAPI stations").into())
+    }
+
+    async fn test_connection(&self, station: &RelayStation, timeout: Duration) -> Result<ConnectionTestResult> {
+        self.newapi.test_connection(station, timeout).await
+    }
+
+    async fn test_token(&self, station: &RelayStation, token_key: &str) -> Result<TokenTestResult> {
+        self.newapi.test_token(station, token_key).await
+    }
+
+    async fn list_tokens(&self, _station: &RelayStation, _page: Option<usize>, _size: Option<usize>) -> Result<TokenPaginationResponse> {
+        Err(RelayError::unsupported("Token management not available for OneAPI stations").into())
+    }
+
+    async fn create_token(&self, _station: &RelayStation, _token_data: &CreateTokenRequest) -> Result<RelayStationToken> {
+        Err(RelayError::unsupported("Token management not available for OneAPI stations").into())
+    }
+
+    fn preview_create_token(&self, _station: &RelayStation, _token_data: &CreateTokenRequest) -> Result<serde_json::Value> {
+        Err(RelayError::unsupported("Token management not available for OneAPI stations").into())
+    }
+
+    async fn update_token(&self, _station: &RelayStation, _token_id: &str, _token_data: &UpdateTokenRequest) -> Result<RelayStationToken> {
+        Err(RelayError::unsupported("Token management not available for OneAPI stations").into())
+    }
+
+    async fn delete_token(&self, _station: &RelayStation, _token_id: &str) -> Result<()> {
+        Err(RelayError::unsupported("Token management not available for OneAPI stations").into())
+    }
+
+    async fn set_token_enabled(&self, _station: &RelayStation, _token_id: &str, _enabled: bool) -> Result<RelayStationToken> {
+        Err(RelayError::unsupported("Token management not available for OneAPI stations").into())
+    }
+
+    async fn get_user_groups(&self, _station: &RelayStation) -> Result<serde_json::Value> {
+        Err(RelayError::unsupported("User groups not available for OneAPI stations").into())
+    }
+
+    async fn get_models(&self, station: &RelayStation) -> Result<Vec<String>> {
+        self.newapi.get_models(station).await
+    }
+
+    async fn get_station_groups(&self, _station: &RelayStation) -> Result<Vec<GroupInfo>> {
+        Err(RelayError::unsupported("User groups not available for OneAPI stations").into())
+    }
+
+    async fn validate_system_token(&self, station: &RelayStation) -> Result<()> {
+        self.newapi.validate_system_token(station).await
+    }
+
+    async fn check_token_scope(&self, station: &RelayStation) -> Result<TokenScope> {
+        self.newapi.check_token_scope(station).await
+    }
+}
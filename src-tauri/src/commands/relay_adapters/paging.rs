@@ -0,0 +1,55 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Page size for logs and token lists when the caller doesn't pass one
+pub const DEFAULT_PAGE_SIZE: usize = 20;
+
+/// Largest default page size that can be configured
+pub const MAX_PAGE_SIZE: usize = 200;
+
+static PAGE_SIZE: AtomicUsize = AtomicUsize::new(DEFAULT_PAGE_SIZE);
+
+/// The page size adapters use when none is requested
+pub fn default_page_size() -> usize {
+    PAGE_SIZE.load(Ordering::Relaxed)
+}
+
+/// Replaces the default page size; callers validate it against `MAX_PAGE_SIZE`
+pub fn set_default_page_size(page_size: usize) {
+    PAGE_SIZE.store(page_size, Ordering::Relaxed);
+}
+
+/// An explicit page size, or the configured default
+pub fn page_size(requested: Option<usize>) -> usize {
+    requested.unwrap_or_else(default_page_size)
+}
+
+#[cfg(test)]
+static PAGE_SIZE_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+/// Runs `test` with the default page size set to `page_size`, restoring the previous value afterwards.
+/// Every test that changes the default must go through here, since it is process-wide.
+#[cfg(test)]
+pub(crate) fn with_default_page_size(page_size: usize, test: impl FnOnce()) {
+    let _guard = PAGE_SIZE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let previous = default_page_size();
+    set_default_page_size(page_size);
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(test));
+    set_default_page_size(previous);
+    if let Err(panic) = result {
+        std::panic::resume_unwind(panic);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_explicit_page_size_overrides_default() {
+        with_default_page_size(50, || {
+            assert_eq!(page_size(None), 50);
+            assert_eq!(page_size(Some(5)), 5);
+        });
+        assert_eq!(page_size(None), DEFAULT_PAGE_SIZE);
+    }
+}
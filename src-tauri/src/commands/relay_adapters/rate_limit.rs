@@ -0,0 +1,133 @@
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Default request rate across all stations
+pub const DEFAULT_GLOBAL_RPS: f64 = 20.0;
+
+/// Default request rate for any single host
+pub const DEFAULT_PER_HOST_RPS: f64 = 5.0;
+
+/// Requests per second allowed by the adapter rate limiter; 0 disables a limit
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RateLimitConfig {
+    pub global_rps: f64,
+    pub per_host_rps: f64,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self { global_rps: DEFAULT_GLOBAL_RPS, per_host_rps: DEFAULT_PER_HOST_RPS }
+    }
+}
+
+/// A token bucket that hands out reservations, letting its balance go negative so
+/// concurrent callers queue up behind each other instead of all waking at once.
+#[derive(Debug)]
+struct TokenBucket {
+    rate: f64,
+    capacity: f64,
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate: f64, now: Instant) -> Self {
+        let capacity = rate.max(1.0);
+        Self { rate, capacity, tokens: capacity, updated_at: now }
+    }
+
+    /// Takes one token and returns how long the caller must wait before using it
+    fn reserve(&mut self, now: Instant) -> Duration {
+        if self.rate <= 0.0 {
+            return Duration::ZERO;
+        }
+        let elapsed = now.saturating_duration_since(self.updated_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity) - 1.0;
+        self.updated_at = now;
+
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.rate)
+        }
+    }
+}
+
+#[derive(Debug)]
+struct RateLimiter {
+    config: RateLimitConfig,
+    global: TokenBucket,
+    hosts: HashMap<String, TokenBucket>,
+}
+
+impl RateLimiter {
+    fn new(config: RateLimitConfig) -> Self {
+        Self { config, global: TokenBucket::new(config.global_rps, Instant::now()), hosts: HashMap::new() }
+    }
+
+    fn reserve(&mut self, host: &str, now: Instant) -> Duration {
+        let per_host_rps = self.config.per_host_rps;
+        let host_wait = self
+            .hosts
+            .entry(host.to_string())
+            .or_insert_with(|| TokenBucket::new(per_host_rps, now))
+            .reserve(now);
+        self.global.reserve(now).max(host_wait)
+    }
+}
+
+static LIMITER: Lazy<Mutex<RateLimiter>> = Lazy::new(|| Mutex::new(RateLimiter::new(RateLimitConfig::default())));
+
+/// The limits currently applied to adapter requests
+pub fn config() -> RateLimitConfig {
+    LIMITER.lock().map(|limiter| limiter.config).unwrap_or_default()
+}
+
+/// Replaces the limits, resetting all buckets
+pub fn set_config(config: RateLimitConfig) {
+    if let Ok(mut limiter) = LIMITER.lock() {
+        *limiter = RateLimiter::new(config);
+    }
+}
+
+/// Waits until a request to `host` fits within both the global and per-host limits
+pub async fn acquire(host: &str) {
+    let wait = match LIMITER.lock() {
+        Ok(mut limiter) => limiter.reserve(host, Instant::now()),
+        Err(_) => Duration::ZERO,
+    };
+    if !wait.is_zero() {
+        log::debug!("Rate limiting request to {} for {:?}", host, wait);
+        tokio::time::sleep(wait).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_allows_burst_then_spaces_requests() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(2.0, start);
+        assert_eq!(bucket.reserve(start), Duration::ZERO);
+        assert_eq!(bucket.reserve(start), Duration::ZERO);
+        // Queued callers wait progressively longer
+        assert_eq!(bucket.reserve(start), Duration::from_millis(500));
+        assert_eq!(bucket.reserve(start), Duration::from_millis(1000));
+        // Refills over time
+        assert_eq!(bucket.reserve(start + Duration::from_secs(5)), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_limiter_tracks_hosts_separately() {
+        let start = Instant::now();
+        let mut limiter = RateLimiter::new(RateLimitConfig { global_rps: 0.0, per_host_rps: 1.0 });
+        assert_eq!(limiter.reserve("a.example.com", start), Duration::ZERO);
+        assert_eq!(limiter.reserve("b.example.com", start), Duration::ZERO);
+        assert_eq!(limiter.reserve("a.example.com", start), Duration::from_secs(1));
+    }
+}
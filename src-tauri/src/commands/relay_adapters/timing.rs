@@ -0,0 +1,161 @@
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio_native_tls::{native_tls, TlsConnector};
+
+use super::http;
+use crate::commands::relay_stations::RelayStation;
+
+/// Stage keys reported in `ConnectionTestResult::details`, in probe order
+pub const TIMING_STAGES: [&str; 4] = ["dns_ms", "tcp_connect_ms", "tls_handshake_ms", "ttfb_ms"];
+
+/// Key describing which request the stage times belong to
+pub const TIMING_PROBE_KEY: &str = "timing_probe";
+
+fn elapsed_ms(start: Instant) -> serde_json::Value {
+    serde_json::json!(start.elapsed().as_millis() as u64)
+}
+
+/// A TLS connector honoring the station's `ca_cert_path` and `danger_accept_invalid_certs`
+fn tls_connector(station: &RelayStation) -> Result<native_tls::TlsConnector> {
+    let mut builder = native_tls::TlsConnector::builder();
+    if let Some(pem) = http::read_ca_cert(station)? {
+        builder.add_root_certificate(native_tls::Certificate::from_pem(&pem)?);
+    }
+    if http::accepts_invalid_certs(station) {
+        builder.danger_accept_invalid_certs(true);
+    }
+    Ok(builder.build()?)
+}
+
+/// Probes `{api_url}{path}` stage by stage and reports how long DNS resolution, TCP connect,
+/// the TLS handshake and time-to-first-byte took, in milliseconds.
+///
+/// This is its own bare HTTP/1.1 request, not the adapter's API call: it sends no custom headers
+/// and bypasses the rate limiter and debug logging. `timing_probe` says so in the output.
+///
+/// Plain HTTP stations report `tls_handshake_ms` as 0. Stages after a failure stay null and the
+/// failure is reported under `timing_error`. Stations behind a `proxy_url` are skipped because the
+/// direct path isn't the one their requests take.
+pub async fn connection_timing(station: &RelayStation, path: &str, timeout: Duration) -> HashMap<String, serde_json::Value> {
+    let mut details: HashMap<String, serde_json::Value> = TIMING_STAGES
+        .iter()
+        .map(|stage| (stage.to_string(), serde_json::Value::Null))
+        .collect();
+    details.insert(
+        TIMING_PROBE_KEY.to_string(),
+        format!("Separate GET {} request, not the API call; custom headers are not sent", path).into(),
+    );
+
+    if http::proxy_url(station).is_some() {
+        details.insert("timing_error".to_string(), "Timing breakdown is unavailable through a proxy".into());
+        return details;
+    }
+
+    match tokio::time::timeout(timeout, staged_probe(station, path, &mut details)).await {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => {
+            details.insert("timing_error".to_string(), e.to_string().into());
+        }
+        Err(_) => {
+            details.insert("timing_error".to_string(), format!("Timed out after {}s", timeout.as_secs()).into());
+        }
+    }
+    details
+}
+
+async fn staged_probe(station: &RelayStation, path: &str, details: &mut HashMap<String, serde_json::Value>) -> Result<()> {
+    let url = reqwest::Url::parse(&station.api_url)?;
+    let host = url
+        .host_str()
+        .ok_or_else(|| anyhow!("API URL has no host"))?
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_string();
+    let port = url.port_or_known_default().unwrap_or(80);
+    let host_header = match url.port() {
+        Some(port) => format!("{}:{}", host, port),
+        None => host.clone(),
+    };
+    let target = format!("{}{}", url.path().trim_end_matches('/'), path);
+
+    let start = Instant::now();
+    let addr = tokio::net::lookup_host((host.as_str(), port))
+        .await?
+        .next()
+        .ok_or_else(|| anyhow!("No addresses found for {}", host))?;
+    details.insert("dns_ms".to_string(), elapsed_ms(start));
+
+    let start = Instant::now();
+    let tcp = TcpStream::connect(addr).await?;
+    details.insert("tcp_connect_ms".to_string(), elapsed_ms(start));
+
+    if url.scheme() == "https" {
+        let connector = TlsConnector::from(tls_connector(station)?);
+        let start = Instant::now();
+        let tls = connector.connect(&host, tcp).await?;
+        details.insert("tls_handshake_ms".to_string(), elapsed_ms(start));
+        measure_ttfb(tls, &host_header, &target, details).await
+    } else {
+        details.insert("tls_handshake_ms".to_string(), serde_json::json!(0));
+        measure_ttfb(tcp, &host_header, &target, details).await
+    }
+}
+
+/// Sends a bare HTTP/1.1 GET and times how long the first response byte takes to arrive
+async fn measure_ttfb<S: AsyncRead + AsyncWrite + Unpin>(
+    mut stream: S,
+    host_header: &str,
+    target: &str,
+    details: &mut HashMap<String, serde_json::Value>,
+) -> Result<()> {
+    let request = format!(
+        "GET {} HTTP/1.1\r\nHost: {}\r\nAccept: */*\r\nConnection: close\r\n\r\n",
+        target, host_header
+    );
+
+    let start = Instant::now();
+    stream.write_all(request.as_bytes()).await?;
+    let mut first_byte = [0u8; 1];
+    stream.read_exact(&mut first_byte).await?;
+    details.insert("ttfb_ms".to_string(), elapsed_ms(start));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn test_plain_http_reports_zero_tls() {
+        tokio::runtime::Runtime::new().unwrap().block_on(async {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let port = listener.local_addr().unwrap().port();
+            tokio::spawn(async move {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = [0u8; 1024];
+                let _ = socket.read(&mut buf).await;
+                let _ = socket.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n").await;
+            });
+
+            let mut station: RelayStation = serde_json::from_value(serde_json::json!({
+                "id": "s", "name": "Local", "description": null, "api_url": "", "adapter": "newapi",
+                "auth_method": "bearer_token", "system_token": "", "user_id": null, "adapter_config": null,
+                "enabled": true, "created_at": 0, "updated_at": 0, "deleted_at": null
+            }))
+            .unwrap();
+            station.api_url = format!("http://127.0.0.1:{}", port);
+
+            let details = connection_timing(&station, "/api/status", Duration::from_secs(5)).await;
+            assert_eq!(details["tls_handshake_ms"], 0);
+            for stage in TIMING_STAGES {
+                assert!(details[stage].is_u64(), "{} missing: {:?}", stage, details);
+            }
+            assert!(!details.contains_key("timing_error"));
+            assert!(details[TIMING_PROBE_KEY].as_str().unwrap().contains("/api/status"));
+        });
+    }
+}
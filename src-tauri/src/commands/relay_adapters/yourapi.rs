@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::time::Duration;
 use anyhow::{Result, anyhow};
 use reqwest;
 use chrono;
@@ -7,10 +8,12 @@ use chrono;
 use crate::commands::relay_stations::{
     RelayStation, RelayStationToken, StationInfo, UserInfo, StationLogEntry, 
     LogPaginationResponse, TokenPaginationResponse, ConnectionTestResult, CreateTokenRequest, UpdateTokenRequest,
-    StationAdapter
+    StationAdapter, StationCapabilities, RelayError, TokenTestResult, DailyUsage, TokenScope, GroupInfo
 };
 
-use super::newapi::NewApiAdapter;
+use super::http::{self, StationRequestExt};
+use super::newapi::{self, NewApiAdapter};
+use super::paging;
 
 /// YourAPI adapter implementation - inherits most functionality from NewAPI but overrides token listing
 pub struct YourApiAdapter {
@@ -28,6 +31,10 @@ impl YourApiAdapter {
 #[async_trait::async_trait]
 impl StationAdapter for YourApiAdapter {
     // Delegate all methods to NewAPI except list_tokens
+    fn capabilities(&self) -> StationCapabilities {
+        self.newapi.capabilities()
+    }
+
     async fn get_station_info(&self, station: &RelayStation) -> Result<StationInfo> {
         self.newapi.get_station_info(station).await
     }
@@ -36,18 +43,26 @@ impl StationAdapter for YourApiAdapter {
         self.newapi.get_user_info(station, user_id).await
     }
 
-    async fn get_logs(&self, station: &RelayStation, page: Option<usize>, page_size: Option<usize>, filters: Option<serde_json::Value>) -> Result<LogPaginationResponse> {
-        self.newapi.get_logs(station, page, page_size, filters).await
+    async fn get_logs(&self, station: &RelayStation, page: Option<usize>, page_size: Option<usize>, cursor: Option<&str>, filters: Option<serde_json::Value>) -> Result<LogPaginationResponse> {
+        self.newapi.get_logs(station, page, page_size, cursor, filters).await
+    }
+
+    async fn test_connection(&self, station: &RelayStation, timeout: Duration) -> Result<ConnectionTestResult> {
+        self.newapi.test_connection(station, timeout).await
     }
 
-    async fn test_connection(&self, station: &RelayStation) -> Result<ConnectionTestResult> {
-        self.newapi.test_connection(station).await
+    async fn test_token(&self, station: &RelayStation, token_key: &str) -> Result<TokenTestResult> {
+        self.newapi.test_token(station, token_key).await
     }
 
     async fn create_token(&self, station: &RelayStation, token_data: &CreateTokenRequest) -> Result<RelayStationToken> {
         self.newapi.create_token(station, token_data).await
     }
 
+    fn preview_create_token(&self, station: &RelayStation, token_data: &CreateTokenRequest) -> Result<serde_json::Value> {
+        self.newapi.preview_create_token(station, token_data)
+    }
+
     async fn update_token(&self, station: &RelayStation, token_id: &str, token_data: &UpdateTokenRequest) -> Result<RelayStationToken> {
         self.newapi.update_token(station, token_id, token_data).await
     }
@@ -56,20 +71,40 @@ impl StationAdapter for YourApiAdapter {
         self.newapi.delete_token(station, token_id).await
     }
 
-    async fn toggle_token(&self, station: &RelayStation, token_id: &str, enabled: bool) -> Result<RelayStationToken> {
-        self.newapi.toggle_token(station, token_id, enabled).await
+    async fn set_token_enabled(&self, station: &RelayStation, token_id: &str, enabled: bool) -> Result<RelayStationToken> {
+        self.newapi.set_token_enabled(station, token_id, enabled).await
     }
 
     async fn get_user_groups(&self, station: &RelayStation) -> Result<serde_json::Value> {
         self.newapi.get_user_groups(station).await
     }
 
+    async fn get_models(&self, station: &RelayStation) -> Result<Vec<String>> {
+        self.newapi.get_models(station).await
+    }
+
+    async fn get_station_groups(&self, station: &RelayStation) -> Result<Vec<GroupInfo>> {
+        self.newapi.get_station_groups(station).await
+    }
+
+    async fn check_token_scope(&self, station: &RelayStation) -> Result<TokenScope> {
+        self.newapi.check_token_scope(station).await
+    }
+
+    async fn get_usage_dashboard(&self, station: &RelayStation, start: i64, end: i64) -> Result<Vec<DailyUsage>> {
+        self.newapi.get_usage_dashboard(station, start, end).await
+    }
+
+    async fn validate_system_token(&self, station: &RelayStation) -> Result<()> {
+        self.newapi.validate_system_token(station).await
+    }
+
     // Override list_tokens for YourAPI format
     async fn list_tokens(&self, station: &RelayStation, page: Option<usize>, size: Option<usize>) -> Result<TokenPaginationResponse> {
-        let client = reqwest::Client::new();
-        let user_id = station.user_id.as_deref().unwrap_or("1");
+        let client = http::client_for(station)?;
+        let user_id = newapi::station_user_id(station)?;
         let page = page.unwrap_or(1); // Use 1-based pagination like frontend expects
-        let size = size.unwrap_or(10);
+        let size = paging::page_size(size);
         
         // YourAPI might use different pagination parameters
         // Try to get more data to estimate if there are more pages
@@ -80,11 +115,11 @@ impl StationAdapter for YourApiAdapter {
             .get(&url)
             .header("Authorization", &format!("Bearer {}", station.system_token))
             .header("New-API-User", user_id)
-            .send()
+            .send_for(station)
             .await?;
 
         if response.status().is_success() {
-            let data: serde_json::Value = response.json().await?;
+            let data = http::json_body(response).await?;
             
             // YourAPI returns data as direct array, not nested in pagination object
             let tokens = data["data"].as_array().ok_or_else(|| anyhow!("Invalid response format: data is not an array"))?;
@@ -170,7 +205,7 @@ impl StationAdapter for YourApiAdapter {
                 total: estimated_total,
             })
         } else {
-            Err(anyhow!("Failed to list tokens: {}", response.status()))
+            Err(RelayError::upstream(response.status(), "Failed to list tokens").into())
         }
     }
 }
\ No newline at end of file
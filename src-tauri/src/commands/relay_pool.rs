@@ -0,0 +1,125 @@
+use anyhow::{anyhow, Result};
+use rusqlite::Connection;
+use std::ops::{Deref, DerefMut};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Connections kept open for reuse; more are opened on demand and closed when returned
+const MAX_IDLE_CONNECTIONS: usize = 4;
+
+/// How long a connection waits for another writer before giving up
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A small SQLite connection pool so readers don't queue behind one shared connection.
+///
+/// The database runs in WAL mode, so any number of readers can proceed alongside a writer.
+/// Every connection enforces foreign keys, which SQLite leaves off by default.
+pub struct ConnectionPool {
+    path: String,
+    idle: Mutex<Vec<Connection>>,
+}
+
+impl ConnectionPool {
+    /// Opens a pool over the database file at `path`, switching it to WAL mode
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path
+            .as_ref()
+            .to_str()
+            .ok_or_else(|| anyhow!("Database path is not valid UTF-8"))?
+            .to_string();
+        let pool = Self { path, idle: Mutex::new(Vec::new()) };
+
+        let conn = pool.get()?;
+        conn.query_row("PRAGMA journal_mode = WAL", [], |row| row.get::<_, String>(0))?;
+        drop(conn);
+
+        Ok(pool)
+    }
+
+    /// Opens a pool over a named shared in-memory database, which lives as long as one of its
+    /// connections is open
+    pub fn in_memory(name: &str) -> Result<Self> {
+        let pool = Self {
+            path: format!("file:{}?mode=memory&cache=shared", name),
+            idle: Mutex::new(Vec::new()),
+        };
+        // Keep one connection idle so the database outlives individual checkouts
+        drop(pool.get()?);
+        Ok(pool)
+    }
+
+    /// Checks out a connection, reusing an idle one when available
+    pub fn get(&self) -> Result<PooledConnection<'_>> {
+        let idle = self.idle.lock().map_err(|e| anyhow!("Lock error: {}", e))?.pop();
+        let conn = match idle {
+            Some(conn) => conn,
+            None => self.connect()?,
+        };
+        Ok(PooledConnection { pool: self, conn: Some(conn) })
+    }
+
+    fn connect(&self) -> Result<Connection> {
+        let conn = Connection::open(&self.path)?;
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+        Ok(conn)
+    }
+}
+
+/// A connection checked out of a `ConnectionPool`, returned to it on drop
+pub struct PooledConnection<'a> {
+    pool: &'a ConnectionPool,
+    conn: Option<Connection>,
+}
+
+impl Deref for PooledConnection<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn.as_ref().expect("pooled connection already returned")
+    }
+}
+
+impl DerefMut for PooledConnection<'_> {
+    fn deref_mut(&mut self) -> &mut Connection {
+        self.conn.as_mut().expect("pooled connection already returned")
+    }
+}
+
+impl Drop for PooledConnection<'_> {
+    fn drop(&mut self) {
+        let Some(conn) = self.conn.take() else { return };
+        if let Ok(mut idle) = self.pool.idle.lock() {
+            if idle.len() < MAX_IDLE_CONNECTIONS {
+                idle.push(conn);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pooled_connections_share_database() {
+        let pool = ConnectionPool::in_memory("relay_pool_test").unwrap();
+        pool.get().unwrap().execute("CREATE TABLE items (id INTEGER)", []).unwrap();
+
+        // Two connections checked out at once see the same data
+        let first = pool.get().unwrap();
+        let second = pool.get().unwrap();
+        first.execute("INSERT INTO items (id) VALUES (1)", []).unwrap();
+        let count: i64 = second.query_row("SELECT COUNT(*) FROM items", [], |row| row.get(0)).unwrap();
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn test_file_pool_uses_wal() {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = ConnectionPool::open(dir.path().join("relay.db")).unwrap();
+        let mode: String = pool.get().unwrap().query_row("PRAGMA journal_mode", [], |row| row.get(0)).unwrap();
+        assert_eq!(mode, "wal");
+    }
+}
@@ -1,14 +1,144 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use tauri::{AppHandle, State, Manager};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use tauri::{AppHandle, Emitter, State, Manager};
 use chrono::Utc;
 use uuid::Uuid;
 use anyhow::{Result, anyhow};
 use reqwest;
-use rusqlite::{params, Connection};
-use std::sync::Mutex;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use futures::stream::{self, StreamExt};
+use once_cell::sync::Lazy;
+use tokio_util::sync::CancellationToken;
 
-use super::relay_adapters::{NewApiAdapter, YourApiAdapter, CustomAdapter};
+use super::relay_adapters::{NewApiAdapter, OneApiAdapter, YourApiAdapter, CustomAdapter};
+use super::relay_adapters::newapi::HEALTH_PROBE_KEY;
+use super::relay_adapters::http;
+use super::relay_adapters::timing;
+use super::relay_adapters::paging::{self, MAX_PAGE_SIZE};
+use super::relay_adapters::rate_limit::{self, RateLimitConfig};
+use super::relay_pool::ConnectionPool;
+use super::provider::{insert_provider_config, AddProviderError, ProviderConfig};
+use crate::i18n;
+use crate::redact::redact;
+
+/// Error returned by relay station commands.
+///
+/// Serialized as a tagged object (`{"kind": "not_found", "message": "..."}`) so the
+/// frontend can branch on `kind` while toasts keep using `message`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RelayError {
+    NotFound { message: String },
+    ManagerUninitialized { message: String },
+    InvalidInput { message: String },
+    Conflict { message: String },
+    Unsupported { message: String },
+    Upstream { status: Option<u16>, message: String },
+    Network { message: String },
+    Database { message: String },
+    Cancelled { message: String },
+}
+
+impl RelayError {
+    pub fn not_found(message: impl Into<String>) -> Self {
+        RelayError::NotFound { message: message.into() }
+    }
+
+    pub fn manager_uninitialized() -> Self {
+        RelayError::ManagerUninitialized { message: "Relay station manager not initialized".to_string() }
+    }
+
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        RelayError::InvalidInput { message: message.into() }
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        RelayError::Conflict { message: message.into() }
+    }
+
+    pub fn unsupported(message: impl Into<String>) -> Self {
+        RelayError::Unsupported { message: message.into() }
+    }
+
+    /// Builds an upstream error from a non-success HTTP status, e.g. "Failed to list tokens: 502 Bad Gateway"
+    pub fn upstream(status: reqwest::StatusCode, context: &str) -> Self {
+        RelayError::Upstream { status: Some(status.as_u16()), message: format!("{}: {}", context, status) }
+    }
+
+    pub fn network(message: impl Into<String>) -> Self {
+        RelayError::Network { message: message.into() }
+    }
+
+    pub fn database(message: impl Into<String>) -> Self {
+        RelayError::Database { message: message.into() }
+    }
+
+    pub fn cancelled(message: impl Into<String>) -> Self {
+        RelayError::Cancelled { message: message.into() }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            RelayError::NotFound { message }
+            | RelayError::ManagerUninitialized { message }
+            | RelayError::InvalidInput { message }
+            | RelayError::Conflict { message }
+            | RelayError::Unsupported { message }
+            | RelayError::Upstream { message, .. }
+            | RelayError::Network { message }
+            | RelayError::Database { message }
+            | RelayError::Cancelled { message } => message,
+        }
+    }
+
+    /// Prefixes the message with the operation that failed, keeping the error kind
+    pub fn context(mut self, context: &str) -> Self {
+        match &mut self {
+            RelayError::NotFound { message }
+            | RelayError::ManagerUninitialized { message }
+            | RelayError::InvalidInput { message }
+            | RelayError::Conflict { message }
+            | RelayError::Unsupported { message }
+            | RelayError::Upstream { message, .. }
+            | RelayError::Network { message }
+            | RelayError::Database { message }
+            | RelayError::Cancelled { message } => *message = format!("{}: {}", context, message),
+        }
+        self
+    }
+}
+
+impl std::fmt::Display for RelayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message())
+    }
+}
+
+impl std::error::Error for RelayError {}
+
+impl From<anyhow::Error> for RelayError {
+    fn from(error: anyhow::Error) -> Self {
+        if let Some(relay_error) = error.downcast_ref::<RelayError>() {
+            return relay_error.clone();
+        }
+        if let Some(reqwest_error) = error.downcast_ref::<reqwest::Error>() {
+            return match reqwest_error.status() {
+                Some(status) => RelayError::Upstream { status: Some(status.as_u16()), message: error.to_string() },
+                None if reqwest_error.is_decode() => RelayError::Upstream { status: None, message: error.to_string() },
+                None => RelayError::network(error.to_string()),
+            };
+        }
+        if error.downcast_ref::<rusqlite::Error>().is_some() {
+            return RelayError::database(error.to_string());
+        }
+        // Anything else comes from interpreting an upstream response (e.g. unexpected shape)
+        RelayError::Upstream { status: None, message: error.to_string() }
+    }
+}
 
 /// Relay station adapter type for different station implementations
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -41,10 +171,15 @@ pub struct CreateRelayStationRequest {
     pub user_id: Option<String>, // For NewAPI stations, this is required
     pub adapter_config: Option<HashMap<String, serde_json::Value>>,
     pub enabled: bool,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Fetch the station's `quota_per_unit` once and store it in adapter_config
+    #[serde(default)]
+    pub discover_quota_per_unit: bool,
 }
 
 /// Represents a relay station configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct RelayStation {
     pub id: String,
     pub name: String,
@@ -56,8 +191,65 @@ pub struct RelayStation {
     pub user_id: Option<String>, // For NewAPI stations, this is required
     pub adapter_config: Option<HashMap<String, serde_json::Value>>,
     pub enabled: bool,
+    #[serde(default)]
+    pub tags: Vec<String>, // Normalized (trimmed, lowercased) on write
     pub created_at: i64,
     pub updated_at: i64,
+    pub deleted_at: Option<i64>, // Set when the station is in the recycle bin
+    #[serde(default)]
+    pub last_used_at: Option<i64>, // Last fetch of its tokens, logs or user info
+    #[serde(default)]
+    pub last_tested_at: Option<i64>, // Last connection test
+    #[serde(default)]
+    pub config_parse_error: bool, // The stored adapter_config is not valid JSON and was ignored
+    #[serde(default)]
+    pub is_secure: bool, // api_url uses https; derived on load, see `RelayStation::is_secure`
+}
+
+impl RelayStation {
+    /// Whether requests to the station are encrypted, i.e. its api_url uses https
+    pub fn is_secure(&self) -> bool {
+        is_secure_url(&self.api_url)
+    }
+
+    /// A warning when the station's system token would be sent over plain http
+    pub fn plain_http_warning(&self) -> Option<String> {
+        if self.is_secure() || self.system_token.trim().is_empty() {
+            return None;
+        }
+        Some(format!("Station {} uses plain http; its system token is sent unencrypted", self.name))
+    }
+}
+
+/// Whether `url` has the https scheme
+pub fn is_secure_url(url: &str) -> bool {
+    reqwest::Url::parse(url.trim()).is_ok_and(|url| url.scheme() == "https")
+}
+
+/// Debug output masks `system_token` so stations can be logged safely
+impl std::fmt::Debug for RelayStation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RelayStation")
+            .field("id", &self.id)
+            .field("name", &self.name)
+            .field("description", &self.description)
+            .field("api_url", &self.api_url)
+            .field("adapter", &self.adapter)
+            .field("auth_method", &self.auth_method)
+            .field("system_token", &redact(&self.system_token))
+            .field("user_id", &self.user_id)
+            .field("adapter_config", &self.adapter_config)
+            .field("enabled", &self.enabled)
+            .field("tags", &self.tags)
+            .field("created_at", &self.created_at)
+            .field("updated_at", &self.updated_at)
+            .field("deleted_at", &self.deleted_at)
+            .field("last_used_at", &self.last_used_at)
+            .field("last_tested_at", &self.last_tested_at)
+            .field("config_parse_error", &self.config_parse_error)
+            .field("is_secure", &self.is_secure)
+            .finish()
+    }
 }
 
 /// Station information retrieved from the relay station
@@ -69,10 +261,241 @@ pub struct StationInfo {
     pub version: Option<String>,
     pub metadata: Option<HashMap<String, serde_json::Value>>,
     pub quota_per_unit: Option<i64>, // Added for price conversion
+    #[serde(default)]
+    pub is_secure: bool, // The station's api_url uses https
 }
 
-/// Token configuration for a relay station
+/// A balance snapshot for charting quota burn over time.
+///
+/// Stores the raw quota integers with the conversion factor in effect when it was taken, so
+/// the dollar values stay correct if the station's `quota_per_unit` changes later.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuotaSample {
+    pub station_id: String,
+    pub recorded_at: i64,
+    pub quota: i64,
+    pub used_quota: Option<i64>,
+    pub quota_per_unit: f64,
+    pub balance_remaining: f64,
+    pub amount_used: Option<f64>,
+}
+
+impl QuotaSample {
+    fn new(station_id: String, recorded_at: i64, quota: i64, used_quota: Option<i64>, quota_per_unit: f64) -> Self {
+        Self {
+            station_id,
+            recorded_at,
+            quota,
+            used_quota,
+            quota_per_unit,
+            balance_remaining: quota as f64 / quota_per_unit,
+            amount_used: used_quota.map(|q| q as f64 / quota_per_unit),
+        }
+    }
+
+    /// Builds a sample from the raw values an adapter leaves in `UserInfo.metadata`
+    /// (`response.quota`, `response.used_quota` and `quota_per_unit`)
+    pub fn from_user_info(station_id: &str, user_info: &UserInfo, recorded_at: i64) -> Option<Self> {
+        let metadata = user_info.metadata.as_ref()?;
+        let response = metadata.get("response")?;
+        let quota = response["quota"].as_i64()?;
+        let quota_per_unit = metadata.get("quota_per_unit")?.as_f64().filter(|q| *q > 0.0)?;
+        Some(Self::new(station_id.to_string(), recorded_at, quota, response["used_quota"].as_i64(), quota_per_unit))
+    }
+}
+
+/// A station whose remaining balance is below the alert threshold
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BalanceAlert {
+    pub station_id: String,
+    pub station_name: String,
+    pub balance_remaining: f64, // In dollars, converted with the station's quota_per_unit
+}
+
+/// One station's share of `get_total_spend`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StationSpend {
+    pub station_id: String,
+    pub station_name: String,
+    pub quota: i64,
+    pub quota_per_unit: f64, // 0 when the adapter can't provide one
+    pub amount: f64, // In dollars
+    pub request_count: usize,
+    pub error: Option<String>, // Why the station's total is missing or incomplete
+}
+
+/// Spend across all enabled stations for a time range
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TotalSpend {
+    pub start: i64,
+    pub end: i64,
+    pub stations: Vec<StationSpend>,
+    pub total_amount: f64, // In dollars
+}
+
+/// Error share of a station's logged requests over a time window
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StationErrorRate {
+    pub station_id: String,
+    pub start: i64,
+    pub end: i64,
+    pub total: usize,
+    pub errors: usize,
+    pub error_rate: f64, // errors / total, 0 when there were no requests
+    pub type_counts: BTreeMap<String, usize>, // Raw upstream log `type` -> count
+    pub complete: bool, // False when the window held more entries than were scanned
+}
+
+/// Nearest-rank percentiles of `use_time` (seconds) over a set of log entries; `None` when
+/// no entry reported a response time
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LatencyPercentiles {
+    pub count: usize,
+    pub p50: Option<i64>,
+    pub p90: Option<i64>,
+    pub p99: Option<i64>,
+    pub max: Option<i64>,
+}
+
+/// Response-time distribution of a station's logged requests over a time window
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StationLatencyStats {
+    pub station_id: String,
+    pub start: i64,
+    pub end: i64,
+    pub overall: LatencyPercentiles,
+    pub streaming: LatencyPercentiles,
+    pub non_streaming: LatencyPercentiles, // Entries without `is_stream` only count towards `overall`
+    pub scanned: usize, // Log entries fetched, including those without a `use_time`
+    pub complete: bool, // False when the window held more entries than were scanned
+}
+
+/// One model's usage on one UTC day
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DailyUsage {
+    pub date: String, // YYYY-MM-DD
+    pub model_name: String,
+    pub request_count: i64,
+    pub token_used: i64,
+    pub quota: i64,
+}
+
+/// Per-day, per-model usage of a station over the last few days
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageDashboard {
+    pub station_id: String,
+    pub start: i64,
+    pub end: i64,
+    pub days: Vec<DailyUsage>,
+    pub source: String, // "dashboard" (the station's stats endpoint) or "logs" (aggregated from log pages)
+    pub complete: bool, // False when the log fallback hit its page limit
+}
+
+/// Folds (timestamp, model, requests, tokens, quota) rows into per-day, per-model totals,
+/// ordered by day and then model
+pub fn aggregate_daily_usage(rows: impl IntoIterator<Item = (i64, String, i64, i64, i64)>) -> Vec<DailyUsage> {
+    let mut totals: BTreeMap<(String, String), DailyUsage> = BTreeMap::new();
+    for (timestamp, model_name, request_count, token_used, quota) in rows {
+        let date = chrono::DateTime::from_timestamp(timestamp, 0).map(|dt| dt.format("%Y-%m-%d").to_string()).unwrap_or_default();
+        let entry = totals.entry((date.clone(), model_name.clone())).or_insert_with(|| DailyUsage {
+            date,
+            model_name,
+            request_count: 0,
+            token_used: 0,
+            quota: 0,
+        });
+        entry.request_count += request_count;
+        entry.token_used += token_used;
+        entry.quota += quota;
+    }
+    totals.into_values().collect()
+}
+
+/// Usage of one model within a token's usage summary
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelUsage {
+    pub model_name: String,
+    pub request_count: i64,
+    pub token_used: i64,
+    pub quota: i64,
+}
+
+/// A token's quota from its record, plus what its logs show over a time window
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenUsage {
+    pub station_id: String,
+    pub token_id: String,
+    pub token_name: String,
+    pub remain_quota: Option<i64>,
+    pub used_quota: Option<i64>,
+    pub unlimited_quota: Option<bool>,
+    pub start: i64,
+    pub end: i64,
+    pub request_count: i64,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    pub quota: i64, // Quota spent in the window, summed from the logs
+    pub models: Vec<ModelUsage>, // Ordered by model name
+    pub recent: Vec<StationLogEntry>, // Newest first
+    pub complete: bool, // False when the window held more entries than were scanned
+}
+
+/// Number of log entries returned in `TokenUsage::recent`
+const TOKEN_USAGE_RECENT_ENTRIES: usize = 20;
+
+/// Builds a token's usage summary from its record and the log entries in `start..=end`
+fn summarize_token_usage(token: &RelayStationToken, mut entries: Vec<StationLogEntry>, start: i64, end: i64, complete: bool) -> TokenUsage {
+    let used_quota = token.metadata.as_ref().and_then(|metadata| {
+        metadata
+            .get("used_quota")
+            .and_then(|v| v.as_i64())
+            .or_else(|| metadata.get("raw").and_then(|raw| raw["used_quota"].as_i64()))
+    });
+
+    let mut models: BTreeMap<String, ModelUsage> = BTreeMap::new();
+    let (mut prompt_tokens, mut completion_tokens, mut quota) = (0, 0, 0);
+    for entry in &entries {
+        let prompt = entry.prompt_tokens.unwrap_or(0);
+        let completion = entry.completion_tokens.unwrap_or(0);
+        let model_name = entry.model_name.clone().unwrap_or_default();
+        let model = models.entry(model_name.clone()).or_insert_with(|| ModelUsage {
+            model_name,
+            request_count: 0,
+            token_used: 0,
+            quota: 0,
+        });
+        model.request_count += 1;
+        model.token_used += prompt + completion;
+        model.quota += entry.quota.unwrap_or(0);
+        prompt_tokens += prompt;
+        completion_tokens += completion;
+        quota += entry.quota.unwrap_or(0);
+    }
+
+    let request_count = entries.len() as i64;
+    entries.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+    entries.truncate(TOKEN_USAGE_RECENT_ENTRIES);
+    TokenUsage {
+        station_id: token.station_id.clone(),
+        token_id: token.id.clone(),
+        token_name: token.name.clone(),
+        remain_quota: token.remain_quota,
+        used_quota,
+        unlimited_quota: token.unlimited_quota,
+        start,
+        end,
+        request_count,
+        prompt_tokens,
+        completion_tokens,
+        quota,
+        models: models.into_values().collect(),
+        recent: entries,
+        complete,
+    }
+}
+
+/// Token configuration for a relay station
+#[derive(Clone, Serialize, Deserialize)]
 pub struct RelayStationToken {
     pub id: String,
     pub station_id: String,
@@ -88,6 +511,52 @@ pub struct RelayStationToken {
     pub created_at: i64,
 }
 
+/// Debug output masks the token key so tokens can be logged safely
+impl std::fmt::Debug for RelayStationToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RelayStationToken")
+            .field("id", &self.id)
+            .field("station_id", &self.station_id)
+            .field("name", &self.name)
+            .field("token", &redact(&self.token))
+            .field("user_id", &self.user_id)
+            .field("enabled", &self.enabled)
+            .field("expires_at", &self.expires_at)
+            .field("group", &self.group)
+            .field("remain_quota", &self.remain_quota)
+            .field("unlimited_quota", &self.unlimited_quota)
+            .field("metadata", &self.metadata)
+            .field("created_at", &self.created_at)
+            .finish()
+    }
+}
+
+impl RelayStationToken {
+    /// Expiry timestamp, treating None and the upstream `-1` sentinel as never expiring
+    fn expiry(&self) -> Option<i64> {
+        self.expires_at.filter(|&t| t > 0)
+    }
+
+    /// Whether the token's expiry time has passed at `now` (Unix seconds)
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expiry().is_some_and(|t| t <= now)
+    }
+
+    /// Whole days until expiry at `now`, negative once expired, or None if the token never expires
+    pub fn expires_in_days(&self, now: i64) -> Option<i64> {
+        self.expiry().map(|t| (t - now).div_euclid(24 * 60 * 60))
+    }
+
+    /// Records `is_expired` and `expires_in_days` in the metadata so the UI can highlight tokens
+    pub fn annotate_expiry(&mut self, now: i64) {
+        let is_expired = self.is_expired(now);
+        let expires_in_days = self.expires_in_days(now);
+        let metadata = self.metadata.get_or_insert_with(HashMap::new);
+        metadata.insert("is_expired".to_string(), serde_json::Value::Bool(is_expired));
+        metadata.insert("expires_in_days".to_string(), serde_json::json!(expires_in_days));
+    }
+}
+
 /// User information retrieved from a relay station
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserInfo {
@@ -130,6 +599,19 @@ pub struct LogPaginationResponse {
     pub page: usize,
     pub page_size: usize,
     pub total: i64,
+    #[serde(default)]
+    pub next_cursor: Option<String>, // Pass back as `cursor` to fetch the next page; None when offset paging
+}
+
+/// One page of `search_station_logs` matches
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogSearchResponse {
+    pub items: Vec<StationLogEntry>,
+    pub page: usize,
+    pub page_size: usize,
+    pub total: usize, // Matches across all pages
+    pub scanned: usize, // Log entries searched
+    pub complete: bool, // False when the window held more entries than the scan cap
 }
 
 /// Token pagination response
@@ -151,6 +633,17 @@ pub struct ConnectionTestResult {
     pub details: Option<HashMap<String, serde_json::Value>>,
 }
 
+/// Result of checking a token key against its station
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenTestResult {
+    pub valid: bool,
+    pub response_time: Option<u64>,
+    pub status_code: Option<u16>,
+    pub message: String,
+    pub remain_quota: Option<i64>, // Only when the station reports it
+    pub unlimited_quota: Option<bool>,
+}
+
 /// Request structure for creating a new token
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateTokenRequest {
@@ -160,8 +653,12 @@ pub struct CreateTokenRequest {
     pub unlimited_quota: Option<bool>,
     pub model_limits_enabled: Option<bool>,
     pub model_limits: Option<String>,
+    /// Model ids to limit the token to; preferred over `model_limits` when both are set
+    pub model_limits_list: Option<Vec<String>>,
     pub group: Option<String>,
     pub allow_ips: Option<String>,
+    /// Identifies one logical create across retries; generated by `add_station_token` when unset
+    pub idempotency_key: Option<String>,
 }
 
 /// Request structure for updating an existing token
@@ -174,28 +671,226 @@ pub struct UpdateTokenRequest {
     pub unlimited_quota: Option<bool>,
     pub model_limits_enabled: Option<bool>,
     pub model_limits: Option<String>,
+    /// Model ids to limit the token to; preferred over `model_limits` when both are set
+    pub model_limits_list: Option<Vec<String>>,
     pub group: Option<String>,
     pub allow_ips: Option<String>,
     pub enabled: Option<bool>,
 }
 
+/// Counts from mirroring a station's upstream tokens into the local store
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TokenSyncSummary {
+    pub added: usize,
+    pub updated: usize,
+    pub removed: usize,
+}
+
+/// Outcome of a single item in a batch operation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchItemResult {
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+impl From<Result<()>> for BatchItemResult {
+    fn from(result: Result<()>) -> Self {
+        match result {
+            Ok(()) => BatchItemResult { success: true, error: None },
+            Err(e) => BatchItemResult { success: false, error: Some(e.to_string()) },
+        }
+    }
+}
+
+/// Maximum number of concurrent upstream requests for batch token operations
+pub const BATCH_CONCURRENCY: usize = 5;
+
+/// Default number of stations probed at once by `test_all_station_connections`
+pub const CONNECTION_TEST_CONCURRENCY: usize = 8;
+
+/// How long a station's model list is reused before it is fetched again
+const MODELS_CACHE_TTL: Duration = Duration::from_secs(120);
+
+/// How long a station's group list is reused before it is fetched again
+const GROUPS_CACHE_TTL: Duration = Duration::from_secs(120);
+
+/// Stations currently below the balance threshold, so `balance-low` fires once per crossing
+static LOW_BALANCE_STATIONS: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+/// Default and minimum polling intervals for live log streams
+const DEFAULT_LOG_POLL_INTERVAL_SECS: u64 = 5;
+const MIN_LOG_POLL_INTERVAL_SECS: u64 = 2;
+
+/// Number of newest log entries fetched per poll
+const LOG_STREAM_PAGE_SIZE: usize = 50;
+
+/// Running log streams keyed by station id; cancelling the token ends the stream's task
+static LOG_STREAMS: Lazy<Mutex<HashMap<String, CancellationToken>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// In-flight cancellable operations keyed by the operation id their caller supplied
+static OPERATIONS: Lazy<Mutex<HashMap<String, CancellationToken>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Cached model lists keyed by station id
+static MODELS_CACHE: Lazy<Mutex<HashMap<String, (Instant, Vec<String>)>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Cached token groups keyed by station id
+static GROUPS_CACHE: Lazy<Mutex<HashMap<String, (Instant, Vec<GroupInfo>)>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Default lifetime of a cached `get_station_info` response, overridable per station with
+/// `station_info_ttl_secs` in its adapter_config (0 disables caching)
+const DEFAULT_STATION_INFO_TTL_SECS: u64 = 60;
+
+/// Cached station info keyed by station id
+static STATION_INFO_CACHE: Lazy<Mutex<HashMap<String, (Instant, StationInfo)>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn station_info_ttl(station: &RelayStation) -> Duration {
+    let secs = station
+        .adapter_config
+        .as_ref()
+        .and_then(|config| config.get("station_info_ttl_secs"))
+        .and_then(|v| v.as_u64())
+        .unwrap_or(DEFAULT_STATION_INFO_TTL_SECS);
+    Duration::from_secs(secs)
+}
+
+/// Returns the cached station info if it is younger than `ttl`
+fn cached_station_info(station_id: &str, ttl: Duration) -> Option<StationInfo> {
+    let cache = STATION_INFO_CACHE.lock().ok()?;
+    cache
+        .get(station_id)
+        .filter(|(fetched_at, _)| fetched_at.elapsed() < ttl)
+        .map(|(_, info)| info.clone())
+}
+
+/// Drops the cached station info so the next `get_station_info` hits the station
+pub(crate) fn invalidate_station_info(station_id: &str) {
+    if let Ok(mut cache) = STATION_INFO_CACHE.lock() {
+        cache.remove(station_id);
+    }
+    http::forget_conditional(station_id);
+}
+
+// Cache invalidation. Per-station in-memory state is STATION_INFO_CACHE, the http conditional
+// GET cache, MODELS_CACHE, GROUPS_CACHE and LOW_BALANCE_STATIONS (so a reconfigured station can
+// alert again).
+// - update_relay_station, delete_relay_station, rotate_system_token and an overwriting config
+//   bundle import clear all of it for that station (`invalidate_station_caches`)
+// - clear_all_caches and a backup restore clear it for every station (`clear_station_caches`)
+// - refresh_station_info only bypasses the station info cache
+
+/// Drops everything cached for one station, after its configuration changed or it was deleted
+pub(crate) fn invalidate_station_caches(station_id: &str) {
+    invalidate_station_info(station_id);
+    if let Ok(mut cache) = MODELS_CACHE.lock() {
+        cache.remove(station_id);
+    }
+    if let Ok(mut cache) = GROUPS_CACHE.lock() {
+        cache.remove(station_id);
+    }
+    if let Ok(mut low_stations) = LOW_BALANCE_STATIONS.lock() {
+        low_stations.remove(station_id);
+    }
+}
+
+/// Drops every station's cached state
+pub(crate) fn clear_station_caches() {
+    if let Ok(mut cache) = STATION_INFO_CACHE.lock() {
+        cache.clear();
+    }
+    if let Ok(mut cache) = MODELS_CACHE.lock() {
+        cache.clear();
+    }
+    if let Ok(mut cache) = GROUPS_CACHE.lock() {
+        cache.clear();
+    }
+    if let Ok(mut low_stations) = LOW_BALANCE_STATIONS.lock() {
+        low_stations.clear();
+    }
+    http::forget_all_conditional();
+}
+
+/// Features an adapter can actually serve, so the UI can hide the rest instead of showing errors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StationCapabilities {
+    pub supports_tokens: bool,
+    pub supports_logs: bool,
+    pub supports_user_info: bool,
+    pub supports_models: bool,
+}
+
+/// Privilege level of a station's `system_token`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenScope {
+    Admin,
+    User, // Accepted, but admin-only endpoints are refused
+    Unknown, // The probe was rejected or inconclusive
+}
+
+/// A group tokens can be assigned to, with the price multiplier the station applies to it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GroupInfo {
+    pub name: String,
+    pub ratio: f64, // 1.0 when the station doesn't report one
+    pub description: Option<String>,
+}
+
 /// Adapter trait for different relay station implementations
 #[async_trait::async_trait]
 pub trait StationAdapter: Send + Sync {
+    /// Which optional features this adapter implements
+    fn capabilities(&self) -> StationCapabilities;
     async fn get_station_info(&self, station: &RelayStation) -> Result<StationInfo>;
     async fn get_user_info(&self, station: &RelayStation, user_id: &str) -> Result<UserInfo>;
-    async fn get_logs(&self, station: &RelayStation, page: Option<usize>, page_size: Option<usize>, filters: Option<serde_json::Value>) -> Result<LogPaginationResponse>;
-    async fn test_connection(&self, station: &RelayStation) -> Result<ConnectionTestResult>;
+    /// Fetches a page of logs. `cursor` (a previous `next_cursor`) replaces `page` on stations that support keyset paging.
+    async fn get_logs(&self, station: &RelayStation, page: Option<usize>, page_size: Option<usize>, cursor: Option<&str>, filters: Option<serde_json::Value>) -> Result<LogPaginationResponse>;
+    /// Probes the station, giving each request at most `timeout`
+    async fn test_connection(&self, station: &RelayStation, timeout: Duration) -> Result<ConnectionTestResult>;
+    /// Checks that the station accepts `token_key` (not the system token) for API calls
+    async fn test_token(&self, station: &RelayStation, token_key: &str) -> Result<TokenTestResult>;
     
     // Token management methods
     async fn list_tokens(&self, station: &RelayStation, page: Option<usize>, size: Option<usize>) -> Result<TokenPaginationResponse>;
     async fn create_token(&self, station: &RelayStation, token_data: &CreateTokenRequest) -> Result<RelayStationToken>;
+    /// The request body `create_token` would send, with all defaults applied
+    fn preview_create_token(&self, station: &RelayStation, token_data: &CreateTokenRequest) -> Result<serde_json::Value>;
     async fn update_token(&self, station: &RelayStation, token_id: &str, token_data: &UpdateTokenRequest) -> Result<RelayStationToken>;
     async fn delete_token(&self, station: &RelayStation, token_id: &str) -> Result<()>;
-    async fn toggle_token(&self, station: &RelayStation, token_id: &str, enabled: bool) -> Result<RelayStationToken>;
+    async fn delete_tokens(&self, station: &RelayStation, token_ids: &[String]) -> HashMap<String, BatchItemResult> {
+        stream::iter(token_ids.iter().cloned())
+            .map(|token_id| async move {
+                let result = self.delete_token(station, &token_id).await;
+                (token_id, BatchItemResult::from(result))
+            })
+            .buffer_unordered(BATCH_CONCURRENCY)
+            .collect()
+            .await
+    }
+    /// Enables or disables a token upstream, leaving its quota and limits untouched
+    async fn set_token_enabled(&self, station: &RelayStation, token_id: &str, enabled: bool) -> Result<RelayStationToken>;
     
     // User groups management
     async fn get_user_groups(&self, station: &RelayStation) -> Result<serde_json::Value>;
+
+    /// Model identifiers offered by the station
+    async fn get_models(&self, station: &RelayStation) -> Result<Vec<String>>;
+
+    /// Groups a token's `group` field can be set to, sorted by name
+    async fn get_station_groups(&self, station: &RelayStation) -> Result<Vec<GroupInfo>>;
+
+    /// Checks that the station accepts `station.system_token`, used before rotating it
+    async fn validate_system_token(&self, station: &RelayStation) -> Result<()>;
+
+    /// Probes an admin-only endpoint to tell whether `station.system_token` is admin-scoped
+    async fn check_token_scope(&self, _station: &RelayStation) -> Result<TokenScope> {
+        Ok(TokenScope::Unknown)
+    }
+
+    /// Per-day, per-model usage between `start` and `end` from the station's stats endpoint.
+    /// Unsupported errors make callers fall back to aggregating logs.
+    async fn get_usage_dashboard(&self, _station: &RelayStation, _start: i64, _end: i64) -> Result<Vec<DailyUsage>> {
+        Err(RelayError::unsupported("Usage dashboard not available for this adapter").into())
+    }
 }
 
 
@@ -203,207 +898,794 @@ pub trait StationAdapter: Send + Sync {
 pub fn create_adapter(adapter_type: &RelayStationAdapter) -> Box<dyn StationAdapter> {
     match adapter_type {
         RelayStationAdapter::Newapi => Box::new(NewApiAdapter),
-        RelayStationAdapter::Oneapi => Box::new(NewApiAdapter), // OneAPI is compatible with NewAPI
+        RelayStationAdapter::Oneapi => Box::new(OneApiAdapter::new()),
         RelayStationAdapter::Yourapi => Box::new(YourApiAdapter::new()),
         RelayStationAdapter::Custom => Box::new(CustomAdapter), // Custom adapter for simple configurations
     }
 }
 
-/// Database manager for relay stations
-pub struct RelayStationManager {
-    db: Arc<Mutex<Connection>>,
-}
+/// Normalizes a relay station api_url before it is stored.
+///
+/// Trims whitespace and trailing slashes, strips a trailing `/api` segment (adapters append
+/// `/api/...` themselves) and requires an absolute http(s) URL with a host.
+pub fn normalize_api_url(api_url: &str) -> Result<String, RelayError> {
+    let mut normalized = api_url.trim().trim_end_matches('/').to_string();
+    if normalized.to_lowercase().ends_with("/api") {
+        normalized.truncate(normalized.len() - "/api".len());
+        normalized = normalized.trim_end_matches('/').to_string();
+    }
 
-use std::sync::Arc;
+    let parsed = reqwest::Url::parse(&normalized)
+        .map_err(|_| RelayError::invalid_input(format!("Invalid API URL '{}': expected a full URL such as https://example.com", api_url.trim())))?;
 
-impl RelayStationManager {
-    pub fn new(db: Arc<Mutex<Connection>>) -> Result<Self> {
-        let manager = Self { db };
-        manager.init_tables()?;
-        Ok(manager)
+    if parsed.scheme() != "http" && parsed.scheme() != "https" {
+        return Err(RelayError::invalid_input(format!("Invalid API URL '{}': scheme must be http or https", api_url.trim())));
+    }
+    if parsed.host_str().map_or(true, |host| host.is_empty()) {
+        return Err(RelayError::invalid_input(format!("Invalid API URL '{}': missing host", api_url.trim())));
     }
 
-    fn init_tables(&self) -> Result<()> {
-        let conn = self.db.lock().unwrap();
-        
-        // Create relay_stations table
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS relay_stations (
-                id TEXT PRIMARY KEY,
-                name TEXT NOT NULL,
-                description TEXT,
-                api_url TEXT NOT NULL,
-                adapter TEXT NOT NULL,
-                auth_method TEXT NOT NULL,
-                system_token TEXT NOT NULL,
-                user_id TEXT,
-                adapter_config TEXT,
-                enabled INTEGER NOT NULL DEFAULT 1,
-                created_at INTEGER NOT NULL,
-                updated_at INTEGER NOT NULL
-            )",
-            [],
-        )?;
-
-        // Add user_id column if it doesn't exist (for existing databases)
-        let _ = conn.execute(
-            "ALTER TABLE relay_stations ADD COLUMN user_id TEXT",
-            [],
-        );
+    Ok(normalized)
+}
 
-        // Create relay_station_tokens table
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS relay_station_tokens (
-                id TEXT PRIMARY KEY,
-                station_id TEXT NOT NULL,
-                name TEXT NOT NULL,
-                token TEXT NOT NULL,
-                user_id TEXT,
-                enabled INTEGER NOT NULL DEFAULT 1,
-                expires_at INTEGER,
-                metadata TEXT,
-                created_at INTEGER NOT NULL,
-                FOREIGN KEY (station_id) REFERENCES relay_stations (id) ON DELETE CASCADE
-            )",
-            [],
-        )?;
+/// Normalizes a token's `allow_ips` list before it is sent upstream.
+///
+/// Accepts entries separated by newlines or commas; each must be an IPv4/IPv6 address or CIDR.
+/// Returns the entries joined by newlines, which is what NewAPI splits on.
+pub fn normalize_allow_ips(allow_ips: &str) -> Result<String, RelayError> {
+    let mut entries = Vec::new();
+    for entry in allow_ips.split([',', '\n', '\r']).map(str::trim).filter(|e| !e.is_empty()) {
+        let valid = match entry.split_once('/') {
+            Some((addr, prefix)) => match (addr.parse::<IpAddr>(), prefix.parse::<u8>()) {
+                (Ok(IpAddr::V4(_)), Ok(prefix)) => prefix <= 32,
+                (Ok(IpAddr::V6(_)), Ok(prefix)) => prefix <= 128,
+                _ => false,
+            },
+            None => entry.parse::<IpAddr>().is_ok(),
+        };
+        if !valid {
+            return Err(RelayError::invalid_input(format!("Invalid allow_ips entry '{}': expected an IP address or CIDR", entry)));
+        }
+        entries.push(entry);
+    }
+    Ok(entries.join("\n"))
+}
 
-        // Create indexes
-        conn.execute("CREATE INDEX IF NOT EXISTS idx_station_tokens_station_id ON relay_station_tokens(station_id)", [])?;
-        conn.execute("CREATE INDEX IF NOT EXISTS idx_station_tokens_enabled ON relay_station_tokens(enabled)", [])?;
+/// Rejects `model_limits_list` entries the station doesn't serve. Skipped when the station's
+/// model list can't be fetched or is empty, since not every station exposes one.
+async fn check_model_limits(app: &AppHandle, station_id: &str, models: Option<&[String]>) -> Result<(), RelayError> {
+    let Some(requested) = models.filter(|m| !m.is_empty()) else {
+        return Ok(());
+    };
+    let served = match get_station_models(station_id.to_string(), app.clone()).await {
+        Ok(served) if !served.is_empty() => served,
+        Ok(_) => return Ok(()),
+        Err(e) => {
+            log::warn!("Skipping model_limits check for station {}: {}", station_id, e);
+            return Ok(());
+        }
+    };
 
+    let unknown: Vec<&str> = requested
+        .iter()
+        .map(|m| m.trim())
+        .filter(|m| !m.is_empty() && !served.iter().any(|s| s == m))
+        .collect();
+    if unknown.is_empty() {
         Ok(())
+    } else {
+        Err(RelayError::invalid_input(format!("Models not served by this station: {}", unknown.join(", "))))
     }
+}
 
-    pub fn list_stations(&self) -> Result<Vec<RelayStation>> {
-        let conn = self.db.lock().unwrap();
-        let mut stmt = conn.prepare("SELECT * FROM relay_stations ORDER BY created_at DESC")?;
-        
-        let station_iter = stmt.query_map([], |row| {
-            let adapter_config_str: Option<String> = row.get("adapter_config")?;
-            let adapter_config = if let Some(config_str) = adapter_config_str {
-                serde_json::from_str(&config_str).ok()
-            } else {
-                None
-            };
-
-            Ok(RelayStation {
-                id: row.get("id")?,
-                name: row.get("name")?,
-                description: row.get("description")?,
-                api_url: row.get("api_url")?,
-                adapter: match row.get::<_, String>("adapter")?.as_str() {
-                    "newapi" => RelayStationAdapter::Newapi,
-                    "oneapi" => RelayStationAdapter::Oneapi,
-                    "yourapi" => RelayStationAdapter::Yourapi,
-                    "custom" => RelayStationAdapter::Custom,
-                    _ => RelayStationAdapter::Newapi,
-                },
-                auth_method: match row.get::<_, String>("auth_method")?.as_str() {
-                    "bearer_token" => AuthMethod::BearerToken,
-                    "api_key" => AuthMethod::ApiKey,
-                    "custom" => AuthMethod::Custom,
-                    _ => AuthMethod::BearerToken,
-                },
-                system_token: row.get("system_token")?,
-                user_id: row.get("user_id")?,
-                adapter_config,
-                enabled: row.get::<_, i32>("enabled")? != 0,
-                created_at: row.get("created_at")?,
-                updated_at: row.get("updated_at")?,
-            })
-        })?;
+fn normalize_allow_ips_field(allow_ips: &mut Option<String>) -> Result<(), RelayError> {
+    if let Some(ips) = allow_ips {
+        *ips = normalize_allow_ips(ips)?;
+    }
+    Ok(())
+}
 
-        station_iter.collect::<Result<Vec<_>, _>>().map_err(|e| anyhow!("Database error: {}", e))
+/// Trims and lowercases tags, dropping empty ones and duplicates while keeping order
+pub fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.trim().to_lowercase();
+        if !tag.is_empty() && !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
     }
+    normalized
+}
 
-    pub fn add_station(&self, station: &RelayStation) -> Result<()> {
-        let conn = self.db.lock().unwrap();
-        
-        let adapter_config_str = if let Some(config) = &station.adapter_config {
-            Some(serde_json::to_string(config)?)
-        } else {
-            None
-        };
+fn adapter_str(adapter: &RelayStationAdapter) -> &'static str {
+    match adapter {
+        RelayStationAdapter::Newapi => "newapi",
+        RelayStationAdapter::Oneapi => "oneapi",
+        RelayStationAdapter::Yourapi => "yourapi",
+        RelayStationAdapter::Custom => "custom",
+    }
+}
+
+fn auth_method_str(auth_method: &AuthMethod) -> &'static str {
+    match auth_method {
+        AuthMethod::BearerToken => "bearer_token",
+        AuthMethod::ApiKey => "api_key",
+        AuthMethod::Custom => "custom",
+    }
+}
+
+/// Maps a `relay_stations` row to a `RelayStation`
+fn station_from_row(row: &rusqlite::Row) -> rusqlite::Result<RelayStation> {
+    let adapter_config_str: Option<String> = row.get("adapter_config")?;
+    let mut config_parse_error = false;
+    let adapter_config = match adapter_config_str {
+        Some(config_str) => match serde_json::from_str(&config_str) {
+            Ok(config) => config,
+            Err(e) => {
+                // Keep listing the station, but flag it: proxy, headers and other settings are lost until fixed
+                let id: String = row.get("id")?;
+                log::warn!("Ignoring corrupt adapter_config for station {}: {}", id, e);
+                config_parse_error = true;
+                None
+            }
+        },
+        None => None,
+    };
+
+    let tags_str: Option<String> = row.get("tags")?;
+    let tags = tags_str
+        .and_then(|tags_str| serde_json::from_str(&tags_str).ok())
+        .unwrap_or_default();
+    let api_url: String = row.get("api_url")?;
+
+    Ok(RelayStation {
+        id: row.get("id")?,
+        name: row.get("name")?,
+        description: row.get("description")?,
+        is_secure: is_secure_url(&api_url),
+        api_url,
+        adapter: match row.get::<_, String>("adapter")?.as_str() {
+            "newapi" => RelayStationAdapter::Newapi,
+            "oneapi" => RelayStationAdapter::Oneapi,
+            "yourapi" => RelayStationAdapter::Yourapi,
+            "custom" => RelayStationAdapter::Custom,
+            _ => RelayStationAdapter::Newapi,
+        },
+        auth_method: match row.get::<_, String>("auth_method")?.as_str() {
+            "bearer_token" => AuthMethod::BearerToken,
+            "api_key" => AuthMethod::ApiKey,
+            "custom" => AuthMethod::Custom,
+            _ => AuthMethod::BearerToken,
+        },
+        system_token: row.get("system_token")?,
+        user_id: row.get("user_id")?,
+        adapter_config,
+        enabled: row.get::<_, i32>("enabled")? != 0,
+        tags,
+        created_at: row.get("created_at")?,
+        updated_at: row.get("updated_at")?,
+        deleted_at: row.get("deleted_at")?,
+        last_used_at: row.get("last_used_at")?,
+        last_tested_at: row.get("last_tested_at")?,
+        config_parse_error,
+    })
+}
+
+/// Maps a `relay_station_tokens` row to a `RelayStationToken`
+fn token_from_row(row: &rusqlite::Row) -> rusqlite::Result<RelayStationToken> {
+    let metadata_str: Option<String> = row.get("metadata")?;
+    let metadata = if let Some(meta_str) = metadata_str {
+        serde_json::from_str(&meta_str).ok()
+    } else {
+        None
+    };
+
+    Ok(RelayStationToken {
+        id: row.get("id")?,
+        station_id: row.get("station_id")?,
+        name: row.get("name")?,
+        token: row.get("token")?,
+        user_id: row.get("user_id")?,
+        enabled: row.get::<_, i32>("enabled")? != 0,
+        expires_at: row.get("expires_at")?,
+        group: row.get("group_name")?,
+        remain_quota: row.get("remain_quota")?,
+        unlimited_quota: None, // Database doesn't store quota settings, they come from API
+        metadata,
+        created_at: row.get("created_at")?,
+    })
+}
+
+/// Inserts or replaces a token row, clearing any soft delete.
+///
+/// The quota columns mirror the metadata blob so they can be queried; `remain_quota` is left
+/// NULL for unlimited tokens since it doesn't apply to them.
+pub(crate) fn upsert_token(conn: &Connection, token: &RelayStationToken) -> Result<()> {
+    let metadata_str = token.metadata.as_ref().map(serde_json::to_string).transpose()?;
+    let used_quota = token.metadata.as_ref().and_then(|metadata| metadata.get("used_quota")).and_then(|v| v.as_i64());
+    let remain_quota = token.remain_quota.filter(|_| token.unlimited_quota != Some(true));
+
+    conn.execute(
+        "INSERT OR REPLACE INTO relay_station_tokens
+            (id, station_id, name, token, user_id, enabled, expires_at, metadata, created_at, deleted_at, used_quota, remain_quota, group_name)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, NULL, ?10, ?11, ?12)",
+        params![
+            token.id,
+            token.station_id,
+            token.name,
+            token.token,
+            token.user_id,
+            if token.enabled { 1 } else { 0 },
+            token.expires_at,
+            metadata_str,
+            token.created_at,
+            used_quota,
+            remain_quota,
+            token.group,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Inserts a new station row
+pub(crate) fn insert_station(conn: &Connection, station: &RelayStation) -> Result<()> {
+    let adapter_config_str = if let Some(config) = &station.adapter_config {
+        Some(serde_json::to_string(config)?)
+    } else {
+        None
+    };
+    let tags_str = serde_json::to_string(&normalize_tags(&station.tags))?;
+
+    conn.execute(
+        "INSERT INTO relay_stations (id, name, description, api_url, adapter, auth_method, system_token, user_id, adapter_config, enabled, tags, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+        params![
+            station.id,
+            station.name,
+            station.description,
+            station.api_url,
+            adapter_str(&station.adapter),
+            auth_method_str(&station.auth_method),
+            station.system_token,
+            station.user_id,
+            adapter_config_str,
+            if station.enabled { 1 } else { 0 },
+            tags_str,
+            station.created_at,
+            station.updated_at,
+        ],
+    )?;
+
+    Ok(())
+}
+
+/// Overwrites every stored field of an existing station, restoring it if it was in the recycle bin
+pub(crate) fn overwrite_station(conn: &Connection, station: &RelayStation) -> Result<()> {
+    let adapter_config_str = if let Some(config) = &station.adapter_config {
+        Some(serde_json::to_string(config)?)
+    } else {
+        None
+    };
+    let tags_str = serde_json::to_string(&normalize_tags(&station.tags))?;
+
+    conn.execute(
+        "UPDATE relay_stations SET name = ?2, description = ?3, api_url = ?4, adapter = ?5, auth_method = ?6,
+            system_token = ?7, user_id = ?8, adapter_config = ?9, enabled = ?10, tags = ?11,
+            created_at = ?12, updated_at = ?13, deleted_at = NULL
+         WHERE id = ?1",
+        params![
+            station.id,
+            station.name,
+            station.description,
+            station.api_url,
+            adapter_str(&station.adapter),
+            auth_method_str(&station.auth_method),
+            station.system_token,
+            station.user_id,
+            adapter_config_str,
+            if station.enabled { 1 } else { 0 },
+            tags_str,
+            station.created_at,
+            Utc::now().timestamp(),
+        ],
+    )?;
+
+    Ok(())
+}
+
+/// A live (not soft-deleted) station by id
+pub(crate) fn select_station(conn: &Connection, station_id: &str) -> Result<Option<RelayStation>> {
+    Ok(select_station_including_deleted(conn, station_id)?.filter(|station| station.deleted_at.is_none()))
+}
+
+/// A station by id, including one in the recycle bin. Only for paths that write over the
+/// row whatever its state, such as bundle import; everything else uses `select_station`.
+pub(crate) fn select_station_including_deleted(conn: &Connection, station_id: &str) -> Result<Option<RelayStation>> {
+    let mut stmt = conn.prepare("SELECT * FROM relay_stations WHERE id = ?1")?;
+
+    let mut station_iter = stmt.query_map([station_id], station_from_row)?;
+
+    match station_iter.next() {
+        Some(station) => Ok(Some(station?)),
+        None => Ok(None),
+    }
+}
+
+/// A live (not soft-deleted) token by station and upstream id
+pub(crate) fn select_token(conn: &Connection, station_id: &str, token_id: &str) -> Result<Option<RelayStationToken>> {
+    let mut stmt = conn.prepare("SELECT * FROM relay_station_tokens WHERE station_id = ?1 AND id = ?2 AND deleted_at IS NULL")?;
+
+    let mut token_iter = stmt.query_map([station_id, token_id], token_from_row)?;
+
+    match token_iter.next() {
+        Some(token) => Ok(Some(token?)),
+        None => Ok(None),
+    }
+}
+
+/// Escapes `%`, `_` and `\` so user input is matched literally inside a LIKE pattern
+fn escape_like(query: &str) -> String {
+    query.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+/// A schema migration, run inside a transaction
+type Migration = fn(&Connection) -> rusqlite::Result<()>;
+
+/// Schema migrations in order; the database's schema version is the number applied so far.
+/// Append new migrations to the end and never edit one that has shipped.
+const MIGRATIONS: &[Migration] = &[
+    // 1: initial relay station and token tables
+    |conn| {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS relay_stations (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                description TEXT,
+                api_url TEXT NOT NULL,
+                adapter TEXT NOT NULL,
+                auth_method TEXT NOT NULL,
+                system_token TEXT NOT NULL,
+                user_id TEXT,
+                adapter_config TEXT,
+                enabled INTEGER NOT NULL DEFAULT 1,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            )",
+            [],
+        )?;
+        // Databases created before user_id existed
+        add_column_if_missing(conn, "relay_stations", "user_id", "TEXT")?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS relay_station_tokens (
+                id TEXT PRIMARY KEY,
+                station_id TEXT NOT NULL,
+                name TEXT NOT NULL,
+                token TEXT NOT NULL,
+                user_id TEXT,
+                enabled INTEGER NOT NULL DEFAULT 1,
+                expires_at INTEGER,
+                metadata TEXT,
+                created_at INTEGER NOT NULL,
+                FOREIGN KEY (station_id) REFERENCES relay_stations (id) ON DELETE CASCADE
+            )",
+            [],
+        )?;
+
+        conn.execute("CREATE INDEX IF NOT EXISTS idx_station_tokens_station_id ON relay_station_tokens(station_id)", [])?;
+        conn.execute("CREATE INDEX IF NOT EXISTS idx_station_tokens_enabled ON relay_station_tokens(enabled)", [])?;
+        Ok(())
+    },
+    // 2: recycle bin
+    |conn| add_column_if_missing(conn, "relay_stations", "deleted_at", "INTEGER"),
+    // 3: station tags (JSON array of strings)
+    |conn| add_column_if_missing(conn, "relay_stations", "tags", "TEXT"),
+    // 4: balance snapshots for quota charts
+    |conn| {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS station_quota_history (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                station_id TEXT NOT NULL,
+                recorded_at INTEGER NOT NULL,
+                quota INTEGER NOT NULL,
+                used_quota INTEGER,
+                quota_per_unit REAL NOT NULL,
+                FOREIGN KEY (station_id) REFERENCES relay_stations (id) ON DELETE CASCADE
+            )",
+            [],
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_quota_history_station_time ON station_quota_history(station_id, recorded_at)",
+            [],
+        )?;
+        Ok(())
+    },
+    // 5: key tokens by (station_id, id) since upstream ids are only unique per station,
+    // and soft-delete tokens that disappear upstream during a sync
+    |conn| {
+        add_column_if_missing(conn, "relay_station_tokens", "deleted_at", "INTEGER")?;
+        conn.execute_batch(
+            "CREATE TABLE relay_station_tokens_v5 (
+                id TEXT NOT NULL,
+                station_id TEXT NOT NULL,
+                name TEXT NOT NULL,
+                token TEXT NOT NULL,
+                user_id TEXT,
+                enabled INTEGER NOT NULL DEFAULT 1,
+                expires_at INTEGER,
+                metadata TEXT,
+                created_at INTEGER NOT NULL,
+                deleted_at INTEGER,
+                PRIMARY KEY (station_id, id),
+                FOREIGN KEY (station_id) REFERENCES relay_stations (id) ON DELETE CASCADE
+            );
+            INSERT INTO relay_station_tokens_v5 (id, station_id, name, token, user_id, enabled, expires_at, metadata, created_at, deleted_at)
+                SELECT id, station_id, name, token, user_id, enabled, expires_at, metadata, created_at, deleted_at FROM relay_station_tokens;
+            DROP TABLE relay_station_tokens;
+            ALTER TABLE relay_station_tokens_v5 RENAME TO relay_station_tokens;
+            CREATE INDEX IF NOT EXISTS idx_station_tokens_station_id ON relay_station_tokens(station_id);
+            CREATE INDEX IF NOT EXISTS idx_station_tokens_enabled ON relay_station_tokens(enabled);",
+        )
+    },
+    // 6: token quota fields as columns (also kept in metadata) so low-quota tokens can be queried
+    |conn| {
+        add_column_if_missing(conn, "relay_station_tokens", "used_quota", "INTEGER")?;
+        add_column_if_missing(conn, "relay_station_tokens", "remain_quota", "INTEGER")?;
+        add_column_if_missing(conn, "relay_station_tokens", "group_name", "TEXT")?;
+        // Backfill from the metadata blob written by earlier versions
+        conn.execute(
+            "UPDATE relay_station_tokens SET
+                used_quota = CASE WHEN json_type(metadata, '$.used_quota') = 'integer'
+                                  THEN json_extract(metadata, '$.used_quota') END,
+                remain_quota = CASE WHEN json_type(metadata, '$.remain_quota') = 'integer'
+                                     AND json_type(metadata, '$.raw.unlimited_quota') IS NOT 'true'
+                                    THEN json_extract(metadata, '$.remain_quota') END,
+                group_name = CASE WHEN json_type(metadata, '$.group') = 'text'
+                                  THEN json_extract(metadata, '$.group') END
+             WHERE json_valid(metadata)",
+            [],
+        )?;
+        Ok(())
+    },
+    // 7: connection test results, so stations can be sorted by their last latency
+    |conn| {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS station_health_history (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                station_id TEXT NOT NULL,
+                recorded_at INTEGER NOT NULL,
+                success INTEGER NOT NULL,
+                response_time INTEGER,
+                status_code INTEGER,
+                FOREIGN KEY (station_id) REFERENCES relay_stations (id) ON DELETE CASCADE
+            )",
+            [],
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_health_history_station_time ON station_health_history(station_id, recorded_at)",
+            [],
+        )?;
+        Ok(())
+    },
+    // 8: when each station was last fetched from and last connection-tested
+    |conn| {
+        add_column_if_missing(conn, "relay_stations", "last_used_at", "INTEGER")?;
+        add_column_if_missing(conn, "relay_stations", "last_tested_at", "INTEGER")
+    },
+];
+
+/// Builds the ORDER BY clause for `list_stations_sorted` from an allowlist, so user input never
+/// reaches the SQL. Unspecified sorting keeps the newest-first default; `latency` sorts on the
+/// most recent connection test. Stations never tested or used sort last on the columns that need it.
+fn station_order_by(sort_by: Option<&str>, order: Option<&str>) -> Result<String, RelayError> {
+    let (column, default_desc) = match sort_by.unwrap_or("created_at") {
+        "created_at" => ("s.created_at", true),
+        "updated_at" => ("s.updated_at", true),
+        "name" => ("s.name COLLATE NOCASE", false),
+        "enabled" => ("s.enabled", true),
+        "latency" => ("h.response_time", false),
+        "last_used_at" => ("s.last_used_at", true),
+        "last_tested_at" => ("s.last_tested_at", true),
+        other => {
+            return Err(RelayError::invalid_input(format!(
+                "Unknown sort_by '{}', expected one of created_at, updated_at, name, enabled, latency, last_used_at, last_tested_at",
+                other
+            )))
+        }
+    };
+    let desc = match order.map(|o| o.to_lowercase()) {
+        None => default_desc,
+        Some(o) if o == "asc" => false,
+        Some(o) if o == "desc" => true,
+        Some(o) => return Err(RelayError::invalid_input(format!("Unknown order '{}', expected asc or desc", o))),
+    };
+
+    let direction = if desc { "DESC" } else { "ASC" };
+    Ok(match column {
+        "s.created_at" => format!("s.created_at {}", direction),
+        "h.response_time" | "s.last_used_at" | "s.last_tested_at" => {
+            format!("{column} IS NULL, {column} {direction}, s.created_at DESC")
+        }
+        _ => format!("{} {}, s.created_at DESC", column, direction),
+    })
+}
+
+/// Adds a column unless it already exists, for databases that picked it up before versioning
+fn add_column_if_missing(conn: &Connection, table: &str, column: &str, definition: &str) -> rusqlite::Result<()> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let exists = stmt
+        .query_map([], |row| row.get::<_, String>("name"))?
+        .collect::<rusqlite::Result<Vec<_>>>()?
+        .iter()
+        .any(|name| name == column);
+    if !exists {
+        conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition), [])?;
+    }
+    Ok(())
+}
+
+/// Database manager for relay stations
+pub struct RelayStationManager {
+    db: ConnectionPool,
+}
+
+impl RelayStationManager {
+    pub fn new(db: ConnectionPool) -> Result<Self> {
+        let manager = Self { db };
+        manager.init_tables()?;
+        Ok(manager)
+    }
+
+    /// Brings the database up to the latest schema, applying each pending migration once in order
+    fn init_tables(&self) -> Result<()> {
+        let mut conn = self.db.get()?;
 
         conn.execute(
-            "INSERT INTO relay_stations (id, name, description, api_url, adapter, auth_method, system_token, user_id, adapter_config, enabled, created_at, updated_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
-            params![
-                station.id,
-                station.name,
-                station.description,
-                station.api_url,
-                match station.adapter {
-                    RelayStationAdapter::Newapi => "newapi",
-                    RelayStationAdapter::Oneapi => "oneapi",
-                    RelayStationAdapter::Yourapi => "yourapi",
-                    RelayStationAdapter::Custom => "custom",
-                },
-                match station.auth_method {
-                    AuthMethod::BearerToken => "bearer_token",
-                    AuthMethod::ApiKey => "api_key",
-                    AuthMethod::Custom => "custom",
-                },
-                station.system_token,
-                station.user_id,
-                adapter_config_str,
-                if station.enabled { 1 } else { 0 },
-                station.created_at,
-                station.updated_at,
-            ],
+            "CREATE TABLE IF NOT EXISTS schema_version (
+                version INTEGER PRIMARY KEY,
+                applied_at INTEGER NOT NULL
+            )",
+            [],
         )?;
 
+        let current: usize = conn.query_row("SELECT COALESCE(MAX(version), 0) FROM schema_version", [], |row| row.get(0))?;
+
+        for (index, migration) in MIGRATIONS.iter().enumerate().skip(current) {
+            let version = index + 1;
+            let tx = conn.transaction()?;
+            migration(&tx).map_err(|e| anyhow!("Relay station schema migration {} failed: {}", version, e))?;
+            tx.execute(
+                "INSERT INTO schema_version (version, applied_at) VALUES (?1, ?2)",
+                params![version, Utc::now().timestamp()],
+            )?;
+            tx.commit()?;
+            log::info!("Applied relay station schema migration {}", version);
+        }
+
         Ok(())
     }
 
-    pub fn get_station(&self, station_id: &str) -> Result<Option<RelayStation>> {
-        let conn = self.db.lock().unwrap();
-        let mut stmt = conn.prepare("SELECT * FROM relay_stations WHERE id = ?1")?;
+    pub fn list_stations(&self) -> Result<Vec<RelayStation>> {
+        self.list_stations_sorted(None, None)
+    }
+
+    /// Lists stations ordered by `sort_by` (created_at, updated_at, name, enabled, latency,
+    /// last_used_at or last_tested_at) and `order` (asc or desc); see `station_order_by`
+    pub fn list_stations_sorted(&self, sort_by: Option<&str>, order: Option<&str>) -> Result<Vec<RelayStation>> {
+        let order_by = station_order_by(sort_by, order)?;
+        let conn = self.db.get()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT s.* FROM relay_stations s
+             LEFT JOIN station_health_history h ON h.id = (
+                SELECT id FROM station_health_history WHERE station_id = s.id ORDER BY recorded_at DESC, id DESC LIMIT 1
+             )
+             WHERE s.deleted_at IS NULL
+             ORDER BY {}",
+            order_by
+        ))?;
         
-        let mut station_iter = stmt.query_map([station_id], |row| {
-            let adapter_config_str: Option<String> = row.get("adapter_config")?;
-            let adapter_config = if let Some(config_str) = adapter_config_str {
-                serde_json::from_str(&config_str).ok()
-            } else {
-                None
-            };
+        let station_iter = stmt.query_map([], station_from_row)?;
 
-            Ok(RelayStation {
-                id: row.get("id")?,
-                name: row.get("name")?,
-                description: row.get("description")?,
-                api_url: row.get("api_url")?,
-                adapter: match row.get::<_, String>("adapter")?.as_str() {
-                    "newapi" => RelayStationAdapter::Newapi,
-                    "oneapi" => RelayStationAdapter::Oneapi,
-                    "yourapi" => RelayStationAdapter::Yourapi,
-                    "custom" => RelayStationAdapter::Custom,
-                    _ => RelayStationAdapter::Newapi,
-                },
-                auth_method: match row.get::<_, String>("auth_method")?.as_str() {
-                    "bearer_token" => AuthMethod::BearerToken,
-                    "api_key" => AuthMethod::ApiKey,
-                    "custom" => AuthMethod::Custom,
-                    _ => AuthMethod::BearerToken,
-                },
-                system_token: row.get("system_token")?,
-                user_id: row.get("user_id")?,
-                adapter_config,
-                enabled: row.get::<_, i32>("enabled")? != 0,
-                created_at: row.get("created_at")?,
-                updated_at: row.get("updated_at")?,
-            })
-        })?;
+        station_iter.collect::<Result<Vec<_>, _>>().map_err(|e| anyhow!("Database error: {}", e))
+    }
+
+    pub fn list_stations_by_tag(&self, tag: &str) -> Result<Vec<RelayStation>> {
+        let conn = self.db.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT * FROM relay_stations
+             WHERE deleted_at IS NULL
+               AND EXISTS (SELECT 1 FROM json_each(relay_stations.tags) WHERE json_each.value = ?1)
+             ORDER BY created_at DESC",
+        )?;
+
+        let station_iter = stmt.query_map([tag.trim().to_lowercase()], station_from_row)?;
+
+        station_iter.collect::<Result<Vec<_>, _>>().map_err(|e| anyhow!("Database error: {}", e))
+    }
+
+    /// Case-insensitive search over name, description and api_url.
+    /// Exact name matches come first, then name prefixes, then any substring match.
+    pub fn search_stations(&self, query: &str) -> Result<Vec<RelayStation>> {
+        let conn = self.db.get()?;
+        let pattern = escape_like(query.trim());
+        let mut stmt = conn.prepare(
+            "SELECT * FROM relay_stations
+             WHERE deleted_at IS NULL
+               AND (name LIKE '%' || ?1 || '%' ESCAPE '\\'
+                OR description LIKE '%' || ?1 || '%' ESCAPE '\\'
+                OR api_url LIKE '%' || ?1 || '%' ESCAPE '\\')
+             ORDER BY CASE
+                WHEN lower(name) = lower(?2) THEN 0
+                WHEN name LIKE ?1 || '%' ESCAPE '\\' THEN 1
+                ELSE 2
+             END, created_at DESC",
+        )?;
+
+        let station_iter = stmt.query_map(params![pattern, query.trim()], station_from_row)?;
+
+        station_iter.collect::<Result<Vec<_>, _>>().map_err(|e| anyhow!("Database error: {}", e))
+    }
+
+    /// Case-insensitive search over a station's stored token names and keys, ranked like `search_stations`
+    pub fn search_tokens(&self, station_id: &str, query: &str) -> Result<Vec<RelayStationToken>> {
+        let conn = self.db.get()?;
+        let pattern = escape_like(query.trim());
+        let mut stmt = conn.prepare(
+            "SELECT * FROM relay_station_tokens
+             WHERE station_id = ?1 AND deleted_at IS NULL
+               AND (name LIKE '%' || ?2 || '%' ESCAPE '\\' OR token LIKE '%' || ?2 || '%' ESCAPE '\\')
+             ORDER BY CASE
+                WHEN lower(name) = lower(?3) THEN 0
+                WHEN name LIKE ?2 || '%' ESCAPE '\\' THEN 1
+                ELSE 2
+             END, created_at DESC",
+        )?;
+
+        let token_iter = stmt.query_map(params![station_id, pattern, query.trim()], token_from_row)?;
+
+        token_iter.collect::<Result<Vec<_>, _>>().map_err(|e| anyhow!("Database error: {}", e))
+    }
+
+    pub fn add_station(&self, station: &RelayStation) -> Result<()> {
+        let conn = self.db.get()?;
+        insert_station(&conn, station)
+    }
+
+    /// Overwrites every stored field of an existing station, restoring it if it was in the recycle bin
+    pub fn replace_station(&self, station: &RelayStation) -> Result<()> {
+        let conn = self.db.get()?;
+        overwrite_station(&conn, station)
+    }
+
+    /// Enabled tokens stored locally for stations that aren't in the recycle bin
+    pub fn list_enabled_tokens(&self) -> Result<Vec<RelayStationToken>> {
+        let conn = self.db.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT t.* FROM relay_station_tokens t
+             JOIN relay_stations s ON s.id = t.station_id
+             WHERE t.enabled = 1 AND t.deleted_at IS NULL AND s.deleted_at IS NULL
+             ORDER BY t.created_at",
+        )?;
+
+        let token_iter = stmt.query_map([], token_from_row)?;
+
+        token_iter.collect::<Result<Vec<_>, _>>().map_err(|e| anyhow!("Database error: {}", e))
+    }
 
-        match station_iter.next() {
-            Some(station) => Ok(Some(station?)),
-            None => Ok(None),
+    pub fn get_token(&self, station_id: &str, token_id: &str) -> Result<Option<RelayStationToken>> {
+        let conn = self.db.get()?;
+        select_token(&conn, station_id, token_id)
+    }
+
+    /// Tokens stored locally for a station, newest first, skipping ones removed upstream
+    pub fn list_cached_tokens(&self, station_id: &str) -> Result<Vec<RelayStationToken>> {
+        let conn = self.db.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT * FROM relay_station_tokens WHERE station_id = ?1 AND deleted_at IS NULL ORDER BY created_at DESC",
+        )?;
+
+        let token_iter = stmt.query_map([station_id], token_from_row)?;
+
+        token_iter.collect::<Result<Vec<_>, _>>().map_err(|e| anyhow!("Database error: {}", e))
+    }
+
+    /// Mirrors a station's full upstream token list into the local store.
+    ///
+    /// Tokens are matched by id: new ones are added, existing ones overwritten, and local tokens
+    /// missing from `remote` are marked deleted. A token that reappears is restored.
+    pub fn sync_tokens(&self, station_id: &str, remote: &[RelayStationToken], now: i64) -> Result<TokenSyncSummary> {
+        let mut conn = self.db.get()?;
+        let tx = conn.transaction()?;
+        let live: HashSet<String> = {
+            let mut stmt = tx.prepare("SELECT id FROM relay_station_tokens WHERE station_id = ?1 AND deleted_at IS NULL")?;
+            let ids = stmt.query_map([station_id], |row| row.get(0))?.collect::<rusqlite::Result<_>>()?;
+            ids
+        };
+
+        let mut summary = TokenSyncSummary::default();
+        let mut seen = HashSet::new();
+        for token in remote.iter().filter(|token| token.station_id == station_id) {
+            if !seen.insert(token.id.as_str()) {
+                continue;
+            }
+            upsert_token(&tx, token)?;
+            if live.contains(&token.id) {
+                summary.updated += 1;
+            } else {
+                summary.added += 1;
+            }
         }
+
+        for id in live.iter().filter(|id| !seen.contains(id.as_str())) {
+            summary.removed += tx.execute(
+                "UPDATE relay_station_tokens SET deleted_at = ?1 WHERE station_id = ?2 AND id = ?3",
+                params![now, station_id, id],
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(summary)
+    }
+
+    /// Inserts a token into the local store, replacing one with the same id
+    pub fn save_token(&self, token: &RelayStationToken) -> Result<()> {
+        let conn = self.db.get()?;
+        upsert_token(&conn, token)
+    }
+
+    /// A station's tokens with less than `threshold` quota units left, lowest first.
+    /// Tokens with unlimited quota never count as low.
+    pub fn list_low_quota_tokens(&self, station_id: &str, threshold: i64) -> Result<Vec<RelayStationToken>> {
+        let conn = self.db.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT * FROM relay_station_tokens
+             WHERE station_id = ?1 AND deleted_at IS NULL AND remain_quota IS NOT NULL AND remain_quota < ?2
+             ORDER BY remain_quota, created_at DESC",
+        )?;
+
+        let token_iter = stmt.query_map(params![station_id, threshold], token_from_row)?;
+
+        token_iter.collect::<Result<Vec<_>, _>>().map_err(|e| anyhow!("Database error: {}", e))
+    }
+
+    /// A live station by id; stations in the recycle bin are reported as missing
+    pub fn get_station(&self, station_id: &str) -> Result<Option<RelayStation>> {
+        let conn = self.db.get()?;
+        select_station(&conn, station_id)
+    }
+
+    /// Runs `f` in one transaction, committing only if it succeeds
+    pub fn transaction<T>(&self, f: impl FnOnce(&Connection) -> Result<T>) -> Result<T> {
+        let mut conn = self.db.get()?;
+        let tx = conn.transaction()?;
+        let value = f(&tx)?;
+        tx.commit()?;
+        Ok(value)
+    }
+
+    /// Sets one adapter_config key without touching the rest of the station
+    pub fn set_adapter_config_value(&self, station_id: &str, key: &str, value: serde_json::Value) -> Result<()> {
+        let conn = self.db.get()?;
+        let current: Option<String> = conn.query_row(
+            "SELECT adapter_config FROM relay_stations WHERE id = ?1",
+            [station_id],
+            |row| row.get(0),
+        )?;
+        // Don't overwrite a corrupt config; the user may still recover its contents
+        let mut config: HashMap<String, serde_json::Value> = match current {
+            Some(s) => serde_json::from_str(&s).map_err(|e| anyhow!("Stored adapter_config is corrupt: {}", e))?,
+            None => HashMap::new(),
+        };
+        config.insert(key.to_string(), value);
+        conn.execute(
+            "UPDATE relay_stations SET adapter_config = ?1 WHERE id = ?2",
+            params![serde_json::to_string(&config)?, station_id],
+        )?;
+        Ok(())
     }
 
     pub fn update_station(&self, station_id: &str, updates: &HashMap<String, serde_json::Value>) -> Result<()> {
-        let conn = self.db.lock().unwrap();
+        let conn = self.db.get()?;
         
         let mut query_parts = Vec::new();
 
@@ -417,6 +1699,7 @@ impl RelayStationManager {
                 "system_token" => query_parts.push("system_token = ?"),
                 "user_id" => query_parts.push("user_id = ?"),
                 "enabled" => query_parts.push("enabled = ?"),
+                "tags" => query_parts.push("tags = ?"),
                 _ => {}
             }
         }
@@ -464,6 +1747,11 @@ impl RelayStationManager {
                         let enabled_val = if value.as_bool().unwrap_or(false) { 1i64 } else { 0i64 };
                         params_vec.push(rusqlite::types::Value::Integer(enabled_val));
                     }
+                    "tags" => {
+                        let tags: Vec<String> = serde_json::from_value(value.clone())
+                            .map_err(|e| RelayError::invalid_input(format!("tags must be a list of strings: {}", e)))?;
+                        params_vec.push(rusqlite::types::Value::Text(serde_json::to_string(&normalize_tags(&tags))?));
+                    }
                     _ => {}
                 }
             }
@@ -476,470 +1764,2852 @@ impl RelayStationManager {
         Ok(())
     }
 
-    pub fn delete_station(&self, station_id: &str) -> Result<()> {
-        let conn = self.db.lock().unwrap();
-        conn.execute("DELETE FROM relay_stations WHERE id = ?1", [station_id])?;
-        Ok(())
+    /// Sets `enabled` on every listed station in one transaction. Returns `None`, changing
+    /// nothing, if any id doesn't name a live station; otherwise the number of stations updated.
+    pub fn set_stations_enabled(&self, station_ids: &[String], enabled: bool) -> Result<Option<usize>> {
+        let mut conn = self.db.get()?;
+        let tx = conn.transaction()?;
+        let now = Utc::now().timestamp();
+        let unique: HashSet<&str> = station_ids.iter().map(String::as_str).collect();
+        for id in &unique {
+            let updated = tx.execute(
+                "UPDATE relay_stations SET enabled = ?1, updated_at = ?2 WHERE id = ?3 AND deleted_at IS NULL",
+                params![enabled, now, id],
+            )?;
+            if updated == 0 {
+                return Ok(None); // Dropping the transaction rolls back
+            }
+        }
+        tx.commit()?;
+        Ok(Some(unique.len()))
     }
 
-    // pub fn list_tokens(&self, station_id: &str) -> Result<Vec<RelayStationToken>> {
-    //     let conn = self.db.lock().unwrap();
-    //     let mut stmt = conn.prepare("SELECT * FROM relay_station_tokens WHERE station_id = ?1 ORDER BY created_at DESC")?;
-        
-    //     let token_iter = stmt.query_map([station_id], |row| {
-    //         let metadata_str: Option<String> = row.get("metadata")?;
-    //         let metadata = if let Some(meta_str) = metadata_str {
-    //             serde_json::from_str(&meta_str).ok()
-    //         } else {
-    //             None
-    //         };
-
-    //         Ok(RelayStationToken {
-    //             id: row.get("id")?,
-    //             station_id: row.get("station_id")?,
-    //             name: row.get("name")?,
-    //             token: row.get("token")?,
-    //             user_id: row.get("user_id")?,
-    //             enabled: row.get::<_, i32>("enabled")? != 0,
-    //             expires_at: row.get("expires_at")?,
-    //             group: None, // Database doesn't store groups, they come from API
-    //             remain_quota: None, // Database doesn't store quotas, they come from API
-    //             unlimited_quota: None, // Database doesn't store quota settings, they come from API
-    //             metadata,
-    //             created_at: row.get("created_at")?,
-    //         })
-    //     })?;
-
-    //     token_iter.collect::<Result<Vec<_>, _>>().map_err(|e| anyhow!("Database error: {}", e))
-    // }
-
-    // pub fn add_token(&self, token: &RelayStationToken) -> Result<()> {
-    //     let conn = self.db.lock().unwrap();
-        
-    //     let metadata_str = if let Some(metadata) = &token.metadata {
-    //         Some(serde_json::to_string(metadata)?)
-    //     } else {
-    //         None
-    //     };
-
-    //     conn.execute(
-    //         "INSERT INTO relay_station_tokens (id, station_id, name, token, user_id, enabled, expires_at, metadata, created_at)
-    //          VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
-    //         params![
-    //             token.id,
-    //             token.station_id,
-    //             token.name,
-    //             token.token,
-    //             token.user_id,
-    //             if token.enabled { 1 } else { 0 },
-    //             token.expires_at,
-    //             metadata_str,
-    //             token.created_at,
-    //         ],
-    //     )?;
-
-    //     Ok(())
-    // }
-
-    // pub fn update_token(&self, token_id: &str, updates: &HashMap<String, serde_json::Value>) -> Result<()> {
-    //     let conn = self.db.lock().unwrap();
-        
-    //     let mut query_parts = Vec::new();
-
-    //     for (key, _) in updates {
-    //         match key.as_str() {
-    //             "name" => query_parts.push("name = ?"),
-    //             "token" => query_parts.push("token = ?"),
-    //             "user_id" => query_parts.push("user_id = ?"),
-    //             "enabled" => query_parts.push("enabled = ?"),
-    //             _ => {}
-    //         }
-    //     }
-
-    //     if !query_parts.is_empty() {
-    //         let query = format!("UPDATE relay_station_tokens SET {} WHERE id = ?", query_parts.join(", "));
-            
-    //         let mut params_vec: Vec<rusqlite::types::Value> = Vec::new();
-    //         for (key, value) in updates {
-    //             match key.as_str() {
-    //                 "name" => {
-    //                     params_vec.push(rusqlite::types::Value::Text(value.as_str().unwrap_or("").to_string()));
-    //                 }
-    //                 "token" => {
-    //                     params_vec.push(rusqlite::types::Value::Text(value.as_str().unwrap_or("").to_string()));
-    //                 }
-    //                 "user_id" => {
-    //                     if let Some(user_id) = value.as_str() {
-    //                         params_vec.push(rusqlite::types::Value::Text(user_id.to_string()));
-    //                     } else {
-    //                         params_vec.push(rusqlite::types::Value::Null);
-    //                     }
-    //                 }
-    //                 "enabled" => {
-    //                     let enabled_val = if value.as_bool().unwrap_or(false) { 1i64 } else { 0i64 };
-    //                     params_vec.push(rusqlite::types::Value::Integer(enabled_val));
-    //                 }
-    //                 _ => {}
-    //             }
-    //         }
-    //         params_vec.push(rusqlite::types::Value::Text(token_id.to_string()));
-
-    //         conn.execute(&query, rusqlite::params_from_iter(params_vec))?;
-    //     }
-
-    //     Ok(())
-    // }
-
-    // pub fn delete_token(&self, token_id: &str) -> Result<()> {
-    //     let conn = self.db.lock().unwrap();
-    //     conn.execute("DELETE FROM relay_station_tokens WHERE id = ?1", [token_id])?;
-    //     Ok(())
-    // }
-}
-
-// Tauri command handlers
+    /// Exchanges the `system_token` and `user_id` of two live stations in one transaction.
+    /// Returns false, changing nothing, if either station doesn't exist.
+    ///
+    /// Cached tokens are left in place even though they belong to the previous account; the
+    /// next `sync_tokens` replaces them with the new account's list and soft-deletes the rest.
+    pub fn swap_station_credentials(&self, station_a: &str, station_b: &str) -> Result<bool> {
+        let mut conn = self.db.get()?;
+        let tx = conn.transaction()?;
+        let credentials = |id: &str| {
+            tx.query_row(
+                "SELECT system_token, user_id FROM relay_stations WHERE id = ?1 AND deleted_at IS NULL",
+                [id],
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?)),
+            )
+            .optional()
+        };
+        let (Some((token_a, user_a)), Some((token_b, user_b))) = (credentials(station_a)?, credentials(station_b)?) else {
+            return Ok(false);
+        };
 
-#[tauri::command]
-pub async fn list_relay_stations(app: AppHandle) -> Result<Vec<RelayStation>, String> {
-    let state: State<Mutex<Option<RelayStationManager>>> = app.state();
-    let manager_lock = state.lock().map_err(|e| format!("Lock error: {}", e))?;
-    
-    if let Some(manager) = manager_lock.as_ref() {
-        manager.list_stations().map_err(|e| format!("Failed to list stations: {}", e))
-    } else {
-        Ok(Vec::new()) // Return empty list if manager not initialized
+        let now = Utc::now().timestamp();
+        for (id, token, user_id) in [(station_a, token_b, user_b), (station_b, token_a, user_a)] {
+            tx.execute(
+                "UPDATE relay_stations SET system_token = ?1, user_id = ?2, updated_at = ?3 WHERE id = ?4",
+                params![token, user_id, now, id],
+            )?;
+        }
+        tx.commit()?;
+        Ok(true)
     }
-}
 
-#[tauri::command]
-pub async fn get_relay_station(station_id: String, app: AppHandle) -> Result<Option<RelayStation>, String> {
-    let state: State<Mutex<Option<RelayStationManager>>> = app.state();
-    let manager_lock = state.lock().map_err(|e| format!("Lock error: {}", e))?;
-    
-    if let Some(manager) = manager_lock.as_ref() {
-        manager.get_station(&station_id).map_err(|e| format!("Failed to get station: {}", e))
-    } else {
-        Ok(None)
+    /// Sets `enabled` on every station carrying `tag`, returning how many were updated
+    pub fn set_stations_enabled_by_tag(&self, tag: &str, enabled: bool) -> Result<usize> {
+        let conn = self.db.get()?;
+        let updated = conn.execute(
+            "UPDATE relay_stations SET enabled = ?1, updated_at = ?2
+             WHERE deleted_at IS NULL
+               AND EXISTS (SELECT 1 FROM json_each(relay_stations.tags) WHERE json_each.value = ?3)",
+            params![enabled, Utc::now().timestamp(), tag.trim().to_lowercase()],
+        )?;
+        Ok(updated)
     }
-}
 
-#[tauri::command]
-pub async fn add_relay_station(
-    station_request: CreateRelayStationRequest,
-    app: AppHandle,
-) -> Result<String, String> {
-    let state: State<Mutex<Option<RelayStationManager>>> = app.state();
-    let manager_lock = state.lock().map_err(|e| format!("Lock error: {}", e))?;
-    
-    if let Some(manager) = manager_lock.as_ref() {
-        let station = RelayStation {
-            id: Uuid::new_v4().to_string(),
-            name: station_request.name,
-            description: station_request.description,
-            api_url: station_request.api_url,
-            adapter: station_request.adapter,
-            auth_method: station_request.auth_method,
-            system_token: station_request.system_token,
-            user_id: station_request.user_id,
-            adapter_config: station_request.adapter_config,
-            enabled: station_request.enabled,
-            created_at: Utc::now().timestamp(),
-            updated_at: Utc::now().timestamp(),
+    /// Clones a station's configuration under a new id and name. The clone starts disabled
+    /// and doesn't carry over cached tokens. Returns `None` if the source station doesn't exist.
+    pub fn duplicate_station(&self, station_id: &str, new_name: &str) -> Result<Option<String>> {
+        let source = match self.get_station(station_id)? {
+            Some(station) => station,
+            None => return Ok(None),
         };
-        
-        manager.add_station(&station).map_err(|e| format!("Failed to add station: {}", e))?;
-        Ok("Station added successfully".to_string())
-    } else {
-        Err("Relay station manager not initialized".to_string())
+
+        let now = Utc::now().timestamp();
+        let clone = RelayStation {
+            id: Uuid::new_v4().to_string(),
+            name: new_name.to_string(),
+            enabled: false,
+            created_at: now,
+            updated_at: now,
+            deleted_at: None,
+            last_used_at: None,
+            last_tested_at: None,
+            config_parse_error: false,
+            ..source
+        };
+        self.add_station(&clone)?;
+
+        Ok(Some(clone.id))
+    }
+
+    /// Moves a station to the recycle bin; its tokens are kept until it is purged
+    pub fn delete_station(&self, station_id: &str) -> Result<()> {
+        let conn = self.db.get()?;
+        conn.execute(
+            "UPDATE relay_stations SET deleted_at = ?1 WHERE id = ?2 AND deleted_at IS NULL",
+            params![Utc::now().timestamp(), station_id],
+        )?;
+        Ok(())
+    }
+
+    pub fn list_deleted_stations(&self) -> Result<Vec<RelayStation>> {
+        let conn = self.db.get()?;
+        let mut stmt = conn.prepare("SELECT * FROM relay_stations WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC")?;
+
+        let station_iter = stmt.query_map([], station_from_row)?;
+
+        station_iter.collect::<Result<Vec<_>, _>>().map_err(|e| anyhow!("Database error: {}", e))
+    }
+
+    /// Restores a station from the recycle bin, returning false if it wasn't deleted
+    pub fn restore_station(&self, station_id: &str) -> Result<bool> {
+        let conn = self.db.get()?;
+        let restored = conn.execute(
+            "UPDATE relay_stations SET deleted_at = NULL, updated_at = ?1 WHERE id = ?2 AND deleted_at IS NOT NULL",
+            params![Utc::now().timestamp(), station_id],
+        )?;
+        Ok(restored > 0)
+    }
+
+    /// Permanently removes a station from the recycle bin together with its tokens
+    pub fn purge_station(&self, station_id: &str) -> Result<bool> {
+        let mut conn = self.db.get()?;
+        let tx = conn.transaction()?;
+        let purged = tx.execute(
+            "DELETE FROM relay_stations WHERE id = ?1 AND deleted_at IS NOT NULL",
+            [station_id],
+        )?;
+        if purged > 0 {
+            tx.execute("DELETE FROM relay_station_tokens WHERE station_id = ?1", [station_id])?;
+        }
+        tx.commit()?;
+        Ok(purged > 0)
+    }
+
+    /// Writes a consistent copy of the database, including pending WAL pages, to `path`
+    pub fn snapshot_to(&self, path: &std::path::Path) -> Result<()> {
+        let path = path.to_str().ok_or_else(|| anyhow!("Snapshot path is not valid UTF-8"))?;
+        self.db.get()?.execute("VACUUM INTO ?1", [path])?;
+        Ok(())
+    }
+
+    /// Replaces all stations and tokens with those in a snapshot written by `snapshot_to`.
+    ///
+    /// Only columns present in both databases are copied, so snapshots from older schema
+    /// versions restore with defaults for newer columns. Returns (stations, tokens) restored.
+    pub fn restore_from_snapshot(&self, path: &std::path::Path) -> Result<(usize, usize)> {
+        let path = path.to_str().ok_or_else(|| anyhow!("Snapshot path is not valid UTF-8"))?;
+        let mut conn = self.db.get()?;
+        conn.execute("ATTACH DATABASE ?1 AS snapshot", [path])?;
+
+        let result = (|| -> Result<(usize, usize)> {
+            let tx = conn.transaction()?;
+            tx.execute("DELETE FROM station_quota_history", [])?;
+            tx.execute("DELETE FROM station_health_history", [])?;
+            tx.execute("DELETE FROM relay_station_tokens", [])?;
+            tx.execute("DELETE FROM relay_stations", [])?;
+
+            let mut counts = Vec::new();
+            for table in ["relay_stations", "relay_station_tokens", "station_quota_history", "station_health_history"] {
+                let columns = |schema: &str| -> Result<Vec<String>> {
+                    let mut stmt = tx.prepare(&format!("PRAGMA {}.table_info({})", schema, table))?;
+                    let names = stmt.query_map([], |row| row.get::<_, String>("name"))?.collect::<rusqlite::Result<Vec<_>>>()?;
+                    Ok(names)
+                };
+                let snapshot_columns = columns("snapshot")?;
+                let shared: Vec<String> = columns("main")?.into_iter().filter(|c| snapshot_columns.contains(c)).collect();
+                if shared.is_empty() {
+                    // Quota history only exists in snapshots from schema 4 on, health history from 7
+                    if matches!(table, "station_quota_history" | "station_health_history") {
+                        continue;
+                    }
+                    return Err(anyhow!("Snapshot has no {} table", table));
+                }
+                let column_list = shared.join(", ");
+                counts.push(tx.execute(
+                    &format!("INSERT INTO main.{table} ({column_list}) SELECT {column_list} FROM snapshot.{table}"),
+                    [],
+                )?);
+            }
+
+            tx.commit()?;
+            Ok((counts[0], counts[1]))
+        })();
+
+        conn.execute("DETACH DATABASE snapshot", [])?;
+        result
+    }
+
+    pub fn record_quota_sample(&self, sample: &QuotaSample) -> Result<()> {
+        self.db.get()?.execute(
+            "INSERT INTO station_quota_history (station_id, recorded_at, quota, used_quota, quota_per_unit) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![sample.station_id, sample.recorded_at, sample.quota, sample.used_quota, sample.quota_per_unit],
+        )?;
+        Ok(())
+    }
+
+    /// Records when the station's tokens, logs or user info were last fetched
+    pub fn mark_station_used(&self, station_id: &str, at: i64) -> Result<()> {
+        self.db.get()?.execute("UPDATE relay_stations SET last_used_at = ?1 WHERE id = ?2", params![at, station_id])?;
+        Ok(())
+    }
+
+    /// Records when the station was last connection-tested
+    pub fn mark_station_tested(&self, station_id: &str, at: i64) -> Result<()> {
+        self.db.get()?.execute("UPDATE relay_stations SET last_tested_at = ?1 WHERE id = ?2", params![at, station_id])?;
+        Ok(())
+    }
+
+    /// Records the outcome of a connection test
+    pub fn record_health_sample(&self, station_id: &str, recorded_at: i64, result: &ConnectionTestResult) -> Result<()> {
+        self.db.get()?.execute(
+            "INSERT INTO station_health_history (station_id, recorded_at, success, response_time, status_code) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![station_id, recorded_at, result.success, result.response_time.map(|t| t as i64), result.status_code],
+        )?;
+        Ok(())
+    }
+
+    /// Quota samples for a station recorded at or after `since`, oldest first
+    pub fn quota_samples(&self, station_id: &str, since: i64) -> Result<Vec<QuotaSample>> {
+        let conn = self.db.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT station_id, recorded_at, quota, used_quota, quota_per_unit FROM station_quota_history
+             WHERE station_id = ?1 AND recorded_at >= ?2 ORDER BY recorded_at",
+        )?;
+        let samples = stmt
+            .query_map(params![station_id, since], |row| {
+                Ok(QuotaSample::new(row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(samples)
     }
 }
 
-#[tauri::command]
-pub async fn update_relay_station(
-    station_id: String,
-    updates: HashMap<String, serde_json::Value>,
-    app: AppHandle,
-) -> Result<String, String> {
-    let state: State<Mutex<Option<RelayStationManager>>> = app.state();
-    let manager_lock = state.lock().map_err(|e| format!("Lock error: {}", e))?;
-    
-    if let Some(manager) = manager_lock.as_ref() {
-        manager.update_station(&station_id, &updates).map_err(|e| format!("Failed to update station: {}", e))?;
-        Ok("Station updated successfully".to_string())
-    } else {
-        Err("Relay station manager not initialized".to_string())
+/// Whether the relay station manager is usable, and why not if it isn't
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelayManagerStatus {
+    pub initialized: bool,
+    pub db_path: String,
+    pub last_error: Option<String>,
+}
+
+/// Tauri state holding the relay station manager, which can be (re)initialized at runtime
+/// so a failed startup shows up as an error instead of an empty station list
+pub struct RelayManagerState {
+    db_path: PathBuf,
+    manager: RwLock<Option<Arc<RelayStationManager>>>,
+    last_error: Mutex<Option<String>>,
+}
+
+impl RelayManagerState {
+    pub fn new(db_path: PathBuf) -> Self {
+        Self { db_path, manager: RwLock::new(None), last_error: Mutex::new(None) }
     }
+
+    /// Opens the database and replaces the current manager, recording the error on failure
+    pub fn init(&self) -> Result<(), RelayError> {
+        let result = ConnectionPool::open(&self.db_path).and_then(RelayStationManager::new);
+        let mut last_error = self.last_error.lock().map_err(|e| RelayError::database(format!("Lock error: {}", e)))?;
+        match result {
+            Ok(manager) => {
+                *self.manager.write().map_err(|e| RelayError::database(format!("Lock error: {}", e)))? = Some(Arc::new(manager));
+                *last_error = None;
+                Ok(())
+            }
+            Err(e) => {
+                let message = format!("Failed to open relay station database at {}: {}", self.db_path.display(), e);
+                *last_error = Some(message.clone());
+                Err(RelayError::database(message))
+            }
+        }
+    }
+
+    /// The current manager; callers hold the Arc, so a concurrent re-init doesn't pull it from under them
+    pub fn get(&self) -> Option<Arc<RelayStationManager>> {
+        self.manager.read().ok()?.clone()
+    }
+
+    pub fn status(&self) -> RelayManagerStatus {
+        RelayManagerStatus {
+            initialized: self.get().is_some(),
+            db_path: self.db_path.display().to_string(),
+            last_error: self.last_error.lock().ok().and_then(|e| e.clone()),
+        }
+    }
+}
+
+// Tauri command handlers
+
+/// Returns the current relay station manager, if it has been initialized
+pub(crate) fn current_manager(app: &AppHandle) -> Result<Arc<RelayStationManager>, RelayError> {
+    let state: State<RelayManagerState> = app.try_state().ok_or_else(RelayError::manager_uninitialized)?;
+    state.get().ok_or_else(|| match state.status().last_error {
+        Some(error) => RelayError::ManagerUninitialized { message: error },
+        None => RelayError::manager_uninitialized(),
+    })
+}
+
+/// Runs a synchronous operation against the current `RelayStationManager`.
+/// The manager isn't behind a lock; each operation checks out its own pooled connection.
+pub(crate) fn with_manager<T>(
+    app: &AppHandle,
+    f: impl FnOnce(&RelayStationManager) -> Result<T, RelayError>,
+) -> Result<T, RelayError> {
+    f(current_manager(app)?.as_ref())
 }
 
+/// Like `with_manager`, but runs the operation on the blocking thread pool. Use it for scans,
+/// aggregates and bulk writes, which would otherwise hold up a runtime worker and stall
+/// unrelated network requests.
+pub(crate) async fn with_manager_blocking<T: Send + 'static>(
+    app: &AppHandle,
+    f: impl FnOnce(&RelayStationManager) -> Result<T, RelayError> + Send + 'static,
+) -> Result<T, RelayError> {
+    run_blocking(current_manager(app)?, f).await
+}
+
+async fn run_blocking<T: Send + 'static>(
+    manager: Arc<RelayStationManager>,
+    f: impl FnOnce(&RelayStationManager) -> Result<T, RelayError> + Send + 'static,
+) -> Result<T, RelayError> {
+    tokio::task::spawn_blocking(move || f(manager.as_ref()))
+        .await
+        .map_err(|e| RelayError::database(format!("Database task failed: {}", e)))?
+}
+
+/// (Re)opens the relay station database, e.g. after fixing whatever made startup fail
 #[tauri::command]
-pub async fn delete_relay_station(station_id: String, app: AppHandle) -> Result<String, String> {
-    let state: State<Mutex<Option<RelayStationManager>>> = app.state();
-    let manager_lock = state.lock().map_err(|e| format!("Lock error: {}", e))?;
-    
-    if let Some(manager) = manager_lock.as_ref() {
-        manager.delete_station(&station_id).map_err(|e| format!("Failed to delete station: {}", e))?;
-        Ok("Station deleted successfully".to_string())
-    } else {
-        Err("Relay station manager not initialized".to_string())
-    }
+pub async fn init_relay_station_manager(app: AppHandle) -> Result<RelayManagerStatus, RelayError> {
+    let state: State<RelayManagerState> = app.try_state().ok_or_else(RelayError::manager_uninitialized)?;
+    state.init()?;
+    Ok(state.status())
 }
 
+/// Reports whether the relay station manager is initialized and the last init error
 #[tauri::command]
-pub async fn get_station_info(station_id: String, app: AppHandle) -> Result<StationInfo, String> {
-    let state: State<Mutex<Option<RelayStationManager>>> = app.state();
-    
-    // Get the station first, releasing the lock before the async call
-    let station = {
-        let manager_lock = state.lock().map_err(|e| format!("Lock error: {}", e))?;
-        if let Some(manager) = manager_lock.as_ref() {
-            manager.get_station(&station_id).map_err(|e| format!("Failed to get station: {}", e))?
-        } else {
-            return Err("Relay station manager not initialized".to_string());
-        }
-    };
-    
-    if let Some(station) = station {
-        let adapter = create_adapter(&station.adapter);
-        adapter.get_station_info(&station).await.map_err(|e| format!("Failed to get station info: {}", e))
-    } else {
-        Err("Station not found".to_string())
+pub async fn relay_manager_status(app: AppHandle) -> Result<RelayManagerStatus, RelayError> {
+    let state: State<RelayManagerState> = app.try_state().ok_or_else(RelayError::manager_uninitialized)?;
+    Ok(state.status())
+}
+
+/// Loads a station by id
+pub(crate) fn load_station(app: &AppHandle, station_id: &str) -> Result<RelayStation, RelayError> {
+    with_manager(app, |manager| {
+        manager
+            .get_station(station_id)
+            .map_err(|e| RelayError::database(format!("Failed to get station: {}", e)))?
+            .ok_or_else(|| RelayError::not_found("Station not found"))
+    })
+}
+
+/// Lists stations, optionally sorted by created_at, updated_at, name, enabled, latency (last connection test),
+/// last_used_at or last_tested_at
+#[tauri::command]
+pub async fn list_relay_stations(sort_by: Option<String>, order: Option<String>, app: AppHandle) -> Result<Vec<RelayStation>, RelayError> {
+    match with_manager(&app, |manager| {
+        manager
+            .list_stations_sorted(sort_by.as_deref(), order.as_deref())
+            .map_err(|e| RelayError::from(e).context("Failed to list stations"))
+    }) {
+        Err(RelayError::ManagerUninitialized { .. }) => Ok(Vec::new()), // Return empty list if manager not initialized
+        result => result,
     }
 }
 
 #[tauri::command]
-pub async fn list_station_tokens(station_id: String, page: Option<usize>, size: Option<usize>, app: AppHandle) -> Result<TokenPaginationResponse, String> {
-    let state: State<Mutex<Option<RelayStationManager>>> = app.state();
-    
-    // Get the station first, releasing the lock before the async call
-    let station = {
-        let manager_lock = state.lock().map_err(|e| format!("Lock error: {}", e))?;
-        if let Some(manager) = manager_lock.as_ref() {
-            manager.get_station(&station_id).map_err(|e| format!("Failed to get station: {}", e))?
-        } else {
-            return Ok(TokenPaginationResponse {
-                items: Vec::new(),
-                page: 1,
-                page_size: 10,
-                total: 0,
-            });
-        }
-    };
-    
-    if let Some(station) = station {
-        let adapter = create_adapter(&station.adapter);
-        adapter.list_tokens(&station, page, size).await.map_err(|e| format!("Failed to list tokens: {}", e))
-    } else {
-        Ok(TokenPaginationResponse {
-            items: Vec::new(),
-            page: 1,
-            page_size: 10,
-            total: 0,
-        })
+pub async fn get_relay_station(station_id: String, app: AppHandle) -> Result<Option<RelayStation>, RelayError> {
+    match with_manager(&app, |manager| {
+        manager.get_station(&station_id).map_err(|e| RelayError::database(format!("Failed to get station: {}", e)))
+    }) {
+        Err(RelayError::ManagerUninitialized { .. }) => Ok(None),
+        result => result,
     }
 }
 
 #[tauri::command]
-pub async fn add_station_token(
-    station_id: String,
-    token_data: CreateTokenRequest,
+pub async fn list_stations_by_tag(tag: String, app: AppHandle) -> Result<Vec<RelayStation>, RelayError> {
+    with_manager(&app, |manager| {
+        manager.list_stations_by_tag(&tag).map_err(|e| RelayError::database(format!("Failed to list stations by tag: {}", e)))
+    })
+}
+
+#[tauri::command]
+pub async fn search_stations(query: String, app: AppHandle) -> Result<Vec<RelayStation>, RelayError> {
+    with_manager_blocking(&app, move |manager| {
+        manager.search_stations(&query).map_err(|e| RelayError::database(format!("Failed to search stations: {}", e)))
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn search_tokens(station_id: String, query: String, app: AppHandle) -> Result<Vec<RelayStationToken>, RelayError> {
+    with_manager_blocking(&app, move |manager| {
+        manager.search_tokens(&station_id, &query).map_err(|e| RelayError::database(format!("Failed to search tokens: {}", e)))
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn add_relay_station(
+    station_request: CreateRelayStationRequest,
     app: AppHandle,
-) -> Result<RelayStationToken, String> {
-    let state: State<Mutex<Option<RelayStationManager>>> = app.state();
+) -> Result<String, RelayError> {
+    create_station(&app, station_request).await?;
+    Ok("Station added successfully".to_string())
+}
+
+/// Validates the request and saves it as a new station, returning the stored station
+pub(crate) async fn create_station(app: &AppHandle, station_request: CreateRelayStationRequest) -> Result<RelayStation, RelayError> {
+    let api_url = normalize_api_url(&station_request.api_url)?;
+    let discover_quota_per_unit = station_request.discover_quota_per_unit;
     
-    // Get the station first, releasing the lock before the async call
-    let station = {
-        let manager_lock = state.lock().map_err(|e| format!("Lock error: {}", e))?;
-        if let Some(manager) = manager_lock.as_ref() {
-            manager.get_station(&station_id).map_err(|e| format!("Failed to get station: {}", e))?
-        } else {
-            return Err("Relay station manager not initialized".to_string());
-        }
+    let mut station = RelayStation {
+        id: Uuid::new_v4().to_string(),
+        name: station_request.name,
+        description: station_request.description,
+        is_secure: is_secure_url(&api_url),
+        api_url,
+        adapter: station_request.adapter,
+        auth_method: station_request.auth_method,
+        system_token: station_request.system_token,
+        user_id: station_request.user_id,
+        adapter_config: station_request.adapter_config,
+        enabled: station_request.enabled,
+        tags: station_request.tags,
+        created_at: Utc::now().timestamp(),
+        updated_at: Utc::now().timestamp(),
+        deleted_at: None,
+        last_used_at: None,
+        last_tested_at: None,
+        config_parse_error: false,
     };
-    
-    if let Some(station) = station {
-        let adapter = create_adapter(&station.adapter);
-        adapter.create_token(&station, &token_data).await.map_err(|e| format!("Failed to create token: {}", e))
-    } else {
-        Err("Station not found".to_string())
+
+    let configured = station.adapter_config.as_ref().is_some_and(|config| config.contains_key(QUOTA_PER_UNIT_KEY));
+    if discover_quota_per_unit && !configured {
+        // Conversions fall back to a live fetch, so a station that can't be reached now is still usable
+        match discover_station_quota_per_unit(&station).await {
+            Ok(quota_per_unit) => {
+                station.adapter_config.get_or_insert_with(HashMap::new).insert(QUOTA_PER_UNIT_KEY.to_string(), quota_per_unit.into());
+            }
+            Err(e) => log::warn!("Adding station {} without quota_per_unit: {}", station.name, e),
+        }
     }
+
+    if let Some(warning) = station.plain_http_warning() {
+        log::warn!("{}", warning);
+    }
+
+    with_manager(app, |manager| {
+        manager.add_station(&station).map_err(|e| RelayError::database(format!("Failed to add station: {}", e)))
+    })?;
+    Ok(station)
+}
+
+/// adapter_config key holding the station's quota units per dollar
+const QUOTA_PER_UNIT_KEY: &str = "quota_per_unit";
+
+/// Reads the quota units per dollar the station reports in its info
+async fn discover_station_quota_per_unit(station: &RelayStation) -> Result<i64, RelayError> {
+    fetch_station_info(station)
+        .await?
+        .quota_per_unit
+        .filter(|&quota_per_unit| quota_per_unit > 0)
+        .ok_or_else(|| RelayError::unsupported("Station does not report quota_per_unit"))
 }
 
+/// Re-fetches the station's `quota_per_unit` and stores it in adapter_config, replacing any previous value
 #[tauri::command]
-pub async fn update_station_token(
+pub async fn refresh_quota_per_unit(station_id: String, app: AppHandle) -> Result<i64, RelayError> {
+    let station = load_station(&app, &station_id)?;
+    let quota_per_unit = discover_station_quota_per_unit(&station).await?;
+    with_manager(&app, |manager| {
+        manager
+            .set_adapter_config_value(&station_id, QUOTA_PER_UNIT_KEY, quota_per_unit.into())
+            .map_err(|e| RelayError::database(format!("Failed to save quota_per_unit: {}", e)))
+    })?;
+    Ok(quota_per_unit)
+}
+
+#[tauri::command]
+pub async fn update_relay_station(
     station_id: String,
-    token_id: String,
-    token_data: UpdateTokenRequest,
+    mut updates: HashMap<String, serde_json::Value>,
     app: AppHandle,
-) -> Result<RelayStationToken, String> {
-    let state: State<Mutex<Option<RelayStationManager>>> = app.state();
+) -> Result<String, RelayError> {
+    if let Some(api_url) = updates.get("api_url") {
+        let api_url = api_url.as_str().ok_or_else(|| RelayError::invalid_input("api_url must be a string"))?;
+        let normalized = normalize_api_url(api_url)?;
+        updates.insert("api_url".to_string(), serde_json::Value::String(normalized));
+    }
+    if let Some(tags) = updates.get("tags") {
+        serde_json::from_value::<Vec<String>>(tags.clone()).map_err(|_| RelayError::invalid_input("tags must be a list of strings"))?;
+    }
     
-    // Get the station first, releasing the lock before the async call
-    let station = {
-        let manager_lock = state.lock().map_err(|e| format!("Lock error: {}", e))?;
-        if let Some(manager) = manager_lock.as_ref() {
-            manager.get_station(&station_id).map_err(|e| format!("Failed to get station: {}", e))?
-        } else {
-            return Err("Relay station manager not initialized".to_string());
+    with_manager(&app, |manager| {
+        manager.update_station(&station_id, &updates).map_err(|e| RelayError::database(format!("Failed to update station: {}", e)))?;
+        Ok(())
+    })?;
+
+    // Cached data may come from the old endpoint, credentials or adapter settings
+    invalidate_station_caches(&station_id);
+    if updates.contains_key("api_url") || updates.contains_key("system_token") {
+        if let Some(warning) = load_station(&app, &station_id).ok().and_then(|station| station.plain_http_warning()) {
+            log::warn!("{}", warning);
         }
-    };
-    
-    if let Some(station) = station {
-        let adapter = create_adapter(&station.adapter);
-        adapter.update_token(&station, &token_id, &token_data).await.map_err(|e| format!("Failed to update token: {}", e))
-    } else {
-        Err("Station not found".to_string())
     }
+    Ok("Station updated successfully".to_string())
 }
 
+/// Enables or disables several stations at once; all succeed or none do
 #[tauri::command]
-pub async fn delete_station_token(
-    station_id: String,
-    token_id: String,
-    app: AppHandle,
-) -> Result<String, String> {
-    let state: State<Mutex<Option<RelayStationManager>>> = app.state();
-    
-    // Get the station first, releasing the lock before the async call
-    let station = {
-        let manager_lock = state.lock().map_err(|e| format!("Lock error: {}", e))?;
-        if let Some(manager) = manager_lock.as_ref() {
-            manager.get_station(&station_id).map_err(|e| format!("Failed to get station: {}", e))?
-        } else {
-            return Err("Relay station manager not initialized".to_string());
-        }
-    };
-    
-    if let Some(station) = station {
-        let adapter = create_adapter(&station.adapter);
-        adapter.delete_token(&station, &token_id).await.map_err(|e| format!("Failed to delete token: {}", e))?;
-        Ok("Token deleted successfully".to_string())
-    } else {
-        Err("Station not found".to_string())
+pub async fn set_stations_enabled(station_ids: Vec<String>, enabled: bool, app: AppHandle) -> Result<usize, RelayError> {
+    with_manager(&app, |manager| {
+        manager
+            .set_stations_enabled(&station_ids, enabled)
+            .map_err(|e| RelayError::database(format!("Failed to update stations: {}", e)))?
+            .ok_or_else(|| RelayError::not_found("One or more stations not found"))
+    })
+}
+
+/// Enables or disables every station with the given tag
+#[tauri::command]
+pub async fn set_stations_enabled_by_tag(tag: String, enabled: bool, app: AppHandle) -> Result<usize, RelayError> {
+    with_manager(&app, |manager| {
+        manager
+            .set_stations_enabled_by_tag(&tag, enabled)
+            .map_err(|e| RelayError::database(format!("Failed to update stations: {}", e)))
+    })
+}
+
+#[tauri::command]
+pub async fn duplicate_relay_station(station_id: String, new_name: String, app: AppHandle) -> Result<String, RelayError> {
+    let new_name = new_name.trim();
+    if new_name.is_empty() {
+        return Err(RelayError::invalid_input("Station name cannot be empty"));
     }
+
+    with_manager(&app, |manager| {
+        manager
+            .duplicate_station(&station_id, new_name)
+            .map_err(|e| RelayError::database(format!("Failed to duplicate station: {}", e)))?
+            .ok_or_else(|| RelayError::not_found("Station not found"))
+    })
 }
 
 #[tauri::command]
-pub async fn get_token_user_info(
-    station_id: String,
-    user_id: String,
-    app: AppHandle,
-) -> Result<UserInfo, String> {
-    let state: State<Mutex<Option<RelayStationManager>>> = app.state();
-    
-    // Get station data first, releasing the lock before async call
-    let station = {
-        let manager_lock = state.lock().map_err(|e| format!("Lock error: {}", e))?;
-        if let Some(manager) = manager_lock.as_ref() {
-            manager.get_station(&station_id).map_err(|e| format!("Failed to get station: {}", e))?
-        } else {
-            return Err("Relay station manager not initialized".to_string());
+pub async fn delete_relay_station(station_id: String, app: AppHandle) -> Result<String, RelayError> {
+    with_manager(&app, |manager| {
+        manager.delete_station(&station_id).map_err(|e| RelayError::database(format!("Failed to delete station: {}", e)))
+    })?;
+    invalidate_station_caches(&station_id);
+    Ok("Station deleted successfully".to_string())
+}
+
+/// Drops all cached station data (info, models, conditional GET state, balance alerts) so the
+/// next reads go to the stations
+#[tauri::command]
+pub async fn clear_all_caches() -> Result<String, RelayError> {
+    clear_station_caches();
+    log::info!("Cleared all relay station caches");
+    Ok("Caches cleared".to_string())
+}
+
+#[tauri::command]
+pub async fn list_deleted_stations(app: AppHandle) -> Result<Vec<RelayStation>, RelayError> {
+    with_manager(&app, |manager| {
+        manager.list_deleted_stations().map_err(|e| RelayError::database(format!("Failed to list deleted stations: {}", e)))
+    })
+}
+
+#[tauri::command]
+pub async fn restore_station(station_id: String, app: AppHandle) -> Result<String, RelayError> {
+    with_manager(&app, |manager| {
+        let restored = manager.restore_station(&station_id).map_err(|e| RelayError::database(format!("Failed to restore station: {}", e)))?;
+        if !restored {
+            return Err(RelayError::not_found("Station not found in recycle bin"));
         }
-    };
-    
-    if let Some(station) = station {
-        let adapter = create_adapter(&station.adapter);
-        // Use the provided user_id directly (from station configuration)
-        adapter.get_user_info(&station, &user_id).await.map_err(|e| format!("Failed to get user info: {}", e))
-    } else {
-        Err("Station not found".to_string())
-    }
+        Ok("Station restored successfully".to_string())
+    })
 }
 
 #[tauri::command]
-pub async fn get_station_logs(
-    station_id: String,
-    page: Option<usize>,
-    page_size: Option<usize>,
-    filters: Option<serde_json::Value>,
-    app: AppHandle,
-) -> Result<LogPaginationResponse, String> {
-    let state: State<Mutex<Option<RelayStationManager>>> = app.state();
-    
-    // Get the station first, releasing the lock before the async call
-    let station = {
-        let manager_lock = state.lock().map_err(|e| format!("Lock error: {}", e))?;
-        if let Some(manager) = manager_lock.as_ref() {
-            manager.get_station(&station_id).map_err(|e| format!("Failed to get station: {}", e))?
-        } else {
-            return Err("Relay station manager not initialized".to_string());
+pub async fn purge_station(station_id: String, app: AppHandle) -> Result<String, RelayError> {
+    with_manager(&app, |manager| {
+        let purged = manager.purge_station(&station_id).map_err(|e| RelayError::database(format!("Failed to purge station: {}", e)))?;
+        if !purged {
+            return Err(RelayError::not_found("Station not found in recycle bin"));
         }
-    };
-    
-    if let Some(station) = station {
-        let adapter = create_adapter(&station.adapter);
-        adapter.get_logs(&station, page, page_size, filters).await.map_err(|e| format!("Failed to get logs: {}", e))
-    } else {
-        Err("Station not found".to_string())
+        Ok("Station permanently deleted".to_string())
+    })
+}
+
+#[tauri::command]
+pub async fn get_station_info(station_id: String, app: AppHandle) -> Result<StationInfo, RelayError> {
+    let station = load_station(&app, &station_id)?;
+    if let Some(info) = cached_station_info(&station.id, station_info_ttl(&station)) {
+        return Ok(info);
     }
+    fetch_station_info(&station).await
 }
 
+/// Fetches station info from the station, bypassing and then refreshing the cache
 #[tauri::command]
-pub async fn test_station_connection(station_id: String, app: AppHandle) -> Result<ConnectionTestResult, String> {
-    let state: State<Mutex<Option<RelayStationManager>>> = app.state();
-    
-    // Get the station first, releasing the lock before the async call
-    let station = {
-        let manager_lock = state.lock().map_err(|e| format!("Lock error: {}", e))?;
-        if let Some(manager) = manager_lock.as_ref() {
-            manager.get_station(&station_id).map_err(|e| format!("Failed to get station: {}", e))?
-        } else {
-            return Err("Relay station manager not initialized".to_string());
-        }
-    };
-    
-    if let Some(station) = station {
-        let adapter = create_adapter(&station.adapter);
-        adapter.test_connection(&station).await.map_err(|e| format!("Failed to test connection: {}", e))
-    } else {
-        Err("Station not found".to_string())
+pub async fn refresh_station_info(station_id: String, app: AppHandle) -> Result<StationInfo, RelayError> {
+    let station = load_station(&app, &station_id)?;
+    fetch_station_info(&station).await
+}
+
+async fn fetch_station_info(station: &RelayStation) -> Result<StationInfo, RelayError> {
+    let adapter = create_adapter(&station.adapter);
+    let info = adapter.get_station_info(station).await.map_err(|e| RelayError::from(e).context("Failed to get station info"))?;
+
+    if let Ok(mut cache) = STATION_INFO_CACHE.lock() {
+        cache.insert(station.id.clone(), (Instant::now(), info.clone()));
     }
+    Ok(info)
 }
 
+/// Replaces a station's system token, but only after the station accepts the new one
 #[tauri::command]
-pub async fn api_user_self_groups(station_id: String, app: AppHandle) -> Result<serde_json::Value, String> {
-    let state: State<Mutex<Option<RelayStationManager>>> = app.state();
-    
-    // Get the station first, releasing the lock before the async call
-    let station = {
-        let manager_lock = state.lock().map_err(|e| format!("Lock error: {}", e))?;
-        if let Some(manager) = manager_lock.as_ref() {
-            manager.get_station(&station_id).map_err(|e| format!("Failed to get station: {}", e))?
-        } else {
-            return Err("Relay station manager not initialized".to_string());
-        }
-    };
-    
-    if let Some(station) = station {
-        let adapter = create_adapter(&station.adapter);
-        adapter.get_user_groups(&station).await.map_err(|e| format!("Failed to get user groups: {}", e))
-    } else {
-        Err("Station not found".to_string())
+pub async fn rotate_system_token(station_id: String, new_token: String, app: AppHandle) -> Result<String, RelayError> {
+    let new_token = new_token.trim();
+    if new_token.is_empty() {
+        return Err(RelayError::invalid_input("System token cannot be empty"));
     }
+
+    let mut candidate = load_station(&app, &station_id)?;
+    candidate.system_token = new_token.to_string();
+    let adapter = create_adapter(&candidate.adapter);
+    adapter
+        .validate_system_token(&candidate)
+        .await
+        .map_err(|e| RelayError::from(e).context("New system token was rejected"))?;
+
+    let updates = HashMap::from([("system_token".to_string(), serde_json::Value::String(new_token.to_string()))]);
+    with_manager(&app, |manager| {
+        manager.update_station(&station_id, &updates).map_err(|e| RelayError::database(format!("Failed to update station: {}", e)))
+    })?;
+    invalidate_station_caches(&station_id);
+
+    Ok("System token rotated successfully".to_string())
 }
 
+/// Swaps the system token and user id of two stations atomically, e.g. to promote a backup
+/// account. Either both stations change or neither does.
 #[tauri::command]
-pub async fn toggle_station_token(
-    station_id: String,
-    token_id: String,
-    enabled: bool,
-    app: AppHandle,
-) -> Result<RelayStationToken, String> {
-    let state: State<Mutex<Option<RelayStationManager>>> = app.state();
-    
-    // Get the station first, releasing the lock before the async call
-    let station = {
-        let manager_lock = state.lock().map_err(|e| format!("Lock error: {}", e))?;
-        if let Some(manager) = manager_lock.as_ref() {
-            manager.get_station(&station_id).map_err(|e| format!("Failed to get station: {}", e))?
-        } else {
-            return Err("Relay station manager not initialized".to_string());
+pub async fn swap_station_credentials(station_a: String, station_b: String, app: AppHandle) -> Result<String, RelayError> {
+    if station_a == station_b {
+        return Err(RelayError::invalid_input("Cannot swap a station's credentials with itself"));
+    }
+    with_manager(&app, |manager| {
+        manager
+            .swap_station_credentials(&station_a, &station_b)
+            .map_err(|e| RelayError::database(format!("Failed to swap station credentials: {}", e)))?
+            .then_some(())
+            .ok_or_else(|| RelayError::not_found("One or both stations not found"))
+    })?;
+    invalidate_station_caches(&station_a);
+    invalidate_station_caches(&station_b);
+
+    Ok("Station credentials swapped successfully".to_string())
+}
+
+#[tauri::command]
+pub async fn list_station_tokens(station_id: String, page: Option<usize>, size: Option<usize>, app: AppHandle) -> Result<TokenPaginationResponse, RelayError> {
+    let station = match load_station(&app, &station_id) {
+        Ok(station) => station,
+        Err(RelayError::ManagerUninitialized { .. }) | Err(RelayError::NotFound { .. }) => {
+            return Ok(TokenPaginationResponse {
+                items: Vec::new(),
+                page: 1,
+                page_size: paging::page_size(size),
+                total: 0,
+            });
         }
+        Err(e) => return Err(e),
     };
     
-    if let Some(station) = station {
-        let adapter = create_adapter(&station.adapter);
-        adapter.toggle_token(&station, &token_id, enabled).await.map_err(|e| format!("Failed to toggle token: {}", e))
-    } else {
-        Err("Station not found".to_string())
+    let adapter = create_adapter(&station.adapter);
+    let mut response = adapter.list_tokens(&station, page, size).await.map_err(|e| RelayError::from(e).context("Failed to list tokens"))?;
+    note_station_used(&app, &station);
+
+    let now = Utc::now().timestamp();
+    for token in &mut response.items {
+        token.annotate_expiry(now);
+    }
+    Ok(response)
+}
+
+/// Attempts made by `add_station_token` when the outcome of a create is unknown
+const CREATE_TOKEN_ATTEMPTS: usize = 3;
+
+/// Pause between create attempts
+const CREATE_TOKEN_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Whether a failed create may still have gone through upstream (timeouts, dropped
+/// connections, gateway errors), making a blind retry unsafe
+fn create_outcome_unknown(error: &RelayError) -> bool {
+    match error {
+        RelayError::Network { .. } => true,
+        RelayError::Upstream { status: Some(status), .. } => *status >= 500,
+        _ => false,
+    }
+}
+
+/// The newest token named `name` whose id isn't in `known_ids`, the ids listed before the first attempt
+fn find_created_token<'a>(tokens: &'a [RelayStationToken], name: &str, known_ids: &HashSet<String>) -> Option<&'a RelayStationToken> {
+    tokens
+        .iter()
+        .filter(|token| token.name == name && !known_ids.contains(&token.id))
+        .max_by_key(|token| token.created_at)
+}
+
+/// Ids of the station's tokens before a create, or `None` if the complete list can't be read
+async fn snapshot_token_ids(station: &RelayStation) -> Option<HashSet<String>> {
+    match fetch_all_tokens(station, TOKEN_LOOKUP_MAX_PAGES * TOKEN_LOOKUP_PAGE_SIZE).await {
+        Ok(existing) if !existing.truncated => Some(existing.tokens.into_iter().map(|token| token.id).collect()),
+        Ok(existing) => {
+            log::warn!("Token create on {} won't be retried: more than {} tokens to compare", station.name, existing.tokens.len());
+            None
+        }
+        Err(e) => {
+            log::warn!("Token create on {} won't be retried: listing existing tokens failed: {}", station.name, e);
+            None
+        }
     }
-}
\ No newline at end of file
+}
+
+/// Creates a token, retrying when the outcome is unknown. Every attempt carries the same
+/// idempotency key, sent upstream when the station sets `idempotency_header` in its
+/// adapter_config. For other stations the existing token ids are listed before the first attempt,
+/// and before each retry a token with the same name and a new id is taken as the landed create,
+/// so neither a pre-existing token nor a repeated create is mistaken for it. If that list can't
+/// be read completely, the create isn't retried.
+async fn create_token_with_retry(station: &RelayStation, token_data: &mut CreateTokenRequest) -> Result<RelayStationToken, RelayError> {
+    token_data.idempotency_key.get_or_insert_with(|| Uuid::new_v4().to_string());
+    let adapter = create_adapter(&station.adapter);
+    let known_ids = match http::idempotency_header(station) {
+        Some(_) => None,
+        None => Some(snapshot_token_ids(station).await),
+    };
+
+    let mut attempt = 1;
+    loop {
+        let error = match adapter.create_token(station, token_data).await {
+            Ok(token) => return Ok(token),
+            Err(e) => RelayError::from(e).context("Failed to create token"),
+        };
+        if attempt >= CREATE_TOKEN_ATTEMPTS || !create_outcome_unknown(&error) {
+            return Err(error);
+        }
+
+        if let Some(known_ids) = &known_ids {
+            // Without a complete before/after comparison we can't rule out a duplicate, so stop rather than guess
+            let Some(known_ids) = known_ids else {
+                return Err(error);
+            };
+            let existing = fetch_all_tokens(station, TOKEN_LOOKUP_MAX_PAGES * TOKEN_LOOKUP_PAGE_SIZE).await.map_err(|lookup| {
+                log::warn!("Not retrying token create on {}: lookup failed: {}", station.name, lookup);
+                error.clone()
+            })?;
+            if let Some(token) = find_created_token(&existing.tokens, &token_data.name, known_ids) {
+                log::info!("Token create on {} landed despite {}; not retrying", station.name, error);
+                return Ok(token.clone());
+            }
+        }
+
+        log::warn!("Retrying token create on {} (attempt {} of {}): {}", station.name, attempt + 1, CREATE_TOKEN_ATTEMPTS, error);
+        tokio::time::sleep(CREATE_TOKEN_RETRY_DELAY).await;
+        attempt += 1;
+    }
+}
+
+/// Creates a token. With `reject_duplicate_name`, first lists the station's tokens and fails
+/// with a conflict if one already has the same name.
+#[tauri::command]
+pub async fn add_station_token(
+    station_id: String,
+    mut token_data: CreateTokenRequest,
+    reject_duplicate_name: Option<bool>,
+    app: AppHandle,
+) -> Result<RelayStationToken, RelayError> {
+    normalize_allow_ips_field(&mut token_data.allow_ips)?;
+    check_model_limits(&app, &station_id, token_data.model_limits_list.as_deref()).await?;
+    let station = load_station(&app, &station_id)?;
+    if reject_duplicate_name.unwrap_or(false) {
+        let existing = fetch_all_tokens(&station, TOKEN_LOOKUP_MAX_PAGES * TOKEN_LOOKUP_PAGE_SIZE).await?;
+        if existing.truncated {
+            log::warn!("Duplicate name check for {} only covered the first {} tokens", station.name, existing.tokens.len());
+        }
+        if let Some(token) = find_token_named(&existing.tokens, &token_data.name) {
+            return Err(RelayError::conflict(format!("A token named '{}' already exists (id {})", token.name, token.id)));
+        }
+    }
+    create_token_with_retry(&station, &mut token_data).await
+}
+
+/// Returns the exact body `add_station_token` would send upstream, without creating the token
+#[tauri::command]
+pub async fn preview_create_token(
+    station_id: String,
+    mut token_data: CreateTokenRequest,
+    app: AppHandle,
+) -> Result<serde_json::Value, RelayError> {
+    normalize_allow_ips_field(&mut token_data.allow_ips)?;
+    let station = load_station(&app, &station_id)?;
+    let adapter = create_adapter(&station.adapter);
+    adapter.preview_create_token(&station, &token_data).map_err(|e| RelayError::from(e).context("Failed to preview token"))
+}
+
+#[tauri::command]
+pub async fn update_station_token(
+    station_id: String,
+    token_id: String,
+    mut token_data: UpdateTokenRequest,
+    app: AppHandle,
+) -> Result<RelayStationToken, RelayError> {
+    normalize_allow_ips_field(&mut token_data.allow_ips)?;
+    check_model_limits(&app, &station_id, token_data.model_limits_list.as_deref()).await?;
+    let station = load_station(&app, &station_id)?;
+    let adapter = create_adapter(&station.adapter);
+    adapter.update_token(&station, &token_id, &token_data).await.map_err(|e| RelayError::from(e).context("Failed to update token"))
+}
+
+#[tauri::command]
+pub async fn delete_station_token(
+    station_id: String,
+    token_id: String,
+    app: AppHandle,
+) -> Result<String, RelayError> {
+    let station = load_station(&app, &station_id)?;
+    let adapter = create_adapter(&station.adapter);
+    adapter.delete_token(&station, &token_id).await.map_err(|e| RelayError::from(e).context("Failed to delete token"))?;
+    Ok("Token deleted successfully".to_string())
+}
+
+/// Page size and page limit used when searching a station's token list for one token
+const TOKEN_LOOKUP_PAGE_SIZE: usize = 100;
+const TOKEN_LOOKUP_MAX_PAGES: usize = 50;
+
+/// Most tokens `list_all_station_tokens` returns before reporting the list as truncated
+const ALL_TOKENS_CAP: usize = 2000;
+
+/// The first token whose name matches `name`, ignoring surrounding whitespace
+fn find_token_named<'a>(tokens: &'a [RelayStationToken], name: &str) -> Option<&'a RelayStationToken> {
+    let name = name.trim();
+    tokens.iter().find(|token| token.name.trim() == name)
+}
+
+/// Every token on a station, up to a cap
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AllStationTokens {
+    pub tokens: Vec<RelayStationToken>,
+    pub truncated: bool, // The cap was hit, or the station kept repeating pages
+}
+
+/// Pages through the station's token list until a short page, deduplicating by id. Stops at
+/// `cap` tokens, or when a full page brings nothing new (an upstream ignoring the page number),
+/// and reports either as truncated.
+async fn fetch_all_tokens(station: &RelayStation, cap: usize) -> Result<AllStationTokens, RelayError> {
+    let adapter = create_adapter(&station.adapter);
+    let mut seen = HashSet::new();
+    let mut tokens = Vec::new();
+    let mut page = 1;
+    loop {
+        let response = adapter
+            .list_tokens(station, Some(page), Some(TOKEN_LOOKUP_PAGE_SIZE))
+            .await
+            .map_err(|e| RelayError::from(e).context("Failed to list tokens"))?;
+        let page_len = response.items.len();
+        let before = tokens.len();
+        tokens.extend(response.items.into_iter().filter(|token| seen.insert(token.id.clone())));
+
+        if page_len < TOKEN_LOOKUP_PAGE_SIZE {
+            return Ok(AllStationTokens { tokens, truncated: false });
+        }
+        if tokens.len() >= cap || tokens.len() == before {
+            tokens.truncate(cap);
+            return Ok(AllStationTokens { tokens, truncated: true });
+        }
+        page += 1;
+    }
+}
+
+/// Fetches every page of the station's tokens (up to 2000), rather than the single page `list_station_tokens` returns
+#[tauri::command]
+pub async fn list_all_station_tokens(station_id: String, app: AppHandle) -> Result<AllStationTokens, RelayError> {
+    let station = load_station(&app, &station_id)?;
+    let mut all = fetch_all_tokens(&station, ALL_TOKENS_CAP).await?;
+    note_station_used(&app, &station);
+
+    let now = Utc::now().timestamp();
+    for token in &mut all.tokens {
+        token.annotate_expiry(now);
+    }
+    if all.truncated {
+        log::warn!("Token list for {} truncated at {} tokens", station.name, all.tokens.len());
+    }
+    Ok(all)
+}
+
+/// Fetches every token on the station and mirrors them into the local store for offline browsing
+#[tauri::command]
+pub async fn sync_station_tokens(station_id: String, app: AppHandle) -> Result<TokenSyncSummary, RelayError> {
+    let station = load_station(&app, &station_id)?;
+
+    let cap = TOKEN_LOOKUP_MAX_PAGES * TOKEN_LOOKUP_PAGE_SIZE;
+    let all = fetch_all_tokens(&station, cap).await?;
+    if all.truncated {
+        // Marking the unfetched remainder as deleted would be wrong
+        return Err(RelayError::invalid_input(format!("Station has more than {} tokens; sync aborted", cap)));
+    }
+    let remote = all.tokens;
+
+    let station_id = station.id.clone();
+    let summary = with_manager_blocking(&app, move |manager| {
+        manager
+            .sync_tokens(&station_id, &remote, Utc::now().timestamp())
+            .map_err(|e| RelayError::database(format!("Failed to sync tokens: {}", e)))
+    })
+    .await?;
+    note_station_used(&app, &station);
+    log::info!(
+        "Synced tokens for {}: {} added, {} updated, {} removed",
+        station.name, summary.added, summary.updated, summary.removed
+    );
+    Ok(summary)
+}
+
+/// Tokens last synced from the station, readable without contacting it
+#[tauri::command]
+pub async fn list_cached_station_tokens(station_id: String, app: AppHandle) -> Result<Vec<RelayStationToken>, RelayError> {
+    let mut tokens = with_manager_blocking(&app, move |manager| {
+        manager.list_cached_tokens(&station_id).map_err(|e| RelayError::database(format!("Failed to list cached tokens: {}", e)))
+    })
+    .await?;
+    let now = Utc::now().timestamp();
+    for token in &mut tokens {
+        token.annotate_expiry(now);
+    }
+    Ok(tokens)
+}
+
+/// Synced tokens with less than `threshold` quota units remaining, lowest first
+#[tauri::command]
+pub async fn list_low_quota_tokens(station_id: String, threshold: i64, app: AppHandle) -> Result<Vec<RelayStationToken>, RelayError> {
+    with_manager_blocking(&app, move |manager| {
+        manager
+            .list_low_quota_tokens(&station_id, threshold)
+            .map_err(|e| RelayError::database(format!("Failed to list low quota tokens: {}", e)))
+    })
+    .await
+}
+
+/// Finds a token with its key, checking the local store before paging through the station's token list
+async fn find_station_token(app: &AppHandle, station: &RelayStation, token_id: &str) -> Result<RelayStationToken, RelayError> {
+    let local = with_manager(app, |manager| {
+        manager.get_token(&station.id, token_id).map_err(|e| RelayError::database(format!("Failed to get token: {}", e)))
+    })?;
+    if let Some(token) = local.filter(|token| !token.token.is_empty()) {
+        return Ok(token);
+    }
+    find_upstream_token(station, token_id).await
+}
+
+/// Pages through the station's token list until the token is found
+async fn find_upstream_token(station: &RelayStation, token_id: &str) -> Result<RelayStationToken, RelayError> {
+    let adapter = create_adapter(&station.adapter);
+    for page in 1..=TOKEN_LOOKUP_MAX_PAGES {
+        let tokens = adapter
+            .list_tokens(station, Some(page), Some(TOKEN_LOOKUP_PAGE_SIZE))
+            .await
+            .map_err(|e| RelayError::from(e).context("Failed to list tokens"))?;
+        let page_len = tokens.items.len();
+        if let Some(token) = tokens.items.into_iter().find(|token| token.id == token_id) {
+            return Ok(token);
+        }
+        if page_len < TOKEN_LOOKUP_PAGE_SIZE {
+            break;
+        }
+    }
+
+    Err(RelayError::not_found("Token not found"))
+}
+
+/// Builds a request that recreates `token`'s settings on another station.
+///
+/// Model limits and allowed IPs aren't mapped onto `RelayStationToken`, so they're read from
+/// the raw upstream object kept in its metadata.
+pub fn clone_token_request(token: &RelayStationToken) -> CreateTokenRequest {
+    let raw = token.metadata.as_ref().and_then(|metadata| metadata.get("raw"));
+    let raw_str = |key: &str| raw.and_then(|raw| raw[key].as_str()).filter(|s| !s.is_empty()).map(|s| s.to_string());
+
+    CreateTokenRequest {
+        name: token.name.clone(),
+        remain_quota: token.remain_quota,
+        expired_time: Some(token.expires_at.unwrap_or(-1)),
+        unlimited_quota: token.unlimited_quota,
+        model_limits_enabled: raw.and_then(|raw| raw["model_limits_enabled"].as_bool()),
+        model_limits: raw_str("model_limits"),
+        model_limits_list: None,
+        group: token.group.clone().filter(|g| !g.is_empty()),
+        allow_ips: raw_str("allow_ips"),
+        idempotency_key: None,
+    }
+}
+
+/// Group names from a `get_user_groups` response (`{"data": {"<group>": {...}}}`)
+fn group_names(groups: &serde_json::Value) -> Option<Vec<String>> {
+    groups["data"].as_object().map(|data| data.keys().cloned().collect())
+}
+
+/// Recreates a token on another station with the source token's name, quota, model limits and group.
+///
+/// The new token gets its own key. Groups are station-specific, so the group is dropped when the
+/// destination doesn't offer it.
+#[tauri::command]
+pub async fn clone_token_to_station(
+    source_station_id: String,
+    token_id: String,
+    dest_station_id: String,
+    app: AppHandle,
+) -> Result<RelayStationToken, RelayError> {
+    let source = load_station(&app, &source_station_id)?;
+    let dest = load_station(&app, &dest_station_id)?;
+    let token = find_upstream_token(&source, &token_id).await?;
+
+    let mut request = clone_token_request(&token);
+    normalize_allow_ips_field(&mut request.allow_ips)?;
+    let dest_adapter = create_adapter(&dest.adapter);
+    if let Some(group) = request.group.clone() {
+        match dest_adapter.get_user_groups(&dest).await {
+            Ok(groups) => {
+                if group_names(&groups).is_some_and(|names| !names.contains(&group)) {
+                    log::warn!("Group {} not offered by {}, using its default group", group, dest.name);
+                    request.group = None;
+                }
+            }
+            Err(e) => log::warn!("Could not list groups on {}, keeping group {}: {}", dest.name, group, e),
+        }
+    }
+
+    dest_adapter
+        .create_token(&dest, &request)
+        .await
+        .map_err(|e| RelayError::from(e).context("Failed to create token on destination station"))
+}
+
+/// Request rates adapter calls are held to, so users can see why probes are spaced out
+#[tauri::command]
+pub async fn get_relay_rate_limits() -> Result<RateLimitConfig, RelayError> {
+    Ok(rate_limit::config())
+}
+
+/// Sets the global and per-host requests per second for adapter calls; 0 disables a limit
+#[tauri::command]
+pub async fn set_relay_rate_limits(config: RateLimitConfig) -> Result<RateLimitConfig, RelayError> {
+    for (name, rps) in [("global_rps", config.global_rps), ("per_host_rps", config.per_host_rps)] {
+        if !rps.is_finite() || rps < 0.0 {
+            return Err(RelayError::invalid_input(format!("{} must be a non-negative number", name)));
+        }
+    }
+    rate_limit::set_config(config);
+    Ok(config)
+}
+
+/// Page size used for logs and token lists when the caller doesn't pass one
+#[tauri::command]
+pub async fn get_default_page_size() -> Result<usize, RelayError> {
+    Ok(paging::default_page_size())
+}
+
+/// Sets the default page size for logs and token lists; explicit per-call sizes still win
+#[tauri::command]
+pub async fn set_default_page_size(page_size: usize) -> Result<usize, RelayError> {
+    if page_size == 0 || page_size > MAX_PAGE_SIZE {
+        return Err(RelayError::invalid_input(format!("Page size must be between 1 and {}", MAX_PAGE_SIZE)));
+    }
+    paging::set_default_page_size(page_size);
+    Ok(page_size)
+}
+
+/// Smallest response size limit that can be configured
+const MIN_MAX_RESPONSE_BYTES: usize = 64 * 1024;
+
+/// Largest response body, in bytes, read from a station before the request fails
+#[tauri::command]
+pub async fn get_max_response_size() -> Result<usize, RelayError> {
+    Ok(http::max_response_bytes())
+}
+
+/// Sets the response size limit (default 16 MB) guarding against runaway or malicious stations
+#[tauri::command]
+pub async fn set_max_response_size(max_bytes: usize) -> Result<usize, RelayError> {
+    if max_bytes < MIN_MAX_RESPONSE_BYTES {
+        return Err(RelayError::invalid_input(format!("Response size limit must be at least {} bytes", MIN_MAX_RESPONSE_BYTES)));
+    }
+    http::set_max_response_bytes(max_bytes);
+    Ok(max_bytes)
+}
+
+/// Saves a provider that points at the station with the given token's key and returns its id
+#[tauri::command]
+pub async fn create_provider_from_token(
+    station_id: String,
+    token_id: String,
+    provider_name: String,
+    app: AppHandle,
+) -> Result<String, RelayError> {
+    let station = load_station(&app, &station_id)?;
+    let token = find_station_token(&app, &station, &token_id).await?;
+    if token.token.is_empty() {
+        return Err(RelayError::invalid_input("Token has no key"));
+    }
+
+    // Stations return keys without the `sk-` prefix clients expect
+    let auth_token = if token.token.starts_with("sk-") {
+        token.token.clone()
+    } else {
+        format!("sk-{}", token.token)
+    };
+    let name = match provider_name.trim() {
+        "" => format!("{} - {}", station.name, token.name),
+        name => name.to_string(),
+    };
+
+    let config = ProviderConfig {
+        id: format!("token-{}-{}", station.id, token.id),
+        name,
+        description: i18n::t_with_args("provider.created_from_token", &[("station", &station.name), ("token", &token.name)]),
+        base_url: station.api_url.clone(),
+        auth_token: Some(auth_token),
+        api_key: None,
+        model: None,
+        small_fast_model: None,
+        extra_env: None,
+        fallback_chain: None,
+    };
+    insert_provider_config(config.clone()).map_err(|e| match e {
+        AddProviderError::DuplicateId(message) => RelayError::conflict(message),
+        AddProviderError::Other(message) => RelayError::invalid_input(message),
+    })?;
+
+    Ok(config.id)
+}
+
+#[tauri::command]
+pub async fn delete_station_tokens(
+    station_id: String,
+    token_ids: Vec<String>,
+    app: AppHandle,
+) -> Result<HashMap<String, BatchItemResult>, RelayError> {
+    let station = load_station(&app, &station_id)?;
+    let adapter = create_adapter(&station.adapter);
+    Ok(adapter.delete_tokens(&station, &token_ids).await)
+}
+
+#[tauri::command]
+pub async fn get_station_models(station_id: String, app: AppHandle) -> Result<Vec<String>, RelayError> {
+    if let Ok(cache) = MODELS_CACHE.lock() {
+        if let Some((fetched_at, models)) = cache.get(&station_id) {
+            if fetched_at.elapsed() < MODELS_CACHE_TTL {
+                return Ok(models.clone());
+            }
+        }
+    }
+
+    let station = load_station(&app, &station_id)?;
+    let adapter = create_adapter(&station.adapter);
+    let models = adapter.get_models(&station).await.map_err(|e| RelayError::from(e).context("Failed to get models"))?;
+
+    if let Ok(mut cache) = MODELS_CACHE.lock() {
+        cache.insert(station_id, (Instant::now(), models.clone()));
+    }
+    Ok(models)
+}
+
+/// Groups available for the station's tokens, cached for a couple of minutes like the model list
+#[tauri::command]
+pub async fn get_station_groups(station_id: String, app: AppHandle) -> Result<Vec<GroupInfo>, RelayError> {
+    if let Ok(cache) = GROUPS_CACHE.lock() {
+        if let Some((fetched_at, groups)) = cache.get(&station_id) {
+            if fetched_at.elapsed() < GROUPS_CACHE_TTL {
+                return Ok(groups.clone());
+            }
+        }
+    }
+
+    let station = load_station(&app, &station_id)?;
+    let adapter = create_adapter(&station.adapter);
+    let groups = adapter
+        .get_station_groups(&station)
+        .await
+        .map_err(|e| RelayError::from(e).context("Failed to get groups"))?;
+
+    if let Ok(mut cache) = GROUPS_CACHE.lock() {
+        cache.insert(station_id, (Instant::now(), groups.clone()));
+    }
+    Ok(groups)
+}
+
+#[tauri::command]
+pub async fn get_token_user_info(
+    station_id: String,
+    user_id: String,
+    app: AppHandle,
+) -> Result<UserInfo, RelayError> {
+    let station = load_station(&app, &station_id)?;
+    let adapter = create_adapter(&station.adapter);
+    // Use the provided user_id directly (from station configuration)
+    let user_info = adapter.get_user_info(&station, &user_id).await.map_err(|e| RelayError::from(e).context("Failed to get user info"))?;
+    note_station_used(&app, &station);
+    Ok(user_info)
+}
+
+/// Checks every enabled station's balance and returns those below `threshold_dollars`.
+/// Emits `balance-low` for stations that have newly dropped below the threshold, and records
+/// a quota sample per station for `get_station_quota_timeseries`.
+#[tauri::command]
+pub async fn check_balance_alerts(threshold_dollars: f64, app: AppHandle) -> Result<Vec<BalanceAlert>, RelayError> {
+    let stations = with_manager(&app, |manager| {
+        manager.list_stations().map_err(|e| RelayError::database(format!("Failed to list stations: {}", e)))
+    })?;
+
+    let checks: Vec<(RelayStation, Option<UserInfo>)> = stream::iter(stations.into_iter().filter(|station| station.enabled))
+        .map(|station| async move {
+            let adapter = create_adapter(&station.adapter);
+            let user_id = station.user_id.clone().unwrap_or_default();
+            let user_info = match adapter.get_user_info(&station, &user_id).await {
+                Ok(user_info) => Some(user_info),
+                Err(e) => {
+                    log::warn!("Skipping balance check for station {}: {}", station.name, e);
+                    None
+                }
+            };
+            (station, user_info)
+        })
+        .buffer_unordered(BATCH_CONCURRENCY)
+        .collect()
+        .await;
+
+    let now = Utc::now().timestamp();
+    let samples: Vec<QuotaSample> = checks
+        .iter()
+        .filter_map(|(station, user_info)| QuotaSample::from_user_info(&station.id, user_info.as_ref()?, now))
+        .collect();
+    with_manager_blocking(&app, move |manager| {
+        for sample in &samples {
+            if let Err(e) = manager.record_quota_sample(sample) {
+                log::warn!("Failed to record quota sample for station {}: {}", sample.station_id, e);
+            }
+        }
+        Ok(())
+    })
+    .await?;
+
+    let checks = checks.into_iter().map(|(station, user_info)| (station, user_info.and_then(|info| info.balance_remaining)));
+    let mut alerts = Vec::new();
+    let mut low_stations = LOW_BALANCE_STATIONS.lock().map_err(|e| RelayError::database(format!("Lock error: {}", e)))?;
+    for (station, balance) in checks {
+        let Some(balance_remaining) = balance else { continue };
+        if balance_remaining >= threshold_dollars {
+            low_stations.remove(&station.id);
+            continue;
+        }
+
+        let alert = BalanceAlert {
+            station_id: station.id.clone(),
+            station_name: station.name.clone(),
+            balance_remaining,
+        };
+        if low_stations.insert(station.id) {
+            let _ = app.emit("balance-low", &alert);
+        }
+        alerts.push(alert);
+    }
+
+    Ok(alerts)
+}
+
+/// Page size and page limit used when scanning a station's logs over a time range
+const LOG_SCAN_PAGE_SIZE: usize = 100;
+const LOG_SCAN_MAX_PAGES: usize = 100;
+
+/// Sums `quota` over log entries whose timestamp falls within `start..=end`, returning (quota, entries counted)
+fn sum_log_quota(entries: &[StationLogEntry], start: i64, end: i64) -> (i64, usize) {
+    entries
+        .iter()
+        .filter(|entry| entry.timestamp >= start && entry.timestamp <= end)
+        .fold((0, 0), |(quota, count), entry| (quota + entry.quota.unwrap_or(0), count + 1))
+}
+
+/// Pages through a station's logs for `start..=end`, optionally limited to one token name,
+/// returning the entries in range and whether the scan reached the end before hitting `LOG_SCAN_MAX_PAGES`
+async fn station_logs_in_range(station: &RelayStation, start: i64, end: i64, token_name: Option<&str>) -> Result<(Vec<StationLogEntry>, bool), RelayError> {
+    scan_station_logs(station, start, end, token_name, LOG_SCAN_MAX_PAGES).await
+}
+
+/// `station_logs_in_range` with a caller-chosen page limit
+async fn scan_station_logs(
+    station: &RelayStation,
+    start: i64,
+    end: i64,
+    token_name: Option<&str>,
+    max_pages: usize,
+) -> Result<(Vec<StationLogEntry>, bool), RelayError> {
+    let adapter = create_adapter(&station.adapter);
+    // The log filters take minutes; entries are re-checked against the exact range below
+    let minute = |ts: i64| chrono::DateTime::from_timestamp(ts, 0).map(|dt| dt.format("%Y-%m-%dT%H:%M").to_string()).unwrap_or_default();
+    let mut filters = serde_json::json!({ "startTime": minute(start), "endTime": minute(end + 59) });
+    if let Some(name) = token_name {
+        filters["tokenName"] = serde_json::Value::String(name.to_string());
+    }
+
+    let mut entries = Vec::new();
+    let mut cursor: Option<String> = None;
+    for page in 1..=max_pages {
+        let logs = adapter
+            .get_logs(station, Some(page), Some(LOG_SCAN_PAGE_SIZE), cursor.as_deref(), Some(filters.clone()))
+            .await
+            .map_err(|e| RelayError::from(e).context("Failed to get logs"))?;
+        let page_len = logs.items.len();
+        entries.extend(logs.items.into_iter().filter(|entry| {
+            // Stations that ignore the token filter still return other tokens' entries
+            entry.timestamp >= start
+                && entry.timestamp <= end
+                && token_name.map_or(true, |name| entry.token_name.as_deref() == Some(name))
+        }));
+        if page_len < LOG_SCAN_PAGE_SIZE {
+            return Ok((entries, true));
+        }
+        cursor = logs.next_cursor;
+    }
+    Ok((entries, false))
+}
+
+/// Whether an entry's message, model or token name contains `query`, which must already be lowercase
+fn log_matches(entry: &StationLogEntry, query: &str) -> bool {
+    [Some(&entry.message), entry.model_name.as_ref(), entry.token_name.as_ref()]
+        .into_iter()
+        .flatten()
+        .any(|field| field.to_lowercase().contains(query))
+}
+
+/// Tallies log entries by their upstream `type`, returning (total, errors, histogram).
+/// Errors are entries `get_logs` maps to the "error" level.
+fn log_type_histogram(entries: &[StationLogEntry]) -> (usize, usize, BTreeMap<String, usize>) {
+    let mut histogram = BTreeMap::new();
+    let mut errors = 0;
+    for entry in entries {
+        let log_type = entry
+            .metadata
+            .as_ref()
+            .and_then(|metadata| metadata.get("raw"))
+            .and_then(|raw| raw["type"].as_i64())
+            .map(|t| t.to_string())
+            .unwrap_or_else(|| "unknown".to_string());
+        *histogram.entry(log_type).or_insert(0) += 1;
+        if entry.level == "error" {
+            errors += 1;
+        }
+    }
+    (entries.len(), errors, histogram)
+}
+
+/// Nearest-rank percentiles of `times`, which is sorted in place
+fn latency_percentiles(times: &mut [i64]) -> LatencyPercentiles {
+    times.sort_unstable();
+    let rank = |p: usize| times.get((times.len() * p).div_ceil(100).max(1) - 1).copied();
+    LatencyPercentiles { count: times.len(), p50: rank(50), p90: rank(90), p99: rank(99), max: times.last().copied() }
+}
+
+/// Splits the entries' response times into (overall, streaming, non-streaming) percentiles
+fn summarize_latency(entries: &[StationLogEntry]) -> (LatencyPercentiles, LatencyPercentiles, LatencyPercentiles) {
+    let (mut overall, mut streaming, mut non_streaming) = (Vec::new(), Vec::new(), Vec::new());
+    for entry in entries {
+        let Some(use_time) = entry.use_time.filter(|t| *t >= 0) else { continue };
+        overall.push(use_time);
+        match entry.is_stream {
+            Some(true) => streaming.push(use_time),
+            Some(false) => non_streaming.push(use_time),
+            None => {}
+        }
+    }
+    (latency_percentiles(&mut overall), latency_percentiles(&mut streaming), latency_percentiles(&mut non_streaming))
+}
+
+/// Default window for `search_station_logs`
+const DEFAULT_LOG_SEARCH_WINDOW_SECS: i64 = 24 * 60 * 60;
+
+/// Largest number of log entries `search_station_logs` may be asked to scan
+const MAX_LOG_SEARCH_SCAN: usize = 50_000;
+
+/// Case-insensitive search over the message, model and token name of a station's logs from the
+/// last `window` seconds (default one day). The station can't search text itself, so up to
+/// `max_scan` entries (default 10,000) are fetched and filtered here; matches are newest first
+/// and paged with `page`/`page_size`. Pass an `operation_id` to allow `cancel_operation`.
+#[tauri::command]
+pub async fn search_station_logs(
+    station_id: String,
+    query: String,
+    window: Option<i64>,
+    page: Option<usize>,
+    page_size: Option<usize>,
+    max_scan: Option<usize>,
+    operation_id: Option<String>,
+    app: AppHandle,
+) -> Result<LogSearchResponse, RelayError> {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return Err(RelayError::invalid_input("Search query is required"));
+    }
+    let window = window.unwrap_or(DEFAULT_LOG_SEARCH_WINDOW_SECS);
+    if window <= 0 {
+        return Err(RelayError::invalid_input("window must be a positive number of seconds"));
+    }
+    let max_scan = max_scan.unwrap_or(LOG_SCAN_MAX_PAGES * LOG_SCAN_PAGE_SIZE);
+    if max_scan == 0 || max_scan > MAX_LOG_SEARCH_SCAN {
+        return Err(RelayError::invalid_input(format!("max_scan must be between 1 and {}", MAX_LOG_SEARCH_SCAN)));
+    }
+    let page = page.unwrap_or(1).max(1);
+    let page_size = paging::page_size(page_size).max(1);
+    let station = load_station(&app, &station_id)?;
+    let end = Utc::now().timestamp();
+    let start = end - window;
+
+    let (entries, complete) = cancellable(operation_id, scan_station_logs(&station, start, end, None, max_scan.div_ceil(LOG_SCAN_PAGE_SIZE))).await?;
+    note_station_used(&app, &station);
+    let scanned = entries.len();
+    let mut matches: Vec<StationLogEntry> = entries.into_iter().filter(|entry| log_matches(entry, &query)).collect();
+    matches.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+    let total = matches.len();
+    let items = matches.into_iter().skip((page - 1) * page_size).take(page_size).collect();
+
+    Ok(LogSearchResponse { items, page, page_size, total, scanned, complete })
+}
+
+/// Default window for `get_station_error_rate`
+const DEFAULT_ERROR_RATE_WINDOW_SECS: i64 = 60 * 60;
+
+/// Share of a station's logged requests that errored over the last `window` seconds (default one hour),
+/// with a histogram of the raw upstream log types
+#[tauri::command]
+pub async fn get_station_error_rate(station_id: String, window: Option<i64>, app: AppHandle) -> Result<StationErrorRate, RelayError> {
+    let window = window.unwrap_or(DEFAULT_ERROR_RATE_WINDOW_SECS);
+    if window <= 0 {
+        return Err(RelayError::invalid_input("window must be a positive number of seconds"));
+    }
+    let station = load_station(&app, &station_id)?;
+    let end = Utc::now().timestamp();
+    let start = end - window;
+
+    let (entries, complete) = station_logs_in_range(&station, start, end, None).await?;
+    let (total, errors, type_counts) = log_type_histogram(&entries);
+    Ok(StationErrorRate {
+        station_id,
+        start,
+        end,
+        total,
+        errors,
+        error_rate: if total == 0 { 0.0 } else { errors as f64 / total as f64 },
+        type_counts,
+        complete,
+    })
+}
+
+/// Default window for `get_station_latency_stats`
+const DEFAULT_LATENCY_WINDOW_SECS: i64 = 24 * 60 * 60;
+
+/// p50/p90/p99 and max of the `use_time` of a station's logged requests over the last `window`
+/// seconds (default one day), overall and split by streaming. At most `LOG_SCAN_MAX_PAGES` pages
+/// of logs are scanned; `complete` is false when the window held more.
+#[tauri::command]
+pub async fn get_station_latency_stats(station_id: String, window: Option<i64>, app: AppHandle) -> Result<StationLatencyStats, RelayError> {
+    let window = window.unwrap_or(DEFAULT_LATENCY_WINDOW_SECS);
+    if window <= 0 {
+        return Err(RelayError::invalid_input("window must be a positive number of seconds"));
+    }
+    let station = load_station(&app, &station_id)?;
+    let end = Utc::now().timestamp();
+    let start = end - window;
+
+    let (entries, complete) = station_logs_in_range(&station, start, end, None).await?;
+    note_station_used(&app, &station);
+    let (overall, streaming, non_streaming) = summarize_latency(&entries);
+    Ok(StationLatencyStats { station_id, start, end, overall, streaming, non_streaming, scanned: entries.len(), complete })
+}
+
+/// Default and maximum number of days covered by `get_usage_dashboard`
+const DEFAULT_USAGE_DAYS: u32 = 7;
+const MAX_USAGE_DAYS: u32 = 90;
+
+/// Per-day, per-model usage over the last `days` UTC days (default 7, today included). Uses the
+/// station's stats endpoint when it has one, otherwise aggregates its logs.
+#[tauri::command]
+pub async fn get_usage_dashboard(station_id: String, days: Option<u32>, app: AppHandle) -> Result<UsageDashboard, RelayError> {
+    let days = days.unwrap_or(DEFAULT_USAGE_DAYS);
+    if days == 0 || days > MAX_USAGE_DAYS {
+        return Err(RelayError::invalid_input(format!("days must be between 1 and {}", MAX_USAGE_DAYS)));
+    }
+    let station = load_station(&app, &station_id)?;
+    let end = Utc::now().timestamp();
+    let start = end - end.rem_euclid(86400) - (days as i64 - 1) * 86400;
+
+    let adapter = create_adapter(&station.adapter);
+    let (days, source, complete) = match adapter.get_usage_dashboard(&station, start, end).await.map_err(RelayError::from) {
+        Ok(days) => (days, "dashboard", true),
+        Err(RelayError::Unsupported { message }) => {
+            log::debug!("Aggregating logs for {} usage: {}", station.name, message);
+            let (entries, complete) = station_logs_in_range(&station, start, end, None).await?;
+            let rows = entries.into_iter().map(|entry| {
+                let tokens = entry.prompt_tokens.unwrap_or(0) + entry.completion_tokens.unwrap_or(0);
+                (entry.timestamp, entry.model_name.unwrap_or_default(), 1, tokens, entry.quota.unwrap_or(0))
+            });
+            (aggregate_daily_usage(rows), "logs", complete)
+        }
+        Err(e) => return Err(e.context("Failed to get usage dashboard")),
+    };
+    note_station_used(&app, &station);
+
+    Ok(UsageDashboard { station_id, start, end, days, source: source.to_string(), complete })
+}
+
+/// Usage of one token over the last `days` UTC days (default 7, today included): its quota from the
+/// token record, plus request, token and quota totals and the most recent entries from its logs.
+/// Logs are matched on the token's name, so tokens sharing a name are counted together.
+#[tauri::command]
+pub async fn get_token_usage(station_id: String, token_id: String, days: Option<u32>, app: AppHandle) -> Result<TokenUsage, RelayError> {
+    let days = days.unwrap_or(DEFAULT_USAGE_DAYS);
+    if days == 0 || days > MAX_USAGE_DAYS {
+        return Err(RelayError::invalid_input(format!("days must be between 1 and {}", MAX_USAGE_DAYS)));
+    }
+    let station = load_station(&app, &station_id)?;
+    // The upstream record carries the current quota; a cached copy may be stale
+    let token = find_upstream_token(&station, &token_id).await?;
+    let end = Utc::now().timestamp();
+    let start = end - end.rem_euclid(86400) - (days as i64 - 1) * 86400;
+
+    let (entries, complete) = station_logs_in_range(&station, start, end, Some(&token.name)).await?;
+    note_station_used(&app, &station);
+    Ok(summarize_token_usage(&token, entries, start, end, complete))
+}
+
+/// Quota units per dollar for converting a station's logs: the value stored in adapter_config,
+/// otherwise whatever the station's own adapter reports in its info
+async fn station_quota_per_unit(station: &RelayStation) -> Result<f64, RelayError> {
+    let stored = station
+        .adapter_config
+        .as_ref()
+        .and_then(|config| config.get(QUOTA_PER_UNIT_KEY))
+        .and_then(|v| v.as_f64())
+        .filter(|&quota_per_unit| quota_per_unit > 0.0);
+    match stored {
+        Some(quota_per_unit) => Ok(quota_per_unit),
+        None => discover_station_quota_per_unit(station).await.map(|quota_per_unit| quota_per_unit as f64),
+    }
+}
+
+/// Total spend per enabled station between `start` and `end` (Unix seconds, inclusive), from
+/// each station's logs converted with its `quota_per_unit`. A station that can't be read, or
+/// whose adapter can't provide a `quota_per_unit`, is listed with an error instead of failing
+/// the whole total; in the latter case its quota is still summed but its amount stays 0.
+#[tauri::command]
+pub async fn get_total_spend(start: i64, end: i64, app: AppHandle) -> Result<TotalSpend, RelayError> {
+    if start > end {
+        return Err(RelayError::invalid_input("start must not be after end"));
+    }
+    let stations = with_manager(&app, |manager| {
+        manager.list_stations().map_err(|e| RelayError::database(format!("Failed to list stations: {}", e)))
+    })?;
+
+    let mut spends: Vec<StationSpend> = stream::iter(stations.into_iter().filter(|station| station.enabled))
+        .map(|station| async move {
+            let mut spend = StationSpend {
+                station_id: station.id.clone(),
+                station_name: station.name.clone(),
+                quota: 0,
+                quota_per_unit: 0.0,
+                amount: 0.0,
+                request_count: 0,
+                error: None,
+            };
+            match station_logs_in_range(&station, start, end, None).await {
+                Ok((entries, complete)) => {
+                    let (quota, count) = sum_log_quota(&entries, start, end);
+                    spend.quota = quota;
+                    spend.request_count = count;
+                    match station_quota_per_unit(&station).await {
+                        Ok(quota_per_unit) => {
+                            spend.quota_per_unit = quota_per_unit;
+                            spend.amount = quota as f64 / quota_per_unit;
+                            if !complete {
+                                spend.error = Some(format!("Only the first {} log entries were counted", LOG_SCAN_MAX_PAGES * LOG_SCAN_PAGE_SIZE));
+                            }
+                        }
+                        Err(e) => {
+                            spend.error = Some(format!("Quota can't be converted to dollars: {}", e.message()));
+                        }
+                    }
+                }
+                Err(e) => {
+                    log::warn!("Skipping spend for station {}: {}", station.name, e);
+                    spend.error = Some(e.message().to_string());
+                }
+            }
+            spend
+        })
+        .buffer_unordered(BATCH_CONCURRENCY)
+        .collect()
+        .await;
+
+    spends.sort_by(|a, b| a.station_name.cmp(&b.station_name));
+    let total_amount = spends.iter().map(|spend| spend.amount).sum();
+    Ok(TotalSpend { start, end, stations: spends, total_amount })
+}
+
+/// Balance snapshots for a station since `since` (Unix seconds; default all), oldest first
+#[tauri::command]
+pub async fn get_station_quota_timeseries(station_id: String, since: Option<i64>, app: AppHandle) -> Result<Vec<QuotaSample>, RelayError> {
+    with_manager_blocking(&app, move |manager| {
+        manager
+            .quota_samples(&station_id, since.unwrap_or(0))
+            .map_err(|e| RelayError::database(format!("Failed to load quota history: {}", e)))
+    })
+    .await
+}
+
+/// One page of a station's logs. Pass an `operation_id` to allow `cancel_operation`.
+#[tauri::command]
+pub async fn get_station_logs(
+    station_id: String,
+    page: Option<usize>,
+    page_size: Option<usize>,
+    cursor: Option<String>,
+    filters: Option<serde_json::Value>,
+    operation_id: Option<String>,
+    app: AppHandle,
+) -> Result<LogPaginationResponse, RelayError> {
+    let station = load_station(&app, &station_id)?;
+    let adapter = create_adapter(&station.adapter);
+    let logs = cancellable(operation_id, async {
+        adapter.get_logs(&station, page, page_size, cursor.as_deref(), filters).await.map_err(|e| RelayError::from(e).context("Failed to get logs"))
+    })
+    .await?;
+    note_station_used(&app, &station);
+    Ok(logs)
+}
+
+/// Numeric log id used to detect entries newer than the last poll
+fn log_id(entry: &StationLogEntry) -> i64 {
+    entry.id.parse().unwrap_or(0)
+}
+
+/// Starts polling a station's logs and emits each new entry as a `station-log:{station_id}` event.
+/// Starting a stream for a station that already has one replaces the previous task.
+#[tauri::command]
+pub async fn start_log_stream(station_id: String, poll_interval_secs: Option<u64>, app: AppHandle) -> Result<String, RelayError> {
+    let station = load_station(&app, &station_id)?;
+    let interval = Duration::from_secs(
+        poll_interval_secs
+            .unwrap_or(DEFAULT_LOG_POLL_INTERVAL_SECS)
+            .max(MIN_LOG_POLL_INTERVAL_SECS),
+    );
+    let event = format!("station-log:{}", station_id);
+    let cancel = CancellationToken::new();
+    let stream_cancel = cancel.clone();
+
+    tokio::spawn(async move {
+        let adapter = create_adapter(&station.adapter);
+        let mut ticker = tokio::time::interval(interval);
+        // None until the first poll, which only records a baseline so history isn't replayed
+        let mut last_seen_id: Option<i64> = None;
+
+        loop {
+            if stream_cancel.run_until_cancelled(ticker.tick()).await.is_none() {
+                break;
+            }
+            // A poll still in flight when the stream is stopped is dropped rather than awaited
+            let Some(result) = stream_cancel
+                .run_until_cancelled(adapter.get_logs(&station, Some(1), Some(LOG_STREAM_PAGE_SIZE), None, None))
+                .await
+            else {
+                break;
+            };
+            let logs = match result {
+                Ok(logs) => logs,
+                Err(e) => {
+                    log::warn!("Log stream poll failed for station {}: {}", station.name, e);
+                    continue;
+                }
+            };
+
+            let max_id = logs.items.iter().map(log_id).max().unwrap_or(0);
+            if let Some(last_id) = last_seen_id {
+                let mut new_entries: Vec<StationLogEntry> = logs.items.into_iter().filter(|entry| log_id(entry) > last_id).collect();
+                new_entries.sort_by_key(log_id);
+                for entry in &new_entries {
+                    let _ = app.emit(&event, entry);
+                }
+            }
+            last_seen_id = Some(last_seen_id.map_or(max_id, |last_id| last_id.max(max_id)));
+        }
+        log::debug!("Log stream for station {} stopped", station.name);
+    });
+
+    let mut streams = LOG_STREAMS.lock().map_err(|e| RelayError::database(format!("Lock error: {}", e)))?;
+    if let Some(previous) = streams.insert(station_id, cancel) {
+        previous.cancel();
+    }
+    Ok("Log stream started".to_string())
+}
+
+#[tauri::command]
+pub async fn stop_log_stream(station_id: String) -> Result<String, RelayError> {
+    let mut streams = LOG_STREAMS.lock().map_err(|e| RelayError::database(format!("Lock error: {}", e)))?;
+    match streams.remove(&station_id) {
+        Some(cancel) => {
+            cancel.cancel();
+            Ok("Log stream stopped".to_string())
+        }
+        None => Ok("No log stream running".to_string()),
+    }
+}
+
+/// Drops an operation's cancellation token once the operation finishes
+struct OperationGuard(String);
+
+impl Drop for OperationGuard {
+    fn drop(&mut self) {
+        if let Ok(mut operations) = OPERATIONS.lock() {
+            operations.remove(&self.0);
+        }
+    }
+}
+
+/// Runs `operation` so that `cancel_operation(operation_id)` can stop it, dropping its in-flight
+/// requests and returning `RelayError::Cancelled`. Without an id the operation just runs.
+async fn cancellable<T>(
+    operation_id: Option<String>,
+    operation: impl std::future::Future<Output = Result<T, RelayError>>,
+) -> Result<T, RelayError> {
+    let Some(operation_id) = operation_id else {
+        return operation.await;
+    };
+    let cancel = CancellationToken::new();
+    {
+        let mut operations = OPERATIONS.lock().map_err(|e| RelayError::database(format!("Lock error: {}", e)))?;
+        if operations.contains_key(&operation_id) {
+            return Err(RelayError::conflict(format!("Operation {} is already running", operation_id)));
+        }
+        operations.insert(operation_id.clone(), cancel.clone());
+    }
+    let _guard = OperationGuard(operation_id);
+
+    cancel
+        .run_until_cancelled(operation)
+        .await
+        .unwrap_or_else(|| Err(RelayError::cancelled("Operation cancelled")))
+}
+
+/// Cancels an operation started with this `operation_id`; it then fails with a `cancelled` error
+#[tauri::command]
+pub async fn cancel_operation(operation_id: String) -> Result<String, RelayError> {
+    let operations = OPERATIONS.lock().map_err(|e| RelayError::database(format!("Lock error: {}", e)))?;
+    match operations.get(&operation_id) {
+        Some(cancel) => {
+            cancel.cancel();
+            Ok("Operation cancelled".to_string())
+        }
+        None => Ok("No operation running".to_string()),
+    }
+}
+
+/// Cancels every running operation and log stream, e.g. when the user leaves the relay views
+#[tauri::command]
+pub async fn cancel_all_operations() -> Result<usize, RelayError> {
+    let operations = OPERATIONS.lock().map_err(|e| RelayError::database(format!("Lock error: {}", e)))?;
+    let mut streams = LOG_STREAMS.lock().map_err(|e| RelayError::database(format!("Lock error: {}", e)))?;
+    for cancel in operations.values().chain(streams.values()) {
+        cancel.cancel();
+    }
+    let cancelled = operations.len() + streams.len();
+    streams.clear();
+    Ok(cancelled)
+}
+
+/// Notes that the user fetched data from the station; failures only log since the fetch itself succeeded
+fn note_station_used(app: &AppHandle, station: &RelayStation) {
+    let marked = with_manager(app, |manager| {
+        manager
+            .mark_station_used(&station.id, Utc::now().timestamp())
+            .map_err(|e| RelayError::database(e.to_string()))
+    });
+    if let Err(e) = marked {
+        log::warn!("Failed to update last_used_at for station {}: {}", station.name, e);
+    }
+}
+
+/// Adds the station's URL scheme to a connection test's details, plus a warning when its
+/// system token travels over plain http
+fn note_connection_security(station: &RelayStation, result: &mut ConnectionTestResult) {
+    let details = result.details.get_or_insert_with(HashMap::new);
+    details.insert("scheme".to_string(), if station.is_secure() { "https" } else { "http" }.into());
+    if let Some(warning) = station.plain_http_warning() {
+        details.insert("warning".to_string(), warning.into());
+    }
+}
+
+/// Records a connection test in the health history and remembers the probe it used
+fn record_connection_test(app: &AppHandle, station: &RelayStation, result: &ConnectionTestResult) {
+    let now = Utc::now().timestamp();
+    let recorded = with_manager(app, |manager| {
+        manager
+            .record_health_sample(&station.id, now, result)
+            .and_then(|_| manager.mark_station_tested(&station.id, now))
+            .map_err(|e| RelayError::database(e.to_string()))
+    });
+    if let Err(e) = recorded {
+        log::warn!("Failed to record health sample for station {}: {}", station.name, e);
+    }
+    remember_health_probe(app, station, result);
+}
+
+/// Stores the liveness probe a successful connection test used, so later tests start with it
+fn remember_health_probe(app: &AppHandle, station: &RelayStation, result: &ConnectionTestResult) {
+    if !result.success {
+        return;
+    }
+    let Some(probe) = result.details.as_ref().and_then(|details| details.get("probe")).and_then(|v| v.as_str()) else {
+        return;
+    };
+    let stored = station.adapter_config.as_ref().and_then(|config| config.get(HEALTH_PROBE_KEY)).and_then(|v| v.as_str());
+    if stored == Some(probe) {
+        return;
+    }
+    let saved = with_manager(app, |manager| {
+        manager
+            .set_adapter_config_value(&station.id, HEALTH_PROBE_KEY, probe.into())
+            .map_err(|e| RelayError::database(e.to_string()))
+    });
+    if let Err(e) = saved {
+        log::warn!("Failed to remember health probe for station {}: {}", station.name, e);
+    }
+}
+
+/// The features the station's adapter supports
+#[tauri::command]
+pub async fn get_station_capabilities(station_id: String, app: AppHandle) -> Result<StationCapabilities, RelayError> {
+    let station = load_station(&app, &station_id)?;
+    Ok(create_adapter(&station.adapter).capabilities())
+}
+
+/// Reports whether the station's system token has admin privileges. User-scoped tokens only
+/// see their own tokens and logs.
+#[tauri::command]
+pub async fn check_token_scope(station_id: String, app: AppHandle) -> Result<TokenScope, RelayError> {
+    let station = load_station(&app, &station_id)?;
+    create_adapter(&station.adapter)
+        .check_token_scope(&station)
+        .await
+        .map_err(|e| RelayError::from(e).context("Failed to check token scope"))
+}
+
+/// Probes the station, waiting up to `timeout_secs` (default: the station timeout, capped at 10s).
+///
+/// With `include_timing`, a separate bare `GET /api/status` runs alongside the probe and its DNS,
+/// TCP, TLS and first-byte times are merged into `details`. That request skips custom headers, the
+/// rate limiter and debug logging, so it only approximates the path real API calls take.
+#[tauri::command]
+pub async fn test_station_connection(
+    station_id: String,
+    timeout_secs: Option<u64>,
+    include_timing: Option<bool>,
+    app: AppHandle,
+) -> Result<ConnectionTestResult, RelayError> {
+    let station = load_station(&app, &station_id)?;
+    let adapter = create_adapter(&station.adapter);
+    let timeout = http::connection_test_timeout(&station, timeout_secs);
+    let probe = adapter.test_connection(&station, timeout);
+    let result = if include_timing.unwrap_or(false) {
+        let (result, breakdown) = tokio::join!(probe, timing::connection_timing(&station, "/api/status", timeout));
+        result.map(|mut result| {
+            result.details.get_or_insert_with(HashMap::new).extend(breakdown);
+            result
+        })
+    } else {
+        probe.await
+    };
+    let mut result = result.map_err(|e| RelayError::from(e).context("Failed to test connection"))?;
+    note_connection_security(&station, &mut result);
+    record_connection_test(&app, &station, &result);
+    Ok(result)
+}
+
+/// Probes `station` as if its adapter_config were `proposed_config`. Building the client first
+/// reports a bad proxy or CA as invalid input rather than as a failed connection.
+async fn test_proposed_adapter_config(
+    mut station: RelayStation,
+    proposed_config: HashMap<String, serde_json::Value>,
+    timeout_secs: Option<u64>,
+) -> Result<ConnectionTestResult, RelayError> {
+    station.adapter_config = Some(proposed_config);
+    http::client_for(&station).map_err(|e| RelayError::from(e).context("Invalid adapter_config"))?;
+    let timeout = http::connection_test_timeout(&station, timeout_secs);
+    let mut result = create_adapter(&station.adapter)
+        .test_connection(&station, timeout)
+        .await
+        .map_err(|e| RelayError::from(e).context("Failed to test connection"))?;
+    note_connection_security(&station, &mut result);
+    Ok(result)
+}
+
+/// Tests the station with `proposed_config` in place of its adapter_config, without saving
+/// the config or recording the result in the station's health history
+#[tauri::command]
+pub async fn test_adapter_config(
+    station_id: String,
+    proposed_config: HashMap<String, serde_json::Value>,
+    timeout_secs: Option<u64>,
+    app: AppHandle,
+) -> Result<ConnectionTestResult, RelayError> {
+    let station = load_station(&app, &station_id)?;
+    test_proposed_adapter_config(station, proposed_config, timeout_secs).await
+}
+
+/// Issues a minimal authenticated request with `token_key` to check the station accepts it
+#[tauri::command]
+pub async fn test_token(station_id: String, token_key: String, app: AppHandle) -> Result<TokenTestResult, RelayError> {
+    let token_key = token_key.trim();
+    if token_key.is_empty() {
+        return Err(RelayError::invalid_input("Token key is required"));
+    }
+    let station = load_station(&app, &station_id)?;
+    let adapter = create_adapter(&station.adapter);
+    adapter
+        .test_token(&station, token_key)
+        .await
+        .map_err(|e| RelayError::from(e).context("Failed to test token"))
+}
+
+/// Tests every enabled station at once, at most `concurrency` (default 8) in flight, each
+/// probe waiting up to `timeout_secs` (default 5s). Pass an `operation_id` to allow `cancel_operation`.
+#[tauri::command]
+pub async fn test_all_station_connections(
+    concurrency: Option<usize>,
+    timeout_secs: Option<u64>,
+    operation_id: Option<String>,
+    app: AppHandle,
+) -> Result<HashMap<String, ConnectionTestResult>, RelayError> {
+    // Snapshot the stations so no database connection is held across the probes
+    let stations = with_manager(&app, |manager| {
+        manager.list_stations().map_err(|e| RelayError::database(format!("Failed to list stations: {}", e)))
+    })?;
+    let concurrency = concurrency.unwrap_or(CONNECTION_TEST_CONCURRENCY).max(1);
+    let timeout_secs = timeout_secs.unwrap_or(http::SWEEP_PROBE_TIMEOUT_SECS);
+
+    let sweep = stream::iter(stations.into_iter().filter(|station| station.enabled))
+        .map(|station| {
+            let app = app.clone();
+            async move {
+                let adapter = create_adapter(&station.adapter);
+                let timeout = http::connection_test_timeout(&station, Some(timeout_secs));
+                let mut result = adapter.test_connection(&station, timeout).await.unwrap_or_else(|e| ConnectionTestResult {
+                    success: false,
+                    response_time: None,
+                    message: RelayError::from(e).message().to_string(),
+                    status_code: None,
+                    details: Some(HashMap::from([("timeout_ms".to_string(), (timeout.as_millis() as u64).into())])),
+                });
+                note_connection_security(&station, &mut result);
+                record_connection_test(&app, &station, &result);
+                (station.id, result)
+            }
+        })
+        .buffer_unordered(concurrency)
+        .collect::<HashMap<_, _>>();
+
+    cancellable(operation_id, async { Ok(sweep.await) }).await
+}
+
+#[tauri::command]
+pub async fn api_user_self_groups(station_id: String, app: AppHandle) -> Result<serde_json::Value, RelayError> {
+    let station = load_station(&app, &station_id)?;
+    let adapter = create_adapter(&station.adapter);
+    adapter.get_user_groups(&station).await.map_err(|e| RelayError::from(e).context("Failed to get user groups"))
+}
+
+/// Pauses or resumes a token on the station without touching its quota or model limits
+#[tauri::command]
+pub async fn set_token_status(
+    station_id: String,
+    token_id: String,
+    enabled: bool,
+    app: AppHandle,
+) -> Result<RelayStationToken, RelayError> {
+    let station = load_station(&app, &station_id)?;
+    let adapter = create_adapter(&station.adapter);
+    adapter
+        .set_token_enabled(&station, &token_id, enabled)
+        .await
+        .map_err(|e| RelayError::from(e).context("Failed to set token status"))
+}
+
+#[tauri::command]
+pub async fn toggle_station_token(
+    station_id: String,
+    token_id: String,
+    enabled: bool,
+    app: AppHandle,
+) -> Result<RelayStationToken, RelayError> {
+    set_token_status(station_id, token_id, enabled, app).await
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::commands::relay_adapters::http::tests::serve_http;
+
+    #[test]
+    fn test_normalize_api_url() {
+        assert_eq!(normalize_api_url("https://example.com").unwrap(), "https://example.com");
+        assert_eq!(normalize_api_url("https://example.com/").unwrap(), "https://example.com");
+        assert_eq!(normalize_api_url("https://example.com/api/").unwrap(), "https://example.com");
+        assert_eq!(normalize_api_url("  http://10.0.0.2:3000/API  ").unwrap(), "http://10.0.0.2:3000");
+        assert_eq!(normalize_api_url("https://example.com/relay").unwrap(), "https://example.com/relay");
+    }
+
+    pub(crate) fn test_manager() -> RelayStationManager {
+        let pool = ConnectionPool::in_memory(&Uuid::new_v4().to_string()).unwrap();
+        RelayStationManager::new(pool).unwrap()
+    }
+
+    pub(crate) fn test_station(id: &str, name: &str, api_url: &str) -> RelayStation {
+        RelayStation {
+            id: id.to_string(),
+            name: name.to_string(),
+            description: None,
+            api_url: api_url.to_string(),
+            adapter: RelayStationAdapter::Newapi,
+            auth_method: AuthMethod::BearerToken,
+            system_token: "system-token".to_string(),
+            user_id: Some("1".to_string()),
+            adapter_config: None,
+            enabled: true,
+            tags: Vec::new(),
+            created_at: 0,
+            updated_at: 0,
+            deleted_at: None,
+            last_used_at: None,
+            last_tested_at: None,
+            config_parse_error: false,
+            is_secure: is_secure_url(api_url),
+        }
+    }
+
+    #[test]
+    fn test_adapter_capabilities() {
+        let custom = create_adapter(&RelayStationAdapter::Custom).capabilities();
+        assert!(!custom.supports_tokens && !custom.supports_logs && !custom.supports_user_info && !custom.supports_models);
+        // OneAPI shares NewAPI's user and model endpoints but not its token and log formats
+        let oneapi = create_adapter(&RelayStationAdapter::Oneapi).capabilities();
+        assert!(!oneapi.supports_tokens && !oneapi.supports_logs && oneapi.supports_user_info && oneapi.supports_models);
+        for adapter in [RelayStationAdapter::Newapi, RelayStationAdapter::Yourapi] {
+            let capabilities = create_adapter(&adapter).capabilities();
+            assert!(capabilities.supports_tokens && capabilities.supports_logs && capabilities.supports_user_info && capabilities.supports_models);
+        }
+    }
+
+    #[test]
+    fn test_search_stations_ranking() {
+        let manager = test_manager();
+        manager.add_station(&test_station("a", "My Relay Backup", "https://a.example.com")).unwrap();
+        manager.add_station(&test_station("b", "relay", "https://b.example.com")).unwrap();
+        manager.add_station(&test_station("c", "Relay Work", "https://c.example.com")).unwrap();
+        manager.add_station(&test_station("d", "Other", "https://relay.example.org")).unwrap();
+        manager.add_station(&test_station("e", "Unrelated", "https://e.example.com")).unwrap();
+
+        let ids: Vec<String> = manager.search_stations("RELAY").unwrap().into_iter().map(|s| s.id).collect();
+        assert_eq!(ids[0], "b");
+        assert_eq!(ids[1], "c");
+        assert_eq!(ids.len(), 4);
+        assert!(!ids.contains(&"e".to_string()));
+
+        // LIKE wildcards in the query are matched literally
+        assert!(manager.search_stations("%").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_list_stations_sorted_by_name_and_latency() {
+        let manager = test_manager();
+        manager.add_station(&test_station("a", "beta", "https://a.example.com")).unwrap();
+        manager.add_station(&test_station("b", "Alpha", "https://b.example.com")).unwrap();
+        manager.add_station(&test_station("c", "gamma", "https://c.example.com")).unwrap();
+
+        let ids = |sort_by, order| -> Vec<String> {
+            manager.list_stations_sorted(sort_by, order).unwrap().into_iter().map(|s| s.id).collect()
+        };
+        assert_eq!(ids(Some("name"), None), ["b", "a", "c"]);
+        assert_eq!(ids(Some("name"), Some("desc")), ["c", "a", "b"]);
+
+        let sample = |response_time| ConnectionTestResult {
+            success: true,
+            response_time: Some(response_time),
+            message: String::new(),
+            status_code: Some(200),
+            details: None,
+        };
+        manager.record_health_sample("a", 100, &sample(900)).unwrap();
+        manager.record_health_sample("a", 200, &sample(50)).unwrap();
+        manager.record_health_sample("c", 100, &sample(300)).unwrap();
+        // Only the latest sample counts, and untested stations come last
+        assert_eq!(ids(Some("latency"), None), ["a", "c", "b"]);
+
+        assert!(manager.list_stations_sorted(Some("name; DROP TABLE relay_stations"), None).is_err());
+        assert!(manager.list_stations_sorted(Some("name"), Some("sideways")).is_err());
+    }
+
+    #[test]
+    fn test_station_activity_timestamps() {
+        let manager = test_manager();
+        manager.add_station(&test_station("a", "A", "https://a.example.com")).unwrap();
+        manager.add_station(&test_station("b", "B", "https://b.example.com")).unwrap();
+        manager.mark_station_used("a", 100).unwrap();
+        manager.mark_station_tested("b", 200).unwrap();
+
+        let a = manager.get_station("a").unwrap().unwrap();
+        assert_eq!((a.last_used_at, a.last_tested_at), (Some(100), None));
+        let ids: Vec<String> = manager.list_stations_sorted(Some("last_tested_at"), None).unwrap().into_iter().map(|s| s.id).collect();
+        assert_eq!(ids, ["b", "a"]);
+
+        // Activity isn't an edit
+        assert_eq!(manager.get_station("b").unwrap().unwrap().updated_at, 0);
+        let clone = manager.duplicate_station("a", "Copy").unwrap().unwrap();
+        assert_eq!(manager.get_station(&clone).unwrap().unwrap().last_used_at, None);
+    }
+
+    #[test]
+    fn test_corrupt_adapter_config_is_flagged() {
+        let manager = test_manager();
+        manager.add_station(&test_station("a", "A", "https://a.example.com")).unwrap();
+        manager.add_station(&test_station("b", "B", "https://b.example.com")).unwrap();
+        manager.db.get().unwrap().execute("UPDATE relay_stations SET adapter_config = '{\"proxy_url\": ' WHERE id = 'a'", []).unwrap();
+
+        let stations = manager.list_stations().unwrap();
+        assert_eq!(stations.len(), 2);
+        let a = stations.iter().find(|s| s.id == "a").unwrap();
+        assert!(a.config_parse_error && a.adapter_config.is_none());
+        assert!(!stations.iter().find(|s| s.id == "b").unwrap().config_parse_error);
+        assert!(manager.get_station("a").unwrap().unwrap().config_parse_error);
+
+        // The corrupt text is left in place rather than replaced by a near-empty config
+        assert!(manager.set_adapter_config_value("a", HEALTH_PROBE_KEY, "GET /api/".into()).is_err());
+    }
+
+    #[test]
+    fn test_soft_delete_restore_and_purge() {
+        let manager = test_manager();
+        manager.add_station(&test_station("a", "Relay", "https://a.example.com")).unwrap();
+        {
+            let conn = manager.db.get().unwrap();
+            conn.execute(
+                "INSERT INTO relay_station_tokens (id, station_id, name, token, enabled, created_at) VALUES ('t1', 'a', 'token', 'sk-1', 1, 0)",
+                [],
+            ).unwrap();
+        }
+
+        manager.delete_station("a").unwrap();
+        assert!(manager.list_stations().unwrap().is_empty());
+        assert_eq!(manager.list_deleted_stations().unwrap().len(), 1);
+        // Commands can't reach a station in the recycle bin
+        assert!(manager.get_station("a").unwrap().is_none());
+
+        assert!(manager.restore_station("a").unwrap());
+        assert_eq!(manager.list_stations().unwrap().len(), 1);
+        assert!(!manager.restore_station("a").unwrap());
+
+        // Purging only works from the recycle bin
+        assert!(!manager.purge_station("a").unwrap());
+        manager.delete_station("a").unwrap();
+        assert!(manager.purge_station("a").unwrap());
+        assert!(manager.get_station("a").unwrap().is_none());
+        let token_count: i64 = manager.db.get().unwrap()
+            .query_row("SELECT COUNT(*) FROM relay_station_tokens", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(token_count, 0);
+    }
+
+    #[test]
+    fn test_station_tags_normalized_and_filtered() {
+        let manager = test_manager();
+        let mut work = test_station("w", "Work", "https://w.example.com");
+        work.tags = vec![" Work ".to_string(), "work".to_string(), "".to_string(), "Paid".to_string()];
+        manager.add_station(&work).unwrap();
+        manager.add_station(&test_station("p", "Personal", "https://p.example.com")).unwrap();
+
+        assert_eq!(manager.get_station("w").unwrap().unwrap().tags, vec!["work", "paid"]);
+        let tagged: Vec<String> = manager.list_stations_by_tag("WORK").unwrap().into_iter().map(|s| s.id).collect();
+        assert_eq!(tagged, vec!["w"]);
+
+        let mut updates = HashMap::new();
+        updates.insert("tags".to_string(), serde_json::json!(["Testing "]));
+        manager.update_station("p", &updates).unwrap();
+        assert_eq!(manager.list_stations_by_tag("testing").unwrap().len(), 1);
+        assert!(manager.list_stations_by_tag("work").unwrap().iter().all(|s| s.id == "w"));
+
+        // Malformed tags are rejected instead of wiping the existing ones
+        updates.insert("tags".to_string(), serde_json::json!("work"));
+        assert!(manager.update_station("w", &updates).is_err());
+        assert_eq!(manager.get_station("w").unwrap().unwrap().tags, vec!["work", "paid"]);
+    }
+
+    #[test]
+    fn test_set_stations_enabled_is_all_or_nothing() {
+        let manager = test_manager();
+        let mut demo = test_station("d", "Demo", "https://d.example.com");
+        demo.tags = vec!["testing".to_string()];
+        manager.add_station(&demo).unwrap();
+        manager.add_station(&test_station("a", "A", "https://a.example.com")).unwrap();
+        manager.add_station(&test_station("b", "B", "https://b.example.com")).unwrap();
+        let enabled = |id: &str| manager.get_station(id).unwrap().unwrap().enabled;
+
+        let ids = vec!["a".to_string(), "b".to_string(), "missing".to_string()];
+        assert_eq!(manager.set_stations_enabled(&ids, false).unwrap(), None);
+        assert!(enabled("a") && enabled("b"));
+
+        assert_eq!(manager.set_stations_enabled(&ids[..2], false).unwrap(), Some(2));
+        assert!(!enabled("a") && !enabled("b") && enabled("d"));
+
+        assert_eq!(manager.set_stations_enabled_by_tag("Testing", false).unwrap(), 1);
+        assert!(!enabled("d"));
+    }
+
+    #[test]
+    fn test_transaction_rolls_back_on_error() {
+        let manager = test_manager();
+        let result = manager.transaction(|conn| {
+            insert_station(conn, &test_station("a", "First", "https://a.example.com"))?;
+            // Same id again: the insert fails and the first one must not survive
+            insert_station(conn, &test_station("a", "Second", "https://a.example.com"))
+        });
+        assert!(result.is_err());
+        assert!(manager.get_station("a").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_swap_station_credentials() {
+        let manager = test_manager();
+        let mut primary = test_station("a", "Primary", "https://a.example.com");
+        primary.system_token = "token-a".to_string();
+        manager.add_station(&primary).unwrap();
+        let mut backup = test_station("b", "Backup", "https://b.example.com");
+        backup.system_token = "token-b".to_string();
+        backup.user_id = None;
+        manager.add_station(&backup).unwrap();
+        manager.sync_tokens("a", &[RelayStationToken { id: "1".to_string(), station_id: "a".to_string(), ..test_token(None) }], 100).unwrap();
+        let credentials = |id: &str| {
+            let station = manager.get_station(id).unwrap().unwrap();
+            (station.system_token, station.user_id)
+        };
+
+        assert!(!manager.swap_station_credentials("a", "missing").unwrap());
+        assert_eq!(credentials("a"), ("token-a".to_string(), Some("1".to_string())));
+        assert_eq!(manager.list_cached_tokens("a").unwrap().len(), 1);
+
+        assert!(manager.swap_station_credentials("a", "b").unwrap());
+        assert_eq!(credentials("a"), ("token-b".to_string(), None));
+        assert_eq!(credentials("b"), ("token-a".to_string(), Some("1".to_string())));
+        assert_eq!(manager.get_station("a").unwrap().unwrap().name, "Primary");
+        // Cached tokens survive the swap until the next sync refreshes them
+        assert_eq!(manager.list_cached_tokens("a").unwrap().len(), 1);
+        let summary = manager.sync_tokens("a", &[], 200).unwrap();
+        assert_eq!(summary.removed, 1);
+        assert!(manager.list_cached_tokens("a").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_duplicate_station_is_disabled_copy() {
+        let manager = test_manager();
+        let mut source = test_station("src", "Primary", "https://relay.example.com");
+        source.adapter_config = Some(HashMap::from([("timeout_secs".to_string(), serde_json::json!(15))]));
+        manager.add_station(&source).unwrap();
+
+        let clone_id = manager.duplicate_station("src", "Secondary").unwrap().unwrap();
+        assert_ne!(clone_id, "src");
+        let clone = manager.get_station(&clone_id).unwrap().unwrap();
+        assert_eq!(clone.name, "Secondary");
+        assert_eq!(clone.api_url, source.api_url);
+        assert_eq!(clone.adapter_config, source.adapter_config);
+        assert!(!clone.enabled);
+
+        assert!(manager.duplicate_station("missing", "Copy").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_migrations_upgrade_legacy_database_once() {
+        // A database from before versioning: the original table without user_id or later columns
+        let name = Uuid::new_v4().to_string();
+        let pool = ConnectionPool::in_memory(&name).unwrap();
+        pool.get().unwrap().execute(
+            "CREATE TABLE relay_stations (
+                id TEXT PRIMARY KEY, name TEXT NOT NULL, description TEXT, api_url TEXT NOT NULL,
+                adapter TEXT NOT NULL, auth_method TEXT NOT NULL, system_token TEXT NOT NULL,
+                adapter_config TEXT, enabled INTEGER NOT NULL DEFAULT 1,
+                created_at INTEGER NOT NULL, updated_at INTEGER NOT NULL
+            )",
+            [],
+        ).unwrap();
+
+        let manager = RelayStationManager::new(pool).unwrap();
+        manager.add_station(&test_station("a", "Relay", "https://a.example.com")).unwrap();
+
+        // Re-opening applies nothing new and keeps the data
+        let reopened = RelayStationManager::new(ConnectionPool::in_memory(&name).unwrap()).unwrap();
+        assert_eq!(reopened.list_stations().unwrap().len(), 1);
+        let versions: Vec<usize> = {
+            let conn = reopened.db.get().unwrap();
+            let mut stmt = conn.prepare("SELECT version FROM schema_version ORDER BY version").unwrap();
+            let versions = stmt.query_map([], |row| row.get(0)).unwrap().collect::<rusqlite::Result<Vec<_>>>().unwrap();
+            versions
+        };
+        assert_eq!(versions, (1..=MIGRATIONS.len()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_deleting_station_cascades_to_tokens() {
+        let manager = test_manager();
+        manager.add_station(&test_station("a", "Relay", "https://a.example.com")).unwrap();
+        manager.add_station(&test_station("b", "Other", "https://b.example.com")).unwrap();
+
+        let conn = manager.db.get().unwrap();
+        for (id, station_id) in [("t1", "a"), ("t2", "a"), ("t3", "b")] {
+            conn.execute(
+                "INSERT INTO relay_station_tokens (id, station_id, name, token, enabled, created_at) VALUES (?1, ?2, 'token', 'sk', 1, 0)",
+                params![id, station_id],
+            ).unwrap();
+        }
+
+        conn.execute("DELETE FROM relay_stations WHERE id = 'a'", []).unwrap();
+        let remaining: Vec<String> = conn
+            .prepare("SELECT id FROM relay_station_tokens ORDER BY id").unwrap()
+            .query_map([], |row| row.get(0)).unwrap()
+            .collect::<rusqlite::Result<Vec<_>>>().unwrap();
+        assert_eq!(remaining, vec!["t3"]);
+    }
+
+    pub(crate) fn test_token(expires_at: Option<i64>) -> RelayStationToken {
+        RelayStationToken {
+            id: "t".to_string(),
+            station_id: "s".to_string(),
+            name: "token".to_string(),
+            token: "sk-token".to_string(),
+            user_id: None,
+            enabled: true,
+            expires_at,
+            group: None,
+            remain_quota: None,
+            unlimited_quota: None,
+            metadata: None,
+            created_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_aggregate_daily_usage_groups_by_day_and_model() {
+        let day = 1_700_000_000 - 1_700_000_000 % 86400;
+        let days = aggregate_daily_usage(vec![
+            (day + 10, "gpt-4o".to_string(), 1, 100, 5),
+            (day + 3600, "gpt-4o".to_string(), 2, 50, 3),
+            (day + 20, "claude".to_string(), 1, 10, 1),
+            (day + 86400, "gpt-4o".to_string(), 1, 1, 1),
+        ]);
+        assert_eq!(days.len(), 3);
+        assert_eq!((days[0].model_name.as_str(), days[0].request_count), ("claude", 1));
+        assert_eq!((days[1].request_count, days[1].token_used, days[1].quota), (3, 150, 8));
+        assert_eq!(days[1].date, "2023-11-14");
+        assert_eq!(days[2].date, "2023-11-15");
+    }
+
+    #[test]
+    fn test_create_retry_only_when_outcome_unknown() {
+        assert!(create_outcome_unknown(&RelayError::network("Request timed out after 30s")));
+        assert!(create_outcome_unknown(&RelayError::Upstream { status: Some(502), message: String::new() }));
+        assert!(!create_outcome_unknown(&RelayError::Upstream { status: Some(400), message: String::new() }));
+        assert!(!create_outcome_unknown(&RelayError::Upstream { status: None, message: String::new() }));
+        assert!(!create_outcome_unknown(&RelayError::invalid_input("bad")));
+
+        let since = 1_700_000_000;
+        let tokens = vec![
+            RelayStationToken { id: "old".to_string(), name: "ci".to_string(), created_at: since + 10, ..test_token(None) },
+            RelayStationToken { id: "new".to_string(), name: "ci".to_string(), created_at: since - 5, ..test_token(None) },
+            RelayStationToken { id: "other".to_string(), name: "other".to_string(), created_at: since + 1, ..test_token(None) },
+        ];
+        // A same-named token that existed before the first attempt is never taken, however recent
+        let known_ids = HashSet::from(["old".to_string()]);
+        assert_eq!(find_created_token(&tokens, "ci", &known_ids).map(|t| t.id.as_str()), Some("new"));
+        assert!(find_created_token(&tokens[..1], "ci", &known_ids).is_none());
+    }
+
+    #[test]
+    fn test_find_token_named_matches_exact_trimmed_name() {
+        let tokens = vec![
+            RelayStationToken { id: "1".to_string(), name: "default ".to_string(), ..test_token(None) },
+            RelayStationToken { id: "2".to_string(), name: "Default".to_string(), ..test_token(None) },
+        ];
+        assert_eq!(find_token_named(&tokens, "default").map(|t| t.id.as_str()), Some("1"));
+        assert_eq!(find_token_named(&tokens, "Default").map(|t| t.id.as_str()), Some("2"));
+        assert!(find_token_named(&tokens, "other").is_none());
+
+        let err = RelayError::conflict("exists");
+        assert_eq!(serde_json::to_value(&err).unwrap()["kind"], "conflict");
+    }
+
+    #[test]
+    fn test_sync_tokens_upserts_and_soft_deletes() {
+        let manager = test_manager();
+        manager.add_station(&test_station("a", "Relay", "https://a.example.com")).unwrap();
+        manager.add_station(&test_station("b", "Other", "https://b.example.com")).unwrap();
+        let remote = |station_id: &str, ids: &[&str]| -> Vec<RelayStationToken> {
+            ids.iter()
+                .map(|id| RelayStationToken { id: id.to_string(), station_id: station_id.to_string(), ..test_token(None) })
+                .collect()
+        };
+
+        let summary = manager.sync_tokens("a", &remote("a", &["1", "2"]), 100).unwrap();
+        assert_eq!(summary, TokenSyncSummary { added: 2, updated: 0, removed: 0 });
+        // Upstream ids only need to be unique per station
+        manager.sync_tokens("b", &remote("b", &["1"]), 100).unwrap();
+
+        let summary = manager.sync_tokens("a", &remote("a", &["2", "3"]), 200).unwrap();
+        assert_eq!(summary, TokenSyncSummary { added: 1, updated: 1, removed: 1 });
+        let ids: Vec<String> = manager.list_cached_tokens("a").unwrap().into_iter().map(|t| t.id).collect();
+        assert_eq!(ids.len(), 2);
+        assert!(!ids.contains(&"1".to_string()));
+        assert!(manager.get_token("a", "1").unwrap().is_none());
+        assert_eq!(manager.list_cached_tokens("b").unwrap().len(), 1);
+
+        // A token that comes back upstream is restored
+        let summary = manager.sync_tokens("a", &remote("a", &["1", "2", "3"]), 300).unwrap();
+        assert_eq!(summary, TokenSyncSummary { added: 1, updated: 2, removed: 0 });
+    }
+
+    fn log_entry(timestamp: i64, quota: Option<i64>) -> StationLogEntry {
+        StationLogEntry {
+            id: timestamp.to_string(),
+            timestamp,
+            level: "info".to_string(),
+            message: String::new(),
+            user_id: None,
+            request_id: None,
+            metadata: None,
+            model_name: None,
+            prompt_tokens: None,
+            completion_tokens: None,
+            quota,
+            token_name: None,
+            use_time: None,
+            is_stream: None,
+            channel: None,
+            group: None,
+        }
+    }
+
+    #[test]
+    fn test_summarize_token_usage_combines_record_and_logs() {
+        let entry = |timestamp: i64, model: &str, quota: i64| StationLogEntry {
+            model_name: Some(model.to_string()),
+            prompt_tokens: Some(10),
+            completion_tokens: Some(5),
+            ..log_entry(timestamp, Some(quota))
+        };
+        let token = RelayStationToken {
+            remain_quota: Some(900),
+            metadata: Some(HashMap::from([("used_quota".to_string(), serde_json::json!(100))])),
+            ..test_token(None)
+        };
+        let entries = (0..25).map(|i| entry(i, if i % 5 == 0 { "claude" } else { "gpt-4o" }, 2)).collect();
+
+        let usage = summarize_token_usage(&token, entries, 0, 100, true);
+        assert_eq!((usage.remain_quota, usage.used_quota), (Some(900), Some(100)));
+        assert_eq!((usage.request_count, usage.prompt_tokens, usage.completion_tokens, usage.quota), (25, 250, 125, 50));
+        assert_eq!(usage.models.len(), 2);
+        assert_eq!((usage.models[0].model_name.as_str(), usage.models[0].request_count, usage.models[0].token_used), ("claude", 5, 75));
+        assert_eq!(usage.recent.len(), TOKEN_USAGE_RECENT_ENTRIES);
+        assert_eq!(usage.recent[0].timestamp, 24);
+    }
+
+    #[test]
+    fn test_log_matches_message_model_or_token() {
+        let entry = StationLogEntry {
+            message: "Upstream Timeout on /v1/chat".to_string(),
+            model_name: Some("GPT-4o".to_string()),
+            token_name: Some("ci-runner".to_string()),
+            ..log_entry(0, None)
+        };
+        assert!(log_matches(&entry, "timeout"));
+        assert!(log_matches(&entry, "gpt-4o"));
+        assert!(log_matches(&entry, "runner"));
+        assert!(!log_matches(&entry, "claude"));
+        assert!(!log_matches(&log_entry(0, None), "timeout"));
+    }
+
+    #[test]
+    fn test_latency_percentiles_split_by_streaming() {
+        let entry = |use_time: Option<i64>, is_stream: Option<bool>| StationLogEntry { use_time, is_stream, ..log_entry(0, None) };
+        let mut entries: Vec<_> = (1..=10).map(|t| entry(Some(t), Some(t % 2 == 0))).collect();
+        entries.push(entry(Some(100), None));
+        entries.push(entry(None, Some(true)));
+
+        let (overall, streaming, non_streaming) = summarize_latency(&entries);
+        assert_eq!(overall, LatencyPercentiles { count: 11, p50: Some(6), p90: Some(10), p99: Some(100), max: Some(100) });
+        assert_eq!(streaming, LatencyPercentiles { count: 5, p50: Some(6), p90: Some(10), p99: Some(10), max: Some(10) });
+        assert_eq!(non_streaming, LatencyPercentiles { count: 5, p50: Some(5), p90: Some(9), p99: Some(9), max: Some(9) });
+
+        assert_eq!(latency_percentiles(&mut []), LatencyPercentiles::default());
+        assert_eq!(latency_percentiles(&mut [7]).p50, Some(7));
+    }
+
+    #[test]
+    fn test_log_type_histogram_counts_errors() {
+        let entry = |log_type: Option<i64>, level: &str| StationLogEntry {
+            level: level.to_string(),
+            metadata: log_type.map(|t| HashMap::from([("raw".to_string(), serde_json::json!({ "type": t }))])),
+            ..log_entry(0, None)
+        };
+        let entries = [entry(Some(2), "api"), entry(Some(2), "api"), entry(Some(4), "error"), entry(Some(5), "info"), entry(None, "info")];
+        let (total, errors, histogram) = log_type_histogram(&entries);
+        assert_eq!((total, errors), (5, 1));
+        assert_eq!(histogram["2"], 2);
+        assert_eq!(histogram["4"], 1);
+        assert_eq!(histogram["5"], 1);
+        assert_eq!(histogram["unknown"], 1);
+    }
+
+    #[test]
+    fn test_sum_log_quota_respects_range() {
+        let entry = log_entry;
+        let entries = [entry(99, Some(1000)), entry(100, Some(500)), entry(150, None), entry(200, Some(250)), entry(201, Some(7))];
+        assert_eq!(sum_log_quota(&entries, 100, 200), (750, 3));
+        assert_eq!(sum_log_quota(&entries, 300, 400), (0, 0));
+    }
+
+    #[test]
+    fn test_low_quota_tokens_use_columns() {
+        let manager = test_manager();
+        manager.add_station(&test_station("a", "Relay", "https://a.example.com")).unwrap();
+        let token = |id: &str, remain_quota: i64, unlimited: bool| RelayStationToken {
+            id: id.to_string(),
+            station_id: "a".to_string(),
+            remain_quota: Some(remain_quota),
+            unlimited_quota: Some(unlimited),
+            group: Some("vip".to_string()),
+            metadata: Some(HashMap::from([("used_quota".to_string(), serde_json::json!(42))])),
+            ..test_token(None)
+        };
+        let remote = [token("1", 500, false), token("2", 50, false), token("3", 0, true), token("4", 5000, false)];
+        manager.sync_tokens("a", &remote, 0).unwrap();
+
+        let low = manager.list_low_quota_tokens("a", 1000).unwrap();
+        assert_eq!(low.iter().map(|t| t.id.as_str()).collect::<Vec<_>>(), vec!["2", "1"]);
+        assert_eq!(low[0].group.as_deref(), Some("vip"));
+
+        let used: i64 = manager.db.get().unwrap()
+            .query_row("SELECT used_quota FROM relay_station_tokens WHERE id = '1'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(used, 42);
+    }
+
+    #[test]
+    fn test_quota_columns_backfilled_from_metadata() {
+        let manager = test_manager();
+        manager.add_station(&test_station("a", "Relay", "https://a.example.com")).unwrap();
+        let conn = manager.db.get().unwrap();
+        conn.execute(
+            r#"INSERT INTO relay_station_tokens (id, station_id, name, token, enabled, metadata, created_at) VALUES
+                ('1', 'a', 'token', 'sk', 1, '{"used_quota": 7, "remain_quota": 30, "group": "default"}', 0),
+                ('2', 'a', 'token', 'sk', 1, '{"remain_quota": 0, "raw": {"unlimited_quota": true}}', 0),
+                ('3', 'a', 'token', 'sk', 1, 'not json', 0)"#,
+            [],
+        ).unwrap();
+        MIGRATIONS[5](&conn).unwrap();
+
+        let low = manager.list_low_quota_tokens("a", 100).unwrap();
+        assert_eq!(low.len(), 1);
+        assert_eq!((low[0].id.as_str(), low[0].remain_quota, low[0].group.as_deref()), ("1", Some(30), Some("default")));
+    }
+
+    #[test]
+    fn test_token_expiry_state() {
+        let now = 1_700_000_000;
+        let day = 24 * 60 * 60;
+
+        let expired = test_token(Some(now - day));
+        assert!(expired.is_expired(now));
+        assert_eq!(expired.expires_in_days(now), Some(-1));
+
+        let expiring = test_token(Some(now + 3 * day + 60));
+        assert!(!expiring.is_expired(now));
+        assert!(matches!(expiring.expires_in_days(now), Some(days) if days <= 7));
+
+        for never in [test_token(None), test_token(Some(-1))] {
+            assert!(!never.is_expired(now));
+            assert_eq!(never.expires_in_days(now), None);
+        }
+
+        let mut annotated = test_token(Some(now + 3 * day + 60));
+        annotated.annotate_expiry(now);
+        let metadata = annotated.metadata.unwrap();
+        assert_eq!(metadata["is_expired"], serde_json::json!(false));
+        assert_eq!(metadata["expires_in_days"], serde_json::json!(3));
+    }
+
+    #[test]
+    fn test_clone_token_request_carries_settings() {
+        let mut token = test_token(None);
+        token.group = Some("vip".to_string());
+        token.remain_quota = Some(5000);
+        token.unlimited_quota = Some(false);
+        token.metadata = Some(HashMap::from([(
+            "raw".to_string(),
+            serde_json::json!({"model_limits_enabled": true, "model_limits": "gpt-4o,claude-3", "allow_ips": ""}),
+        )]));
+
+        let request = clone_token_request(&token);
+        assert_eq!(request.name, "token");
+        assert_eq!(request.remain_quota, Some(5000));
+        assert_eq!(request.expired_time, Some(-1));
+        assert_eq!(request.group.as_deref(), Some("vip"));
+        assert_eq!(request.model_limits_enabled, Some(true));
+        assert_eq!(request.model_limits.as_deref(), Some("gpt-4o,claude-3"));
+        assert_eq!(request.allow_ips, None);
+
+        let groups = serde_json::json!({"success": true, "data": {"default": {}, "vip": {}}});
+        assert_eq!(group_names(&groups).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_quota_samples_keep_their_conversion_factor() {
+        let manager = test_manager();
+        manager.add_station(&test_station("a", "Relay", "https://a.example.com")).unwrap();
+
+        let user_info = UserInfo {
+            user_id: "1".to_string(),
+            username: None,
+            email: None,
+            balance_remaining: None,
+            amount_used: None,
+            request_count: None,
+            status: None,
+            metadata: Some(HashMap::from([
+                ("response".to_string(), serde_json::json!({"quota": 1_000_000, "used_quota": 250_000})),
+                ("quota_per_unit".to_string(), serde_json::json!(500_000.0)),
+            ])),
+        };
+        let sample = QuotaSample::from_user_info("a", &user_info, 100).unwrap();
+        manager.record_quota_sample(&sample).unwrap();
+        // The station's factor changed later
+        manager.record_quota_sample(&QuotaSample::new("a".to_string(), 200, 1_000_000, None, 1_000_000.0)).unwrap();
+
+        let samples = manager.quota_samples("a", 0).unwrap();
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[0].balance_remaining, 2.0);
+        assert_eq!(samples[0].amount_used, Some(0.5));
+        assert_eq!(samples[1].balance_remaining, 1.0);
+        assert_eq!(manager.quota_samples("a", 150).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_discovers_quota_per_unit_from_station_info() {
+        tokio::runtime::Runtime::new().unwrap().block_on(async {
+            let mut replies = [250_000, 0].into_iter();
+            let base_url = serve_http(move |_| {
+                let quota_per_unit = replies.next().unwrap_or(0);
+                ("application/json", serde_json::json!({ "success": true, "data": { "quota_per_unit": quota_per_unit } }).to_string())
+            })
+            .await;
+
+            let station = test_station(&Uuid::new_v4().to_string(), "Local", &base_url);
+            assert_eq!(discover_station_quota_per_unit(&station).await.unwrap(), 250_000);
+            let unreported = discover_station_quota_per_unit(&station).await;
+            assert!(matches!(unreported, Err(RelayError::Unsupported { .. })), "{:?}", unreported.err());
+        });
+    }
+
+    #[test]
+    fn test_proposed_adapter_config_is_tested_without_saving() {
+        tokio::runtime::Runtime::new().unwrap().block_on(async {
+            let base_url = serve_http(|_| ("application/json", serde_json::json!({ "success": true, "data": {} }).to_string())).await;
+
+            let mut station = test_station(&Uuid::new_v4().to_string(), "Local", &base_url);
+            station.adapter_config = Some(HashMap::from([("proxy_url".to_string(), "http://127.0.0.1:1".into())]));
+
+            let invalid = HashMap::from([("proxy_url".to_string(), "::not a proxy::".into())]);
+            let rejected = test_proposed_adapter_config(station.clone(), invalid, Some(2)).await;
+            assert!(matches!(rejected, Err(RelayError::InvalidInput { .. })), "{:?}", rejected);
+
+            // Dropping the unreachable proxy from the proposal lets the probe through
+            let proposed = HashMap::from([("timeout_secs".to_string(), 2.into())]);
+            let result = test_proposed_adapter_config(station.clone(), proposed, None).await.unwrap();
+            assert!(result.success, "{}", result.message);
+            assert_eq!(result.details.unwrap()["scheme"], "http");
+            assert_eq!(station.adapter_config.unwrap()["proxy_url"], "http://127.0.0.1:1");
+        });
+    }
+
+    #[test]
+    fn test_cancelled_operation_reports_cancelled() {
+        tokio::runtime::Runtime::new().unwrap().block_on(async {
+            let id = Uuid::new_v4().to_string();
+            let slow = cancellable(Some(id.clone()), async {
+                tokio::time::sleep(Duration::from_secs(30)).await;
+                Ok(())
+            });
+            let cancel = async {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                let duplicate = cancellable(Some(id.clone()), async { Ok(()) }).await;
+                assert!(matches!(duplicate, Err(RelayError::Conflict { .. })));
+                cancel_operation(id.clone()).await.unwrap()
+            };
+            let (result, message) = tokio::join!(slow, cancel);
+            assert!(matches!(result, Err(RelayError::Cancelled { .. })));
+            assert_eq!(message, "Operation cancelled");
+            assert!(!OPERATIONS.lock().unwrap().contains_key(&id));
+            assert_eq!(cancellable(None, async { Ok(1) }).await.unwrap(), 1);
+        });
+    }
+
+    #[test]
+    fn test_blocking_manager_calls_leave_runtime_free() {
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        let manager = Arc::new(test_manager());
+        runtime.block_on(async {
+            let started = std::time::Instant::now();
+            let db = run_blocking(manager, |manager| {
+                std::thread::sleep(std::time::Duration::from_millis(300));
+                manager.list_stations().map_err(|e| RelayError::database(e.to_string()))
+            });
+            // Stands in for a network request sharing the single runtime thread
+            let network = async {
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+                started.elapsed()
+            };
+            let (stations, network_elapsed) = tokio::join!(db, network);
+            assert!(stations.unwrap().is_empty());
+            assert!(network_elapsed < std::time::Duration::from_millis(250), "network task stalled for {:?}", network_elapsed);
+        });
+    }
+
+    #[test]
+    fn test_manager_state_reports_init_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let broken = RelayManagerState::new(dir.path().join("missing").join("agents.db"));
+        assert!(broken.init().is_err());
+        let status = broken.status();
+        assert!(!status.initialized);
+        assert!(status.last_error.unwrap().contains("Failed to open relay station database"));
+
+        let state = RelayManagerState::new(dir.path().join("agents.db"));
+        state.init().unwrap();
+        assert!(state.status().initialized);
+        assert!(state.status().last_error.is_none());
+    }
+
+    #[test]
+    fn test_normalize_allow_ips() {
+        assert_eq!(normalize_allow_ips("").unwrap(), "");
+        assert_eq!(
+            normalize_allow_ips(" 10.0.0.1, 192.168.0.0/16\n::1\r\n2001:db8::/32,").unwrap(),
+            "10.0.0.1\n192.168.0.0/16\n::1\n2001:db8::/32"
+        );
+
+        for (input, bad) in [
+            ("10.0.0.1, 10.0.0.256", "10.0.0.256"),
+            ("10.0.0.0/33", "10.0.0.0/33"),
+            ("::1\nlocalhost", "localhost"),
+            ("2001:db8::/129", "2001:db8::/129"),
+            ("10.0.0.0/", "10.0.0.0/"),
+        ] {
+            let err = normalize_allow_ips(input).unwrap_err();
+            assert!(matches!(err, RelayError::InvalidInput { .. }));
+            assert!(err.message().contains(&format!("'{}'", bad)), "{}", err.message());
+        }
+    }
+
+    #[test]
+    fn test_normalize_api_url_rejects_malformed() {
+        for input in ["example.com", "", "ftp://example.com", "https://", "not a url"] {
+            assert!(
+                matches!(normalize_api_url(input), Err(RelayError::InvalidInput { .. })),
+                "expected {:?} to be rejected",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_plain_http_stations_are_flagged() {
+        let manager = test_manager();
+        manager.add_station(&test_station("local", "Local", "http://localhost:3000")).unwrap();
+        manager.add_station(&test_station("remote", "Remote", "https://relay.example.com")).unwrap();
+
+        let local = manager.get_station("local").unwrap().unwrap();
+        assert!(!local.is_secure && !local.is_secure());
+        assert!(local.plain_http_warning().unwrap().contains("unencrypted"));
+        assert!(RelayStation { system_token: String::new(), ..local }.plain_http_warning().is_none());
+
+        let remote = manager.get_station("remote").unwrap().unwrap();
+        assert!(remote.is_secure && remote.plain_http_warning().is_none());
+        assert!(is_secure_url("HTTPS://relay.example.com"));
+        assert!(!is_secure_url("not a url"));
+    }
+
+    #[test]
+    fn test_station_info_cache_respects_ttl_and_invalidation() {
+        let info = StationInfo {
+            name: "Relay".to_string(),
+            announcement: None,
+            api_url: "https://relay.example.com".to_string(),
+            version: None,
+            metadata: None,
+            quota_per_unit: None,
+            is_secure: true,
+        };
+        let id = format!("cache-{}", Uuid::new_v4());
+        STATION_INFO_CACHE.lock().unwrap().insert(id.clone(), (Instant::now(), info));
+
+        assert!(cached_station_info(&id, Duration::from_secs(60)).is_some());
+        assert!(cached_station_info(&id, Duration::ZERO).is_none());
+
+        invalidate_station_info(&id);
+        assert!(cached_station_info(&id, Duration::from_secs(60)).is_none());
+    }
+
+    #[test]
+    fn test_invalidate_station_caches_clears_only_that_station() {
+        let id = format!("cache-{}", Uuid::new_v4());
+        let other = format!("cache-{}", Uuid::new_v4());
+        for station_id in [&id, &other] {
+            MODELS_CACHE.lock().unwrap().insert(station_id.clone(), (Instant::now(), vec!["gpt-4o".to_string()]));
+            GROUPS_CACHE.lock().unwrap().insert(station_id.clone(), (Instant::now(), Vec::new()));
+            LOW_BALANCE_STATIONS.lock().unwrap().insert(station_id.clone());
+        }
+
+        invalidate_station_caches(&id);
+        assert!(!MODELS_CACHE.lock().unwrap().contains_key(&id));
+        assert!(!GROUPS_CACHE.lock().unwrap().contains_key(&id));
+        assert!(!LOW_BALANCE_STATIONS.lock().unwrap().contains(&id));
+        assert!(MODELS_CACHE.lock().unwrap().contains_key(&other));
+        assert!(GROUPS_CACHE.lock().unwrap().contains_key(&other));
+        assert!(LOW_BALANCE_STATIONS.lock().unwrap().contains(&other));
+    }
+}
@@ -1,4 +1,6 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
@@ -21,23 +23,114 @@ impl Language {
             _ => Language::Zh,
         }
     }
+
+    /// 根据系统语言环境推断语言，无法识别时回退到中文
+    pub fn from_system_locale() -> Self {
+        let locale = sys_locale::get_locale()
+            .or_else(|| {
+                ["LC_ALL", "LC_MESSAGES", "LANG"]
+                    .iter()
+                    .filter_map(|var| std::env::var(var).ok())
+                    .find(|value| !value.is_empty() && value != "C" && value != "POSIX")
+            });
+
+        match locale {
+            // 只取主语言部分，例如 en-GB、en_US.UTF-8 -> en
+            Some(locale) => {
+                let primary = locale.split(['-', '_', '.']).next().unwrap_or("");
+                Self::from_str(primary)
+            }
+            None => Language::Zh,
+        }
+    }
+
+    /// 启动时使用的语言：优先用户保存的偏好，其次系统语言
+    pub fn initial() -> Self {
+        load_language_preference().unwrap_or_else(Self::from_system_locale)
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Language::Zh => "zh",
+            Language::En => "en",
+        }
+    }
+
+    pub fn all() -> [Language; 2] {
+        [Language::Zh, Language::En]
+    }
 }
 
 pub struct I18n {
     current_language: Language,
     messages: HashMap<String, HashMap<Language, String>>,
+    missing_keys: Mutex<HashSet<String>>,
 }
 
 impl I18n {
     pub fn new(language: Language) -> Self {
+        Self::with_external_dir(language, Self::external_dir().as_deref())
+    }
+
+    /// 使用指定的外部翻译目录创建实例，`None` 时只包含内置翻译
+    pub(crate) fn with_external_dir(language: Language, dir: Option<&Path>) -> Self {
         let mut i18n = Self {
             current_language: language,
             messages: HashMap::new(),
+            missing_keys: Mutex::new(HashSet::new()),
         };
         i18n.initialize_messages();
+        i18n.load_external_messages(dir);
         i18n
     }
 
+    /// 重新加载翻译：先恢复内置默认值，再合并外部翻译文件
+    pub fn reload(&mut self) -> usize {
+        self.messages.clear();
+        self.initialize_messages();
+        self.load_external_messages(Self::external_dir().as_deref())
+    }
+
+    // 外部翻译文件目录 ~/.claude/i18n
+    fn external_dir() -> Option<PathBuf> {
+        crate::paths::claude_dir().map(|dir| dir.join("i18n"))
+    }
+
+    // 合并 {dir}/{lang}.json 中的翻译，返回合并的条目数
+    fn load_external_messages(&mut self, dir: Option<&Path>) -> usize {
+        let Some(dir) = dir else {
+            return 0;
+        };
+
+        let mut loaded = 0;
+        for language in Language::all() {
+            let path = dir.join(format!("{}.json", language.code()));
+            if !path.exists() {
+                continue;
+            }
+            match self.merge_external_file(&language, &path) {
+                Ok(count) => loaded += count,
+                Err(e) => log::warn!("Ignoring translation file {}: {}", path.display(), e),
+            }
+        }
+        loaded
+    }
+
+    // 读取 key → 字符串 的 JSON 文件并覆盖对应语言的内置翻译
+    fn merge_external_file(&mut self, language: &Language, path: &Path) -> Result<usize, String> {
+        let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let overrides: HashMap<String, String> = serde_json::from_str(&content).map_err(|e| e.to_string())?;
+
+        let count = overrides.len();
+        for (key, value) in overrides {
+            self.messages
+                .entry(key)
+                .or_default()
+                .insert(language.clone(), value);
+        }
+        Ok(count)
+    }
+
     pub fn set_language(&mut self, language: Language) {
         self.current_language = language;
     }
@@ -47,9 +140,18 @@ impl I18n {
     }
 
     pub fn t(&self, key: &str) -> String {
-        self.messages
+        let translation = self.messages
             .get(key)
-            .and_then(|translations| translations.get(&self.current_language))
+            .and_then(|translations| translations.get(&self.current_language));
+
+        // 记录当前语言缺失的翻译，便于审计
+        if translation.is_none() {
+            if let Ok(mut missing) = self.missing_keys.lock() {
+                missing.insert(key.to_string());
+            }
+        }
+
+        translation
             .or_else(|| {
                 // 如果当前语言没有翻译，尝试英文
                 self.messages
@@ -60,12 +162,62 @@ impl I18n {
             .unwrap_or_else(|| format!("Missing translation: {}", key))
     }
 
-    pub fn t_with_args(&self, key: &str, args: &[(&str, &str)]) -> String {
-        let mut message = self.t(key);
-        for (placeholder, value) in args {
-            message = message.replace(&format!("{{{}}}", placeholder), value);
+    /// 返回运行期间在当前语言下缺失翻译的 key，按字母排序
+    pub fn missing_keys(&self) -> Vec<String> {
+        let mut keys: Vec<String> = self.missing_keys
+            .lock()
+            .map(|missing| missing.iter().cloned().collect())
+            .unwrap_or_default();
+        keys.sort();
+        keys
+    }
+
+    /// 按点号前的模块前缀（provider、process 等）分组返回所有已注册的 key，组内排序
+    pub fn keys_by_prefix(&self) -> BTreeMap<String, Vec<String>> {
+        let mut groups: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for key in self.messages.keys() {
+            let prefix = key.split_once('.').map(|(prefix, _)| prefix).unwrap_or(key);
+            groups.entry(prefix.to_string()).or_default().push(key.clone());
+        }
+        for keys in groups.values_mut() {
+            keys.sort();
         }
-        message
+        groups
+    }
+
+    /// 所有 key 当前生效的中英文（已合并外部翻译文件），按 key 排序
+    pub fn translation_pairs(&self) -> BTreeMap<String, TranslationPair> {
+        self.messages
+            .iter()
+            .map(|(key, translations)| {
+                let pair = TranslationPair {
+                    zh: translations.get(&Language::Zh).cloned(),
+                    en: translations.get(&Language::En).cloned(),
+                };
+                (key.clone(), pair)
+            })
+            .collect()
+    }
+
+    // 只含内置默认翻译的实例，用于判断导入的值是否与默认值相同
+    fn builtin() -> Self {
+        let mut i18n = Self {
+            current_language: Language::default(),
+            messages: HashMap::new(),
+            missing_keys: Mutex::new(HashSet::new()),
+        };
+        i18n.initialize_messages();
+        i18n
+    }
+
+    pub fn clear_missing_keys(&self) {
+        if let Ok(mut missing) = self.missing_keys.lock() {
+            missing.clear();
+        }
+    }
+
+    pub fn t_with_args(&self, key: &str, args: &[(&str, &str)]) -> String {
+        substitute_args(&self.t(key), args)
     }
 
     fn add_message(&mut self, key: &str, zh: &str, en: &str) {
@@ -82,20 +234,43 @@ impl I18n {
         self.add_message("provider.read_claude_settings_failed", "读取 Claude settings 文件失败: {error}", "Failed to read Claude settings file: {error}");
         self.add_message("provider.parse_claude_settings_failed", "解析 Claude settings 文件失败: {error}", "Failed to parse Claude settings file: {error}");
         self.add_message("provider.serialize_claude_settings_failed", "序列化 Claude settings 失败: {error}", "Failed to serialize Claude settings: {error}");
+        self.add_message("provider.backup_claude_settings_failed", "备份 Claude settings 文件失败: {error}", "Failed to back up Claude settings file: {error}");
         self.add_message("provider.write_claude_settings_failed", "写入 Claude settings 文件失败: {error}", "Failed to write Claude settings file: {error}");
         self.add_message("provider.read_config_failed", "读取配置文件失败: {error}", "Failed to read config file: {error}");
         self.add_message("provider.parse_config_failed", "解析配置文件失败: {error}", "Failed to parse config file: {error}");
         self.add_message("provider.serialize_config_failed", "序列化配置失败: {error}", "Failed to serialize config: {error}");
         self.add_message("provider.write_config_failed", "写入配置文件失败: {error}", "Failed to write config file: {error}");
+        self.add_message("provider.created_from_token", "从中转站 {station} 的令牌 {token} 创建", "Created from token {token} on relay station {station}");
         self.add_message("provider.invalid_config_format", "配置文件格式错误: {error}", "Invalid config file format: {error}");
         self.add_message("provider.id_already_exists", "ID '{id}' 已存在，请使用不同的ID", "ID '{id}' already exists, please use a different ID");
+        self.add_message("provider.validation_id_empty", "ID 不能为空", "ID cannot be empty");
+        self.add_message("provider.validation_invalid_url", "无效的 URL '{url}'，需要完整的 http 或 https 地址", "Invalid URL '{url}', expected a full http or https address");
+        self.add_message("provider.validation_missing_credentials", "auth_token 和 api_key 至少需要填写一个", "At least one of auth_token and api_key is required");
+        self.add_message("provider.model_not_served", "模型 {model} 不在中转站 {station} 的模型列表中", "Model {model} is not served by relay station {station}");
+        self.add_message("provider.import_invalid_settings", "无法解析 settings.json: {error}", "Failed to parse settings.json: {error}");
+        self.add_message("provider.import_no_provider_env", "settings.json 中没有 ANTHROPIC_BASE_URL 及认证信息", "settings.json has no ANTHROPIC_BASE_URL with a token or API key");
+        self.add_message("provider.settings_env_unrecoverable", "settings.json 中的 env 配置块已损坏且没有可用备份，请手动修复", "The env block in settings.json is damaged and there is no usable backup; please fix it manually");
+        self.add_message("provider.repair_unsupported_shape", "providers.json 既不是数组也不包含 providers 数组", "providers.json is neither an array nor an object with a providers array");
+        self.add_message("provider.import_conflict", "已存在相同地址和认证信息的代理商配置: {name}", "A provider with the same URL and credentials already exists: {name}");
+        self.add_message("provider.clear_dry_run", "将移除以下环境变量（未修改文件）: {keys}", "Would remove these environment variables (no changes made): {keys}");
         self.add_message("provider.add_success", "成功添加代理商配置: {name}", "Successfully added provider config: {name}");
         self.add_message("provider.config_not_found", "未找到ID为 '{id}' 的配置", "Config with ID '{id}' not found");
         self.add_message("provider.update_success", "成功更新代理商配置: {name}", "Successfully updated provider config: {name}");
         self.add_message("provider.delete_success", "成功删除代理商配置: {name}", "Successfully deleted provider config: {name}");
         self.add_message("provider.switch_success", "已成功切换到 {name} ({description})，配置已保存到 Raw Settings", "Successfully switched to {name} ({description}), config saved to Raw Settings");
         self.add_message("provider.clear_success", "已清理所有 ANTHROPIC 环境变量在 Raw Settings 中", "Cleared all ANTHROPIC environment variables in Raw Settings");
-        self.add_message("provider.connection_test_complete", "连接测试完成：{url}", "Connection test completed: {url}");
+        self.add_message("provider.connection_authorized", "连接成功：{url}（HTTP {status}，{latency} ms）", "Connection successful: {url} (HTTP {status}, {latency} ms)");
+        self.add_message("provider.connection_unauthorized", "端点可达但认证失败：{url}（HTTP {status}）", "Endpoint reachable but authentication failed: {url} (HTTP {status})");
+        self.add_message("provider.connection_rate_limited", "端点可达但请求被限流：{url}（HTTP {status}）", "Endpoint reachable but rate limited: {url} (HTTP {status})");
+        self.add_message("provider.connection_reachable", "端点可达但拒绝了测试请求：{url}（HTTP {status}）", "Endpoint reachable but rejected the test request: {url} (HTTP {status})");
+        self.add_message("provider.connection_not_found", "端点不存在，请检查 base_url：{url}（HTTP {status}）", "Endpoint not found, check the base_url: {url} (HTTP {status})");
+        self.add_message("provider.connection_unexpected_status", "端点返回了意外的状态码：{url}（HTTP {status}）", "Endpoint returned an unexpected status: {url} (HTTP {status})");
+        self.add_message("provider.connection_server_error", "端点返回服务器错误：{url}（HTTP {status}）", "Endpoint returned a server error: {url} (HTTP {status})");
+        self.add_message("provider.connection_dns_failure", "DNS 解析失败：{url}: {error}", "DNS resolution failed: {url}: {error}");
+        self.add_message("provider.connection_timeout", "连接超时：{url}", "Connection timed out: {url}");
+        self.add_message("provider.probe_failed", "无法连接 {url}: {error}", "Failed to reach {url}: {error}");
+        self.add_message("provider.fallback_switch_success", "主代理商不可用，已切换到备用代理商 {name}（跳过 {count} 个）", "Primary provider unavailable, switched to fallback {name} ({count} skipped)");
+        self.add_message("provider.fallback_all_failed", "备用链中的 {count} 个代理商均不可用，配置未更改", "All {count} providers in the fallback chain are unavailable, settings unchanged");
         
         // Process termination messages
         self.add_message("process.terminating_claude_processes", "正在终止所有Claude进程以应用新的代理商配置...", "Terminating all Claude processes to apply new provider configuration...");
@@ -148,6 +323,39 @@ impl I18n {
         self.add_message("slash.command_execution_failed", "斜杠命令执行失败: {error}", "Slash command execution failed: {error}");
         self.add_message("slash.command_add_success", "成功添加斜杠命令: {name}", "Successfully added slash command: {name}");
         self.add_message("slash.command_delete_success", "成功删除斜杠命令: {name}", "Successfully deleted slash command: {name}");
+
+        // Translation messages
+        self.add_message("i18n.reloaded", "已重新加载翻译，合并 {count} 条外部翻译", "Translations reloaded, {count} external entries merged");
+        self.add_message("i18n.exported", "已导出 {count} 条翻译到 {path}", "Exported {count} translations to {path}");
+        self.add_message("i18n.imported", "已导入翻译，更新 {count} 条外部翻译", "Translations imported, {count} external entries updated");
+        self.add_message("i18n.io_failed", "读写翻译文件 {path} 失败: {error}", "Failed to read or write translation file {path}: {error}");
+        self.add_message("i18n.parse_failed", "无法解析翻译文件: {error}", "Failed to parse translation file: {error}");
+
+        // Provider switch history messages
+        self.add_message("history.read_failed", "读取切换历史失败: {error}", "Failed to read switch history: {error}");
+        self.add_message("history.parse_failed", "解析切换历史失败: {error}", "Failed to parse switch history: {error}");
+        self.add_message("history.write_failed", "写入切换历史失败: {error}", "Failed to write switch history: {error}");
+        self.add_message("history.no_previous_provider", "没有可恢复的上一个代理商", "No previous provider to restore");
+
+        // Environment profile messages
+        self.add_message("profile.name_empty", "配置名称不能为空", "Profile name cannot be empty");
+        self.add_message("profile.not_found", "未找到名为 '{name}' 的环境配置", "Profile '{name}' not found");
+        self.add_message("profile.saved", "已保存环境配置: {name}", "Saved profile: {name}");
+        self.add_message("profile.applied", "已应用环境配置 {name}（{count} 个变量）", "Applied profile {name} ({count} variables)");
+        self.add_message("profile.deleted", "已删除环境配置: {name}", "Deleted profile: {name}");
+
+        // Provider group messages
+        self.add_message("group.name_empty", "代理商组名称不能为空", "Provider group name cannot be empty");
+        self.add_message("group.no_weight", "代理商组至少需要一个权重大于 0 的成员", "A provider group needs at least one member with a weight above 0");
+        self.add_message("group.not_found", "未找到名为 '{name}' 的代理商组", "Provider group '{name}' not found");
+        self.add_message("group.saved", "已保存代理商组: {name}", "Saved provider group: {name}");
+        self.add_message("group.deleted", "已删除代理商组: {name}", "Deleted provider group: {name}");
+        self.add_message("group.recently_unhealthy", "最近的健康检查失败", "Failed a recent health check");
+        self.add_message("group.switch_success", "已按权重从组 {group} 中选择 {name}", "Selected {name} from group {group} by weight");
+        self.add_message("group.all_failed", "组 {group} 中没有可用的代理商，配置未更改", "No provider in group {group} is available, settings unchanged");
+
+        // Relay station messages
+        self.add_message("relay.log_message", "API调用 - 模型: {model} | 提示: {prompt_tokens} | 补全: {completion_tokens} | 花费: {quota}", "API call - model: {model} | prompt: {prompt_tokens} | completion: {completion_tokens} | cost: {quota}");
     }
 }
 
@@ -156,9 +364,68 @@ use std::sync::{Arc, Mutex};
 use once_cell::sync::Lazy;
 
 static GLOBAL_I18N: Lazy<Arc<Mutex<I18n>>> = Lazy::new(|| {
-    Arc::new(Mutex::new(I18n::new(Language::Zh)))
+    // 测试中不读取配置目录：其他测试可能正把它指向临时目录
+    #[cfg(test)]
+    let i18n = I18n::with_external_dir(Language::from_system_locale(), None);
+    #[cfg(not(test))]
+    let i18n = I18n::new(Language::initial());
+    Arc::new(Mutex::new(i18n))
 });
 
+// 用户语言偏好文件 ~/.claude/i18n/preference.json
+fn language_preference_path() -> Option<PathBuf> {
+    I18n::external_dir().map(|dir| dir.join("preference.json"))
+}
+
+#[derive(Serialize, Deserialize)]
+struct LanguagePreference {
+    language: Language,
+}
+
+fn load_language_preference() -> Option<Language> {
+    read_language_preference(&language_preference_path()?)
+}
+
+fn read_language_preference(path: &Path) -> Option<Language> {
+    let content = fs::read_to_string(path).ok()?;
+    serde_json::from_str::<LanguagePreference>(&content)
+        .map(|preference| preference.language)
+        .ok()
+}
+
+// 先写入同目录临时文件再重命名，避免中途崩溃留下半截的偏好文件
+fn write_language_preference(path: &Path, language: &Language) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(&LanguagePreference { language: language.clone() })
+        .map_err(|e| e.to_string())?;
+    write_file_atomic(path, &content)
+}
+
+// 先写入同目录下的临时文件再重命名，写入中途崩溃不会留下半个文件
+fn write_file_atomic(path: &Path, content: &str) -> Result<(), String> {
+    let dir = path.parent().ok_or_else(|| format!("Invalid path: {}", path.display()))?;
+    fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    let mut temp_file = tempfile::NamedTempFile::new_in(dir).map_err(|e| e.to_string())?;
+    std::io::Write::write_all(&mut temp_file, content.as_bytes()).map_err(|e| e.to_string())?;
+    temp_file.persist(path).map_err(|e| e.error.to_string())?;
+    Ok(())
+}
+
+/// 保存用户显式选择的语言，使其在重启后依然生效
+pub fn save_language_preference(language: &Language) -> Result<(), String> {
+    let path = language_preference_path().ok_or_else(|| "Failed to get user home directory".to_string())?;
+    write_language_preference(&path, language)
+}
+
+/// 切换语言并持久化。写文件与切换在同一把锁内完成，并发调用时内存中的语言与偏好文件始终一致；
+/// 保存失败时返回错误，但本次切换依然生效
+pub fn set_language_persisted(language: Language) -> Result<(), String> {
+    let mut i18n = GLOBAL_I18N.lock().unwrap_or_else(|e| e.into_inner());
+    let saved = save_language_preference(&language);
+    i18n.set_language(language);
+    saved
+}
+
+/// 仅修改内存中的语言，不写偏好文件（供测试和临时切换使用）
 pub fn set_language(language: Language) {
     if let Ok(mut i18n) = GLOBAL_I18N.lock() {
         i18n.set_language(language);
@@ -171,6 +438,258 @@ pub fn get_language() -> Language {
         .unwrap_or_default()
 }
 
+pub fn get_missing_translations() -> Vec<String> {
+    GLOBAL_I18N.lock()
+        .map(|i18n| i18n.missing_keys())
+        .unwrap_or_default()
+}
+
+pub fn list_translation_keys() -> BTreeMap<String, Vec<String>> {
+    GLOBAL_I18N.lock()
+        .map(|i18n| i18n.keys_by_prefix())
+        .unwrap_or_default()
+}
+
+pub fn clear_missing_translations() {
+    if let Ok(i18n) = GLOBAL_I18N.lock() {
+        i18n.clear_missing_keys();
+    }
+}
+
+pub fn reload_translations() -> usize {
+    GLOBAL_I18N.lock()
+        .map(|mut i18n| i18n.reload())
+        .unwrap_or(0)
+}
+
+/// 导出和导入翻译的文件格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TranslationFormat {
+    /// 扁平 JSON：`{key: {zh, en}}`
+    Json,
+    /// gettext .po：msgctxt 为 key，msgid 为英文原文，msgstr 为中文译文
+    Po,
+}
+
+impl TranslationFormat {
+    /// 按扩展名推断格式，.po 以外都按 JSON 处理
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("po") => TranslationFormat::Po,
+            _ => TranslationFormat::Json,
+        }
+    }
+}
+
+/// 一个 key 的中英文翻译，缺失或留空的语言为 None
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TranslationPair {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zh: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub en: Option<String>,
+}
+
+fn escape_po(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n").replace('\t', "\\t")
+}
+
+// 解析 .po 中带引号的字符串，不是合法字符串时返回 None
+fn unquote_po(quoted: &str) -> Option<String> {
+    let inner = quoted.strip_prefix('"')?.strip_suffix('"')?;
+    let mut value = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        match chars.next()? {
+            'n' => value.push('\n'),
+            't' => value.push('\t'),
+            other => value.push(other),
+        }
+    }
+    Some(value)
+}
+
+/// 将翻译写成指定格式的文本
+pub fn render_translations(pairs: &BTreeMap<String, TranslationPair>, format: TranslationFormat) -> Result<String, String> {
+    match format {
+        TranslationFormat::Json => serde_json::to_string_pretty(pairs).map_err(|e| e.to_string()),
+        TranslationFormat::Po => {
+            let mut out = String::from("msgid \"\"\nmsgstr \"\"\n\"Content-Type: text/plain; charset=UTF-8\\n\"\n\"Language: zh_CN\\n\"\n");
+            for (key, pair) in pairs {
+                // 没有英文原文时用 key 作为 msgid，避免与空 msgid 的文件头冲突
+                let source = pair.en.as_deref().unwrap_or(key);
+                out.push_str(&format!(
+                    "\nmsgctxt \"{}\"\nmsgid \"{}\"\nmsgstr \"{}\"\n",
+                    escape_po(key),
+                    escape_po(source),
+                    escape_po(pair.zh.as_deref().unwrap_or_default())
+                ));
+            }
+            Ok(out)
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum PoField {
+    Context,
+    Id,
+    Str,
+}
+
+/// 解析 `render_translations` 格式的文本。.po 只带回中文（msgstr），空值视为未翻译
+pub fn parse_translations(content: &str, format: TranslationFormat) -> Result<BTreeMap<String, TranslationPair>, String> {
+    if format == TranslationFormat::Json {
+        return serde_json::from_str(content).map_err(|e| e.to_string());
+    }
+
+    let mut pairs = BTreeMap::new();
+    let mut context: Option<String> = None;
+    let mut msgstr = String::new();
+    let mut field: Option<PoField> = None;
+    let mut flush = |context: &mut Option<String>, msgstr: &mut String| {
+        if let Some(key) = context.take() {
+            let zh = Some(std::mem::take(msgstr)).filter(|s| !s.is_empty());
+            pairs.insert(key, TranslationPair { zh, en: None });
+        }
+        msgstr.clear();
+    };
+
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (keyword, quoted) = match line.split_once(' ') {
+            Some((keyword, rest)) if !line.starts_with('"') => (Some(keyword), rest.trim()),
+            _ => (None, line),
+        };
+        let value = unquote_po(quoted).ok_or_else(|| format!("line {}: invalid string", index + 1))?;
+        match (keyword, field) {
+            (Some("msgctxt"), _) => {
+                flush(&mut context, &mut msgstr);
+                context = Some(value);
+                field = Some(PoField::Context);
+            }
+            (Some("msgid"), previous) => {
+                if previous == Some(PoField::Str) {
+                    flush(&mut context, &mut msgstr);
+                }
+                field = Some(PoField::Id);
+            }
+            (Some("msgstr"), _) => {
+                msgstr = value;
+                field = Some(PoField::Str);
+            }
+            (Some(other), _) => return Err(format!("line {}: unsupported keyword {}", index + 1, other)),
+            (None, Some(PoField::Context)) => context.get_or_insert_with(String::new).push_str(&value),
+            (None, Some(PoField::Str)) => msgstr.push_str(&value),
+            (None, Some(PoField::Id)) => {}
+            (None, None) => return Err(format!("line {}: string outside an entry", index + 1)),
+        }
+    }
+    flush(&mut context, &mut msgstr);
+    Ok(pairs)
+}
+
+// 把导入的某种语言的值合并进外部翻译覆盖表：与内置默认值相同的值移出覆盖表，
+// 以便内置翻译后续的修改仍能生效。返回发生变化的条目数
+fn merge_overrides(
+    overrides: &mut BTreeMap<String, String>,
+    builtin: &I18n,
+    language: &Language,
+    imported: &BTreeMap<String, TranslationPair>,
+) -> usize {
+    let mut changed = 0;
+    for (key, pair) in imported {
+        let value = match language {
+            Language::Zh => pair.zh.as_deref(),
+            Language::En => pair.en.as_deref(),
+        };
+        let Some(value) = value.filter(|v| !v.is_empty()) else { continue };
+        let default = builtin.messages.get(key).and_then(|translations| translations.get(language));
+        if default.map(String::as_str) == Some(value) {
+            if overrides.remove(key).is_some() {
+                changed += 1;
+            }
+        } else if overrides.get(key).map(String::as_str) != Some(value) {
+            overrides.insert(key.clone(), value.to_string());
+            changed += 1;
+        }
+    }
+    changed
+}
+
+/// 导出所有 key 当前生效的中英文
+pub fn export_translations(format: TranslationFormat) -> Result<(String, usize), String> {
+    let pairs = GLOBAL_I18N.lock()
+        .map(|i18n| i18n.translation_pairs())
+        .map_err(|e| e.to_string())?;
+    Ok((render_translations(&pairs, format)?, pairs.len()))
+}
+
+/// 把翻译人员编辑后的文件合并进 ~/.claude/i18n/{lang}.json 并重新加载，返回更新的条目数
+pub fn import_translations(content: &str, format: TranslationFormat) -> Result<usize, String> {
+    let imported = parse_translations(content, format)?;
+    let dir = I18n::external_dir().ok_or_else(|| "Failed to get user home directory".to_string())?;
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+
+    let builtin = I18n::builtin();
+    let mut changed = 0;
+    for language in Language::all() {
+        let path = dir.join(format!("{}.json", language.code()));
+        let mut overrides: BTreeMap<String, String> = match fs::read_to_string(&path) {
+            Ok(existing) => serde_json::from_str(&existing).map_err(|e| format!("{}: {}", path.display(), e))?,
+            Err(_) => BTreeMap::new(),
+        };
+        let language_changed = merge_overrides(&mut overrides, &builtin, &language, &imported);
+        if language_changed > 0 {
+            let content = serde_json::to_string_pretty(&overrides).map_err(|e| e.to_string())?;
+            write_file_atomic(&path, &content).map_err(|e| format!("{}: {}", path.display(), e))?;
+            changed += language_changed;
+        }
+    }
+
+    reload_translations();
+    Ok(changed)
+}
+
+/// 单次从左到右替换 `{name}` 占位符：替换进来的值不会再被解析，未知占位符原样保留
+fn substitute_args(template: &str, args: &[(&str, &str)]) -> String {
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(open) = rest.find('{') {
+        result.push_str(&rest[..open]);
+        let after_open = &rest[open + 1..];
+        let value = after_open.find('}').and_then(|close| {
+            let name = &after_open[..close];
+            args.iter()
+                .find(|(placeholder, _)| *placeholder == name)
+                .map(|(_, value)| (*value, close))
+        });
+
+        match value {
+            Some((value, close)) => {
+                result.push_str(value);
+                rest = &after_open[close + 1..];
+            }
+            None => {
+                // 不是已知占位符，保留这个 '{' 并从下一个字符继续扫描
+                result.push('{');
+                rest = after_open;
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
 pub fn t(key: &str) -> String {
     GLOBAL_I18N.lock()
         .map(|i18n| i18n.t(key))
@@ -183,13 +702,147 @@ pub fn t_with_args(key: &str, args: &[(&str, &str)]) -> String {
         .unwrap_or_else(|_| format!("Translation error: {}", key))
 }
 
-// 便捷宏
+// 便捷宏：参数值可以是任意实现 Display 的类型，例如 t!("process.found_active_sessions", "count" => sessions.len())
 #[macro_export]
 macro_rules! t {
     ($key:expr) => {
         crate::i18n::t($key)
     };
-    ($key:expr, $($arg_name:expr => $arg_value:expr),+) => {
-        crate::i18n::t_with_args($key, &[$(($arg_name, $arg_value)),+])
+    ($key:expr, $($arg_name:expr => $arg_value:expr),+ $(,)?) => {
+        crate::i18n::t_with_args($key, &[$(($arg_name, ::std::string::ToString::to_string(&$arg_value).as_str())),+])
     };
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_external_file_overrides_builtin() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("en.json"), r#"{"provider.clear_success": "Cleared", "custom.key": "Custom"}"#).unwrap();
+
+        let mut i18n = I18n::with_external_dir(Language::En, None);
+        assert_eq!(i18n.load_external_messages(Some(temp_dir.path())), 2);
+        assert_eq!(i18n.t("provider.clear_success"), "Cleared");
+        assert_eq!(i18n.t("custom.key"), "Custom");
+        // Keys absent from the file keep their built-in text
+        assert_eq!(i18n.t("provider.home_dir_not_found"), "Failed to get user home directory");
+    }
+
+    #[test]
+    fn test_missing_keys_are_collected() {
+        let mut i18n = I18n::with_external_dir(Language::En, None);
+        i18n.add_message("zh.only", "仅中文", "Chinese only");
+        i18n.messages.get_mut("zh.only").unwrap().remove(&Language::En);
+
+        i18n.t("provider.clear_success");
+        assert!(i18n.missing_keys().is_empty());
+
+        assert_eq!(i18n.t("does.not.exist"), "Missing translation: does.not.exist");
+        i18n.t("zh.only");
+        assert_eq!(i18n.missing_keys(), vec!["does.not.exist".to_string(), "zh.only".to_string()]);
+
+        i18n.clear_missing_keys();
+        assert!(i18n.missing_keys().is_empty());
+    }
+
+    #[test]
+    fn test_language_preference_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("i18n").join("preference.json");
+        assert_eq!(read_language_preference(&path), None);
+
+        write_language_preference(&path, &Language::En).unwrap();
+        assert_eq!(read_language_preference(&path), Some(Language::En));
+        // Overwriting replaces the file in place and leaves no temp files behind
+        write_language_preference(&path, &Language::Zh).unwrap();
+        assert_eq!(read_language_preference(&path), Some(Language::Zh));
+        assert_eq!(fs::read_dir(path.parent().unwrap()).unwrap().count(), 1);
+
+        fs::write(&path, "{\"language\":").unwrap();
+        assert_eq!(read_language_preference(&path), None);
+    }
+
+    #[test]
+    fn test_keys_grouped_by_prefix() {
+        let mut i18n = I18n::with_external_dir(Language::En, None);
+        i18n.add_message("nodot", "无", "None");
+        let groups = i18n.keys_by_prefix();
+
+        let provider = &groups["provider"];
+        assert!(provider.contains(&"provider.clear_success".to_string()));
+        assert!(provider.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert!(provider.iter().all(|key| key.starts_with("provider.")));
+        assert_eq!(groups["nodot"], vec!["nodot".to_string()]);
+    }
+
+    #[test]
+    fn test_args_are_substituted_once() {
+        // A value containing a later placeholder is not substituted again
+        assert_eq!(
+            substitute_args("{name}: {error}", &[("name", "{error}"), ("error", "boom")]),
+            "{error}: boom"
+        );
+        // Braces in values and unknown placeholders are left intact
+        assert_eq!(
+            substitute_args("{a} {unknown} {{a}} {", &[("a", "{x} }{")]),
+            "{x} }{ {unknown} {{x} }{} {"
+        );
+        assert_eq!(substitute_args("无参数", &[("a", "b")]), "无参数");
+    }
+
+    #[test]
+    fn test_macro_accepts_display_values() {
+        let sessions = [1, 2, 3];
+        let message = t!("i18n.reloaded", "count" => sessions.len());
+        assert!(message.contains('3'), "{}", message);
+        // String slices still work as before
+        assert_eq!(t!("provider.create_config_dir_failed", "error" => "disk full"), t_with_args("provider.create_config_dir_failed", &[("error", "disk full")]));
+    }
+
+    #[test]
+    fn test_po_round_trip_keeps_chinese_values() {
+        let pairs = BTreeMap::from([
+            ("a.quoted".to_string(), TranslationPair { zh: Some("引号 \"{name}\"\n换行".to_string()), en: Some("Quote \"{name}\"".to_string()) }),
+            ("b.untranslated".to_string(), TranslationPair { zh: None, en: Some("Only English".to_string()) }),
+        ]);
+        let po = render_translations(&pairs, TranslationFormat::Po).unwrap();
+        assert!(po.contains("msgctxt \"a.quoted\"\nmsgid \"Quote \\\"{name}\\\"\""), "{}", po);
+
+        let parsed = parse_translations(&po, TranslationFormat::Po).unwrap();
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed["a.quoted"].zh, pairs["a.quoted"].zh);
+        assert_eq!(parsed["b.untranslated"], TranslationPair::default());
+        assert!(parse_translations("msgstr unquoted", TranslationFormat::Po).is_err());
+    }
+
+    #[test]
+    fn test_merge_overrides_drops_values_matching_builtin() {
+        let builtin = I18n::builtin();
+        let builtin_en = builtin.messages["provider.clear_success"][&Language::En].clone();
+        let mut overrides = BTreeMap::from([("provider.clear_success".to_string(), "Old override".to_string())]);
+        let imported = BTreeMap::from([
+            ("provider.clear_success".to_string(), TranslationPair { zh: None, en: Some(builtin_en) }),
+            ("custom.key".to_string(), TranslationPair { zh: Some("自定义".to_string()), en: Some("Custom".to_string()) }),
+            ("empty.key".to_string(), TranslationPair { zh: Some(String::new()), en: None }),
+        ]);
+
+        assert_eq!(merge_overrides(&mut overrides, &builtin, &Language::En, &imported), 2);
+        assert_eq!(overrides, BTreeMap::from([("custom.key".to_string(), "Custom".to_string())]));
+        // Re-importing the same values changes nothing
+        assert_eq!(merge_overrides(&mut overrides, &builtin, &Language::En, &imported), 0);
+    }
+
+    #[test]
+    fn test_malformed_external_file_keeps_builtin() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("en.json");
+        fs::write(&path, "{ not json").unwrap();
+
+        let mut i18n = I18n::with_external_dir(Language::En, None);
+        assert!(i18n.merge_external_file(&Language::En, &path).is_err());
+        assert_eq!(i18n.t("provider.clear_success"), "Cleared all ANTHROPIC environment variables in Raw Settings");
+    }
+}
@@ -6,6 +6,9 @@ pub mod claude_binary;
 pub mod commands;
 pub mod process;
 pub mod i18n;
+pub mod logging;
+pub mod paths;
+pub mod redact;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
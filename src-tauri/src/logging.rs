@@ -0,0 +1,165 @@
+//! Logger setup. By default logs go to stderr through `env_logger` (`RUST_LOG`); setting
+//! `CLAUDE_SUITE_LOG` (same filter syntax, e.g. `info` or `warn,claude_suite=debug`) switches to
+//! JSON lines in a size-rotated file under `~/.claude/logs` (see `paths::claude_dir`).
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Env var that enables JSON file logging and sets its level filter
+pub const LOG_ENV_VAR: &str = "CLAUDE_SUITE_LOG";
+
+/// Name of the active log file; rotated copies get a `.1`, `.2`, ... suffix
+const LOG_FILE_NAME: &str = "claude-suite.log";
+
+/// Size at which the active log file is rotated
+const MAX_LOG_FILE_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Number of rotated files kept besides the active one
+const MAX_ROTATED_FILES: usize = 5;
+
+/// Installs the global logger. Call once at startup, before anything logs.
+pub fn init() {
+    let Some(filters) = std::env::var(LOG_ENV_VAR).ok().filter(|s| !s.trim().is_empty()) else {
+        env_logger::init();
+        return;
+    };
+
+    let file = crate::paths::claude_dir()
+        .map(|dir| dir.join("logs"))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "home directory not found"))
+        .and_then(|dir| RotatingFile::open(&dir, MAX_LOG_FILE_BYTES, MAX_ROTATED_FILES));
+
+    match file {
+        Ok(file) => {
+            env_logger::Builder::new()
+                .parse_filters(&filters)
+                .format(|buf, record| writeln!(buf, "{}", json_line(record)))
+                .target(env_logger::Target::Pipe(Box::new(file)))
+                .init();
+        }
+        Err(e) => {
+            env_logger::init();
+            log::warn!("JSON file logging disabled, falling back to stderr: {}", e);
+        }
+    }
+}
+
+/// Formats a record as one JSON object
+fn json_line(record: &log::Record) -> serde_json::Value {
+    let mut fields = serde_json::Map::new();
+    if let Some(module) = record.module_path() {
+        fields.insert("module".to_string(), module.into());
+    }
+    if let Some(file) = record.file() {
+        fields.insert("file".to_string(), file.into());
+    }
+    if let Some(line) = record.line() {
+        fields.insert("line".to_string(), line.into());
+    }
+
+    serde_json::json!({
+        "timestamp": chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        "level": record.level().to_string(),
+        "target": record.target(),
+        "message": record.args().to_string(),
+        "fields": fields,
+    })
+}
+
+/// An append-only log file that rolls over to `<name>.1` once it reaches `max_bytes`
+struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    max_bytes: u64,
+    max_files: usize,
+}
+
+impl RotatingFile {
+    fn open(dir: &Path, max_bytes: u64, max_files: usize) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let path = dir.join(LOG_FILE_NAME);
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self { path, file, size, max_bytes, max_files })
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{}", index));
+        PathBuf::from(name)
+    }
+
+    /// Shifts `.1` -> `.2` and so on, dropping the oldest, then starts a fresh active file
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        if self.max_files == 0 {
+            self.file = File::create(&self.path)?;
+        } else {
+            let _ = fs::remove_file(self.rotated_path(self.max_files));
+            for index in (1..self.max_files).rev() {
+                let from = self.rotated_path(index);
+                if from.exists() {
+                    fs::rename(&from, self.rotated_path(index + 1))?;
+                }
+            }
+            fs::rename(&self.path, self.rotated_path(1))?;
+            self.file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        }
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.size > 0 && self.size + buf.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_line_fields() {
+        let args = format_args!("hello {}", 42);
+        let record = log::Record::builder()
+            .args(args)
+            .level(log::Level::Warn)
+            .target("relay")
+            .line(Some(7))
+            .build();
+        let line = json_line(&record);
+        assert_eq!(line["level"], "WARN");
+        assert_eq!(line["target"], "relay");
+        assert_eq!(line["message"], "hello 42");
+        assert_eq!(line["fields"]["line"], 7);
+        assert!(line["timestamp"].as_str().is_some());
+    }
+
+    #[test]
+    fn test_rotating_file_caps_size_and_count() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut file = RotatingFile::open(dir.path(), 16, 2).unwrap();
+        for i in 0..5 {
+            writeln!(file, "line number {}", i).unwrap();
+        }
+
+        let active = fs::read_to_string(dir.path().join(LOG_FILE_NAME)).unwrap();
+        assert_eq!(active, "line number 4\n");
+        assert_eq!(fs::read_to_string(file.rotated_path(1)).unwrap(), "line number 3\n");
+        assert_eq!(fs::read_to_string(file.rotated_path(2)).unwrap(), "line number 2\n");
+        assert!(!file.rotated_path(3).exists());
+    }
+}
@@ -6,6 +6,9 @@ mod claude_binary;
 mod commands;
 mod process;
 mod i18n;
+mod logging;
+mod paths;
+mod redact;
 
 use checkpoint::state::CheckpointState;
 use commands::agents::{
@@ -51,23 +54,36 @@ use commands::clipboard::{
 };
 use commands::provider::{
     get_provider_presets, get_current_provider_config, switch_provider_config,
-    clear_provider_config, test_provider_connection, add_provider_config,
+    clear_provider_config, clear_provider_config_preview, import_providers_from_settings_json, repair_providers_config, validate_settings_json, repair_settings_json, test_provider_connection, validate_provider_config, add_provider_config,
     update_provider_config, delete_provider_config, get_provider_config,
-    detect_current_provider, is_provider_applied, set_backend_language, get_backend_language,
+    detect_current_provider, detect_current_provider_verified, diff_provider_against_current, is_provider_applied, set_backend_language, set_language_persisted, get_backend_language,
+    switch_with_fallback, get_provider_history, set_provider_history_limit, restore_previous_provider,
+    reload_translations, get_missing_translations, clear_missing_translations, list_all_translation_keys,
+    export_translations, import_translations,
+    save_profile, list_profiles, apply_profile, delete_profile,
+    save_provider_group, list_provider_groups, delete_provider_group, select_provider_weighted,
 };
 use commands::relay_stations::{
     list_relay_stations, get_relay_station, add_relay_station, update_relay_station,
-    delete_relay_station, get_station_info, list_station_tokens, add_station_token,
-    update_station_token, delete_station_token, get_token_user_info, get_station_logs,
-    test_station_connection, api_user_self_groups, toggle_station_token, RelayStationManager,
+    delete_relay_station, clear_all_caches, check_token_scope, get_station_info, refresh_station_info, refresh_quota_per_unit, rotate_system_token, swap_station_credentials, list_station_tokens,
+    clone_token_to_station, get_relay_rate_limits, set_relay_rate_limits, get_default_page_size, set_default_page_size, get_max_response_size, set_max_response_size, get_station_quota_timeseries, get_total_spend, get_station_error_rate, get_station_latency_stats, get_usage_dashboard, get_token_usage, add_station_token,
+    update_station_token, delete_station_token, get_token_user_info, get_station_logs, search_station_logs,
+    test_station_connection, test_adapter_config, test_token, get_station_capabilities, api_user_self_groups, toggle_station_token, set_token_status, list_all_station_tokens, sync_station_tokens, list_cached_station_tokens, list_low_quota_tokens, delete_station_tokens,
+    search_stations, search_tokens, list_stations_by_tag, set_stations_enabled, set_stations_enabled_by_tag, duplicate_relay_station,
+    list_deleted_stations, restore_station, purge_station, get_station_models, get_station_groups,
+    check_balance_alerts, start_log_stream, stop_log_stream, cancel_operation, cancel_all_operations, preview_create_token,
+    create_provider_from_token, test_all_station_connections,
+    init_relay_station_manager, relay_manager_status, RelayManagerState,
 };
+use commands::config_bundle::{export_config_bundle, import_config_bundle, export_station_template, import_station_template};
+use commands::backup::{backup_claude_dir, restore_claude_dir};
 use process::ProcessRegistryState;
 use std::sync::Mutex;
 use tauri::Manager;
 
 fn main() {
-    // Initialize logger
-    env_logger::init();
+    // Initialize logger (JSON file output when CLAUDE_SUITE_LOG is set)
+    logging::init();
 
 
     tauri::Builder::default()
@@ -85,19 +101,20 @@ fn main() {
                 .app_data_dir()
                 .expect("Failed to get app data dir")
                 .join("agents.db");
-            let relay_conn = rusqlite::Connection::open(&db_path).expect("Failed to open database for relay stations");
-            let relay_manager = RelayStationManager::new(std::sync::Arc::new(Mutex::new(relay_conn)))
-                .expect("Failed to initialize relay station manager");
-            app.manage(Mutex::new(Some(relay_manager)) as Mutex<Option<RelayStationManager>>);
+            // A failed init is reported through relay_manager_status and can be retried
+            let relay_state = RelayManagerState::new(db_path);
+            if let Err(e) = relay_state.init() {
+                log::error!("{}", e);
+            }
+            app.manage(relay_state);
 
             // Initialize checkpoint state
             let checkpoint_state = CheckpointState::new();
 
             // Set the Claude directory path
-            if let Ok(claude_dir) = dirs::home_dir()
+            if let Ok(claude_dir) = paths::claude_dir()
                 .ok_or_else(|| "Could not find home directory")
-                .and_then(|home| {
-                    let claude_path = home.join(".claude");
+                .and_then(|claude_path| {
                     claude_path
                         .canonicalize()
                         .map_err(|_| "Could not find ~/.claude directory")
@@ -243,16 +260,43 @@ fn main() {
             get_provider_presets,
             get_current_provider_config,
             switch_provider_config,
+            switch_with_fallback,
+            get_provider_history,
+            set_provider_history_limit,
+            restore_previous_provider,
             clear_provider_config,
+            clear_provider_config_preview,
+            import_providers_from_settings_json,
+            repair_providers_config,
+            validate_settings_json,
+            repair_settings_json,
             test_provider_connection,
+            validate_provider_config,
             add_provider_config,
             update_provider_config,
             delete_provider_config,
             get_provider_config,
             detect_current_provider,
+            detect_current_provider_verified,
+            diff_provider_against_current,
             is_provider_applied,
             set_backend_language,
+            set_language_persisted,
             get_backend_language,
+            reload_translations,
+            get_missing_translations,
+            clear_missing_translations,
+            list_all_translation_keys,
+            export_translations,
+            import_translations,
+            save_profile,
+            list_profiles,
+            apply_profile,
+            delete_profile,
+            save_provider_group,
+            list_provider_groups,
+            delete_provider_group,
+            select_provider_weighted,
             
             // Relay Station Management
             list_relay_stations,
@@ -260,16 +304,72 @@ fn main() {
             add_relay_station,
             update_relay_station,
             delete_relay_station,
+            clear_all_caches,
+            check_token_scope,
             get_station_info,
+            refresh_station_info,
+            refresh_quota_per_unit,
+            rotate_system_token,
+            swap_station_credentials,
+            clone_token_to_station,
+            get_relay_rate_limits,
+            set_relay_rate_limits,
+            get_default_page_size,
+            set_default_page_size,
+            get_max_response_size,
+            set_max_response_size,
+            get_station_quota_timeseries,
+            get_total_spend,
+            get_station_error_rate,
+            get_station_latency_stats,
+            get_usage_dashboard,
+            get_token_usage,
+            init_relay_station_manager,
+            relay_manager_status,
             list_station_tokens,
             add_station_token,
+            preview_create_token,
+            create_provider_from_token,
             update_station_token,
             delete_station_token,
+            delete_station_tokens,
+            search_stations,
+            search_tokens,
+            list_stations_by_tag,
+            set_stations_enabled,
+            set_stations_enabled_by_tag,
+            duplicate_relay_station,
+            list_deleted_stations,
+            restore_station,
+            purge_station,
             get_token_user_info,
+            get_station_models,
+            get_station_groups,
+            check_balance_alerts,
             get_station_logs,
+            search_station_logs,
+            start_log_stream,
+            stop_log_stream,
+            cancel_operation,
+            cancel_all_operations,
             test_station_connection,
+            test_adapter_config,
+            test_token,
+            get_station_capabilities,
+            test_all_station_connections,
             api_user_self_groups,
             toggle_station_token,
+            set_token_status,
+            list_all_station_tokens,
+            sync_station_tokens,
+            list_cached_station_tokens,
+            list_low_quota_tokens,
+            export_config_bundle,
+            import_config_bundle,
+            export_station_template,
+            import_station_template,
+            backup_claude_dir,
+            restore_claude_dir,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
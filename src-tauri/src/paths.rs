@@ -0,0 +1,35 @@
+//! Location of the Claude config directory. Defaults to `~/.claude`; `CLAUDE_SUITE_CONFIG_DIR`
+//! overrides it so tests and portable installs never touch the real home directory.
+
+use std::path::PathBuf;
+
+/// Env var that replaces `~/.claude` for providers, settings, logs and translations
+pub const CONFIG_DIR_ENV_VAR: &str = "CLAUDE_SUITE_CONFIG_DIR";
+
+/// The Claude config directory, without creating it. `None` only when there is no override
+/// and no home directory.
+pub fn claude_dir() -> Option<PathBuf> {
+    std::env::var_os(CONFIG_DIR_ENV_VAR)
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".claude")))
+}
+
+#[cfg(test)]
+static CONFIG_DIR_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+/// Runs `test` with `CLAUDE_SUITE_CONFIG_DIR` pointing at a fresh, not yet created temp dir.
+/// Every test that resolves `claude_dir` must go through here, since env vars are process-wide.
+#[cfg(test)]
+pub(crate) fn with_config_dir(test: impl FnOnce(&std::path::Path)) {
+    let _guard = CONFIG_DIR_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    // A directory that doesn't exist yet is created on first use
+    let config_dir = temp_dir.path().join("nested");
+    std::env::set_var(CONFIG_DIR_ENV_VAR, &config_dir);
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| test(&config_dir)));
+    std::env::remove_var(CONFIG_DIR_ENV_VAR);
+    if let Err(panic) = result {
+        std::panic::resume_unwind(panic);
+    }
+}
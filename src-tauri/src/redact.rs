@@ -0,0 +1,48 @@
+//! Masking for secrets (auth tokens, API keys, system tokens) that could reach logs or error messages.
+
+/// Number of trailing characters left visible so a secret can still be told apart
+const VISIBLE_SUFFIX: usize = 4;
+
+/// Masks all but the last 4 characters of a secret, e.g. `sk-abcdef1234` becomes `*********1234`.
+/// Secrets of 4 characters or fewer are masked entirely.
+pub fn redact(secret: &str) -> String {
+    let len = secret.chars().count();
+    if len <= VISIBLE_SUFFIX {
+        return "*".repeat(len);
+    }
+    let suffix: String = secret.chars().skip(len - VISIBLE_SUFFIX).collect();
+    format!("{}{}", "*".repeat(len - VISIBLE_SUFFIX), suffix)
+}
+
+/// Redacts an optional secret, keeping `None` as is
+pub fn redact_opt(secret: &Option<String>) -> Option<String> {
+    secret.as_deref().map(redact)
+}
+
+/// Replaces every occurrence of the given secrets in `text` with their redacted form
+pub fn scrub(text: &str, secrets: &[&str]) -> String {
+    secrets
+        .iter()
+        .filter(|secret| !secret.is_empty())
+        .fold(text.to_string(), |text, secret| text.replace(secret, &redact(secret)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_keeps_last_four() {
+        assert_eq!(redact("sk-abcdef1234"), "*********1234");
+        assert_eq!(redact("abcd"), "****");
+        assert_eq!(redact(""), "");
+    }
+
+    #[test]
+    fn test_scrub_removes_secrets_from_text() {
+        let message = "request to https://relay.example.com?key=sk-abcdef1234 failed";
+        let scrubbed = scrub(message, &["sk-abcdef1234", ""]);
+        assert!(!scrubbed.contains("sk-abcdef1234"));
+        assert!(scrubbed.contains("*********1234"));
+    }
+}
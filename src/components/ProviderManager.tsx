@@ -101,8 +101,8 @@ export default function ProviderManager({ onBack, onProviderChanged }: ProviderM
   const testConnection = async (config: ProviderConfig) => {
     try {
       setTesting(config.id);
-      const message = await api.testProviderConnection(config.base_url);
-      setToastMessage({ message, type: 'success' });
+      const result = await api.testProviderConnection(config);
+      setToastMessage({ message: result.message, type: result.success ? 'success' : 'error' });
     } catch (error) {
       console.error('Failed to test connection:', error);
       setToastMessage({ message: t('common.connectionTestFailed'), type: 'error' });
@@ -44,7 +44,7 @@ import { Label } from '@/components/ui/label';
 import { Textarea } from '@/components/ui/textarea';
 import { Select, SelectContent, SelectItem, SelectTrigger, SelectValue } from '@/components/ui/select';
 import { Switch } from '@/components/ui/switch';
-import { api, type RelayStation, type RelayStationAdapter, type CreateRelayStationRequest, type RelayStationToken, type StationInfo, type UserInfo, type StationLogEntry, type LogPaginationResponse, type ConnectionTestResult, type CreateTokenRequest, type UpdateTokenRequest, type TokenGroup } from '@/lib/api';
+import { api, type RelayStation, type RelayStationAdapter, type CreateRelayStationRequest, type RelayStationToken, type StationInfo, type UserInfo, type StationLogEntry, type LogPaginationResponse, type ConnectionTestResult, type CreateTokenRequest, type UpdateTokenRequest, type TokenGroup, type RelayError } from '@/lib/api';
 import { Toast } from '@/components/ui/toast';
 
 interface RelayStationManagerProps {
@@ -83,6 +83,9 @@ interface AddStationDialogProps {
   editStation?: RelayStation;
 }
 
+// 中转站返回的密钥通常不带 sk- 前缀，已带前缀时保持原样
+const withSkPrefix = (key: string) => (key.startsWith('sk-') ? key : `sk-${key}`);
+
 const AddTokenDialog: React.FC<AddTokenDialogProps> = ({ open, onOpenChange, station, onTokenAdded }) => {
   const [loading, setLoading] = useState(false);
   const [groups, setGroups] = useState<TokenGroup[]>([]);
@@ -444,7 +447,7 @@ const AddStationDialog: React.FC<AddStationDialogProps> = ({
                 </SelectTrigger>
                 <SelectContent>
                   <SelectItem value="newapi">NewAPI (完整功能)</SelectItem>
-                  <SelectItem value="oneapi">OneAPI (完整功能)</SelectItem>
+                  <SelectItem value="oneapi">OneAPI (无令牌和日志管理)</SelectItem>
                   <SelectItem value="yourapi">YourAPI (完整功能)</SelectItem>
                   <SelectItem value="custom">自定义 (仅配置切换)</SelectItem>
                 </SelectContent>
@@ -606,7 +609,7 @@ const TokenDetailView: React.FC<TokenDetailViewProps> = ({ token, station, onBac
 
   const handleCopyToken = async () => {
     try {
-      await navigator.clipboard.writeText(`sk-${token.token}`);
+      await navigator.clipboard.writeText(withSkPrefix(token.token));
       setToastMessage({ 
         message: `令牌 "${token.name}" 已复制到剪贴板！`, 
         type: 'success' 
@@ -615,7 +618,7 @@ const TokenDetailView: React.FC<TokenDetailViewProps> = ({ token, station, onBac
       console.error('Failed to copy to clipboard:', error);
       // Fallback: create a temporary textarea
       const textarea = document.createElement('textarea');
-      textarea.value = `sk-${token.token}`;
+      textarea.value = withSkPrefix(token.token);
       document.body.appendChild(textarea);
       textarea.select();
       document.execCommand('copy');
@@ -890,7 +893,7 @@ const StationDetailView: React.FC<DetailViewProps> = ({ station, onBack, onStati
     const baseUrlMatches = currentProviderConfig.anthropic_base_url === station.api_url;
     
     // 检查认证令牌是否匹配
-    const authTokenMatches = currentProviderConfig.anthropic_auth_token === `sk-${token.token}`;
+    const authTokenMatches = currentProviderConfig.anthropic_auth_token === withSkPrefix(token.token);
     
     return baseUrlMatches && authTokenMatches;
   };
@@ -985,7 +988,7 @@ const StationDetailView: React.FC<DetailViewProps> = ({ station, onBack, onStati
       setLoadedTabs(prev => new Set(prev).add('info'));
     } catch (error) {
       console.error('Failed to load basic station data:', error);
-      const errorMessage = error instanceof Error ? error.message : String(error);
+      const errorMessage = error instanceof Error ? error.message : (error as RelayError)?.message ?? String(error);
       setError(`加载站点数据失败: ${errorMessage}`);
     } finally {
       setInitialLoading(false);
@@ -1092,7 +1095,7 @@ const StationDetailView: React.FC<DetailViewProps> = ({ station, onBack, onStati
       setLoadedTabs(prev => new Set(prev).add(tabValue));
     } catch (error) {
       console.error(`Failed to load data for tab ${tabValue}:`, error);
-      const errorMessage = error instanceof Error ? error.message : String(error);
+      const errorMessage = error instanceof Error ? error.message : (error as RelayError)?.message ?? String(error);
       // You could set a specific error state for tabs if needed
       console.error(`Tab ${tabValue} error:`, errorMessage);
     } finally {
@@ -1117,7 +1120,7 @@ const StationDetailView: React.FC<DetailViewProps> = ({ station, onBack, onStati
       }));
     } catch (error) {
       console.error('Failed to load logs page:', error);
-      const errorMessage = error instanceof Error ? error.message : String(error);
+      const errorMessage = error instanceof Error ? error.message : (error as RelayError)?.message ?? String(error);
       console.error(`Logs page ${page} error:`, errorMessage);
     } finally {
       setTabLoading(false);
@@ -1394,7 +1397,7 @@ const StationDetailView: React.FC<DetailViewProps> = ({ station, onBack, onStati
         name: `${station.name} - ${token.name}`,
         description: `从中转站 ${station.name} 应用的令牌配置`,
         base_url: station.api_url,
-        auth_token: `sk-${token.token}`, // 使用sk-前缀写入ANTHROPIC_AUTH_TOKEN
+        auth_token: withSkPrefix(token.token), // 使用sk-前缀写入ANTHROPIC_AUTH_TOKEN
       };
       
       await api.switchProviderConfig(providerConfig);
@@ -1414,7 +1417,7 @@ const StationDetailView: React.FC<DetailViewProps> = ({ station, onBack, onStati
 
   const handleCopyToken = async (token: string, tokenName: string) => {
     try {
-      await navigator.clipboard.writeText(`sk-${token}`);
+      await navigator.clipboard.writeText(withSkPrefix(token));
       setToastMessage({ 
         message: `令牌 "${tokenName}" 已复制到剪贴板！`, 
         type: 'success' 
@@ -1423,7 +1426,7 @@ const StationDetailView: React.FC<DetailViewProps> = ({ station, onBack, onStati
       console.error('Failed to copy to clipboard:', error);
       // Fallback: create a temporary textarea
       const textarea = document.createElement('textarea');
-      textarea.value = `sk-${token}`;
+      textarea.value = withSkPrefix(token);
       document.body.appendChild(textarea);
       textarea.select();
       document.execCommand('copy');
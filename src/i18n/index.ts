@@ -38,7 +38,7 @@ i18n
 // Sync with backend language when frontend language changes
 i18n.on('languageChanged', async (lng: string) => {
   try {
-    await api.setBackendLanguage(lng);
+    await api.setLanguagePersisted(lng);
   } catch (error) {
     console.warn('Failed to sync backend language:', error);
   }
@@ -354,6 +354,120 @@ export interface ProviderConfig {
   auth_token?: string;
   api_key?: string;
   model?: string;
+  small_fast_model?: string;
+  extra_env?: Record<string, string>;
+  fallback_chain?: string[];
+}
+
+/**
+ * Result of testing a provider endpoint
+ */
+export interface ProviderConnectionTestResult {
+  success: boolean;
+  status: 'authorized' | 'unauthorized' | 'reachable' | 'not_found' | 'unexpected_status' | 'server_error' | 'dns_failure' | 'timeout' | 'unreachable';
+  status_code?: number;
+  latency_ms?: number;
+  message: string;
+}
+
+/**
+ * An environment variable that clearing the provider config would remove
+ */
+export interface ClearedEnvVar {
+  key: string;
+  value: string;
+}
+
+/**
+ * Outcome of repairing an old providers.json
+ */
+export interface ProvidersRepairReport {
+  total: number;
+  /** Records kept after filling defaults or regenerating ids */
+  migrated: number;
+  /** Unrecoverable records moved to the quarantine file */
+  dropped: number;
+  quarantine_path?: string;
+}
+
+/**
+ * Whether ~/.claude/settings.json parses
+ */
+export interface SettingsValidation {
+  exists: boolean;
+  valid: boolean;
+  /** Parse error with line and column */
+  error?: string;
+  /** settings.json.bak exists and parses */
+  backup_valid: boolean;
+}
+
+/**
+ * Outcome of repairing a malformed settings.json
+ */
+export interface SettingsRepairReport {
+  /** "none" when the file was missing or already valid */
+  action: 'none' | 'restored_backup' | 'salvaged';
+  recovered_keys: string[];
+  /** Copy of the malformed file taken before repairing */
+  corrupt_copy_path?: string;
+}
+
+/**
+ * A named set of equivalent providers that select_provider_weighted rotates between
+ */
+export interface ProviderGroup {
+  name: string;
+  /** Members with weight 0 are never selected */
+  members: { provider_id: string; weight: number }[];
+}
+
+/**
+ * Provider a switch passed over, and why
+ */
+export interface SkippedProvider {
+  id: string;
+  name?: string;
+  reason: string;
+}
+
+/**
+ * Outcome of a switch that may skip unavailable providers
+ */
+export interface FallbackSwitchResult {
+  applied_id: string;
+  applied_name: string;
+  skipped: SkippedProvider[];
+  message: string;
+}
+
+/**
+ * How a settings.json env var compares with what a provider would set (credentials redacted)
+ */
+export interface ProviderEnvDiff {
+  key: string;
+  current?: string;
+  provider?: string;
+  matches: boolean;
+}
+
+/**
+ * Result of detecting the current provider with a live endpoint check
+ */
+export interface VerifiedProviderDetection {
+  id: string;
+  verified: boolean;
+  reachable: boolean;
+  status: ProviderConnectionTestResult['status'];
+  message: string;
+}
+
+/**
+ * A field-level validation error for a provider configuration
+ */
+export interface ProviderFieldError {
+  field: string;
+  message: string;
 }
 
 /**
@@ -364,6 +478,7 @@ export interface CurrentProviderConfig {
   anthropic_auth_token?: string;
   anthropic_api_key?: string;
   anthropic_model?: string;
+  anthropic_small_fast_model?: string;
 }
 
 /**
@@ -517,6 +632,19 @@ export interface CreateRelayStationRequest {
   adapter_config?: Record<string, any>;
   /** Whether the station is enabled */
   enabled: boolean;
+  /** Tags used to group and filter stations */
+  tags?: string[];
+  /** Fetch the station's quota_per_unit once and store it in adapter_config */
+  discover_quota_per_unit?: boolean;
+}
+
+/**
+ * Error returned by relay station commands
+ */
+export interface RelayError {
+  kind: 'not_found' | 'manager_uninitialized' | 'invalid_input' | 'conflict' | 'unsupported' | 'upstream' | 'network' | 'database' | 'cancelled';
+  message: string;
+  status?: number;
 }
 
 /**
@@ -543,10 +671,22 @@ export interface RelayStation {
   adapter_config?: Record<string, any>;
   /** Whether the station is enabled */
   enabled: boolean;
+  /** Normalized (trimmed, lowercased) tags */
+  tags: string[];
   /** Creation timestamp */
   created_at: number;
   /** Last update timestamp */
   updated_at: number;
+  /** Timestamp when the station was moved to the recycle bin */
+  deleted_at?: number;
+  /** When its tokens, logs or user info were last fetched */
+  last_used_at?: number;
+  /** When it was last connection-tested */
+  last_tested_at?: number;
+  /** The stored adapter_config is corrupt and was ignored; proxy/header settings are missing */
+  config_parse_error: boolean;
+  /** The api_url uses https; plain http stations send their system token unencrypted */
+  is_secure: boolean;
 }
 
 /**
@@ -565,6 +705,8 @@ export interface StationInfo {
   metadata?: Record<string, any>;
   /** Quota per unit for price conversion */
   quota_per_unit?: number;
+  /** The station's api_url uses https */
+  is_secure: boolean;
 }
 
 /**
@@ -617,7 +759,7 @@ export interface RelayStationToken {
   remain_quota?: number;
   /** Whether the token has unlimited quota */
   unlimited_quota?: boolean;
-  /** Additional token metadata */
+  /** Additional token metadata; `list_station_tokens` adds `is_expired` and `expires_in_days` (null if it never expires) */
   metadata?: Record<string, any>;
   /** Creation timestamp */
   created_at: number;
@@ -639,10 +781,14 @@ export interface CreateTokenRequest {
   model_limits_enabled?: boolean;
   /** Model limits configuration */
   model_limits?: string;
+  /** Models to limit the token to; preferred over model_limits and enables limits when non-empty */
+  model_limits_list?: string[];
   /** Token group */
   group?: string;
   /** Allowed IP addresses */
   allow_ips?: string;
+  /** Reuse across manual retries of the same create; generated by the backend when omitted */
+  idempotency_key?: string;
 }
 
 /**
@@ -663,6 +809,8 @@ export interface UpdateTokenRequest {
   model_limits_enabled?: boolean;
   /** Model limits configuration */
   model_limits?: string;
+  /** Models to limit the token to; preferred over model_limits and enables limits when non-empty */
+  model_limits_list?: string[];
   /** Token group */
   group?: string;
   /** Allowed IP addresses */
@@ -732,9 +880,172 @@ export interface StationLogEntry {
   group?: string;
 }
 
+/**
+ * A recorded balance snapshot; dollar values use the quota_per_unit stored with it
+ */
+export interface QuotaSample {
+  station_id: string;
+  recorded_at: number;
+  quota: number;
+  used_quota?: number;
+  quota_per_unit: number;
+  balance_remaining: number;
+  amount_used?: number;
+}
+
+export interface StationSpend {
+  station_id: string;
+  station_name: string;
+  quota: number;
+  /** 0 when the station's adapter can't provide one; `error` then says why */
+  quota_per_unit: number;
+  /** Spend in dollars */
+  amount: number;
+  request_count: number;
+  /** Why the station's total is missing or incomplete */
+  error?: string;
+}
+
+export interface TotalSpend {
+  start: number;
+  end: number;
+  stations: StationSpend[];
+  /** Spend in dollars across all stations */
+  total_amount: number;
+}
+
+/** Nearest-rank percentiles of `use_time` in seconds; null when no entry reported one */
+export interface LatencyPercentiles {
+  count: number;
+  p50: number | null;
+  p90: number | null;
+  p99: number | null;
+  max: number | null;
+}
+
+export interface StationLatencyStats {
+  station_id: string;
+  start: number;
+  end: number;
+  overall: LatencyPercentiles;
+  streaming: LatencyPercentiles;
+  /** Entries without `is_stream` only count towards `overall` */
+  non_streaming: LatencyPercentiles;
+  /** Log entries fetched, including those without a `use_time` */
+  scanned: number;
+  /** False when the window held more entries than were scanned */
+  complete: boolean;
+}
+
+export interface StationErrorRate {
+  station_id: string;
+  start: number;
+  end: number;
+  total: number;
+  errors: number;
+  /** errors / total, 0 when there were no requests */
+  error_rate: number;
+  /** Raw upstream log type -> count */
+  type_counts: Record<string, number>;
+  /** False when the window held more entries than were scanned */
+  complete: boolean;
+}
+
+export interface DailyUsage {
+  /** UTC day, YYYY-MM-DD */
+  date: string;
+  model_name: string;
+  request_count: number;
+  token_used: number;
+  quota: number;
+}
+
+export interface UsageDashboard {
+  station_id: string;
+  start: number;
+  end: number;
+  days: DailyUsage[];
+  /** "dashboard" when read from the station's stats endpoint, "logs" when aggregated from logs */
+  source: 'dashboard' | 'logs';
+  /** False when the log fallback hit its page limit */
+  complete: boolean;
+}
+
+export interface ModelUsage {
+  model_name: string;
+  request_count: number;
+  token_used: number;
+  quota: number;
+}
+
+export interface TokenUsage {
+  station_id: string;
+  token_id: string;
+  token_name: string;
+  /** From the token record */
+  remain_quota?: number;
+  used_quota?: number;
+  unlimited_quota?: boolean;
+  start: number;
+  end: number;
+  request_count: number;
+  prompt_tokens: number;
+  completion_tokens: number;
+  /** Quota spent in the window, summed from the logs */
+  quota: number;
+  models: ModelUsage[];
+  /** Most recent log entries, newest first */
+  recent: StationLogEntry[];
+  /** False when the window held more entries than were scanned */
+  complete: boolean;
+}
+
+export interface AllStationTokens {
+  tokens: RelayStationToken[];
+  /** The 2000-token cap was hit, or the station kept repeating pages */
+  truncated: boolean;
+}
+
+export interface TokenSyncSummary {
+  added: number;
+  updated: number;
+  /** Local tokens no longer present upstream, now marked deleted */
+  removed: number;
+}
+
+export interface RelayManagerStatus {
+  initialized: boolean;
+  db_path: string;
+  /** Why the last initialization failed, if it did */
+  last_error?: string;
+}
+
+/**
+ * Request rate limits applied to relay station calls
+ */
+export interface RateLimitConfig {
+  /** Requests per second across all stations (0 = unlimited) */
+  global_rps: number;
+  /** Requests per second to any single host (0 = unlimited) */
+  per_host_rps: number;
+}
+
 /**
  * Paginated log response
  */
+export interface LogSearchResponse {
+  /** Matching entries on this page, newest first */
+  items: StationLogEntry[];
+  page: number;
+  page_size: number;
+  /** Matches across all pages */
+  total: number;
+  /** Log entries searched */
+  scanned: number;
+  /** False when the window held more entries than the scan cap */
+  complete: boolean;
+}
+
 export interface LogPaginationResponse {
   /** Log entries for the current page */
   items: StationLogEntry[];
@@ -744,6 +1055,8 @@ export interface LogPaginationResponse {
   page_size: number;
   /** Total number of log entries */
   total: number;
+  /** Cursor for the next page on stations that support keyset paging */
+  next_cursor?: string;
 }
 
 /**
@@ -760,6 +1073,42 @@ export interface TokenPaginationResponse {
   total: number;
 }
 
+/**
+ * Columns relay stations can be sorted by; latency uses the most recent connection test
+ */
+export type StationSortBy =
+  | "created_at"
+  | "updated_at"
+  | "name"
+  | "enabled"
+  | "latency"
+  | "last_used_at"
+  | "last_tested_at";
+
+/**
+ * Privilege level of a station's system token; user-scoped tokens only see their own tokens and logs
+ */
+export type TokenScope = 'admin' | 'user' | 'unknown';
+
+/**
+ * Features a relay station's adapter supports; unsupported actions should be hidden
+ */
+export interface StationCapabilities {
+  supports_tokens: boolean;
+  supports_logs: boolean;
+  supports_user_info: boolean;
+  supports_models: boolean;
+}
+
+/**
+ * A group a token can be assigned to, with the price multiplier the station applies
+ */
+export interface GroupInfo {
+  name: string;
+  ratio: number;
+  description?: string | null;
+}
+
 /**
  * Connection test result for a relay station
  */
@@ -776,6 +1125,23 @@ export interface ConnectionTestResult {
   details?: Record<string, any>;
 }
 
+/**
+ * Result of checking a token key against its relay station
+ */
+export interface TokenTestResult {
+  /** Whether the station accepted the token */
+  valid: boolean;
+  /** Response time in milliseconds */
+  response_time?: number;
+  /** HTTP status code if applicable */
+  status_code?: number;
+  /** Status message, with the token redacted */
+  message: string;
+  /** Remaining quota, when the station reports it */
+  remain_quota?: number;
+  unlimited_quota?: boolean;
+}
+
 /**
  * API client for interacting with the Rust backend
  */
@@ -2325,11 +2691,12 @@ export const api = {
   /**
    * Switches to a new provider configuration
    * @param config - The provider configuration to switch to
+   * @param modelCheck - Optionally check that a relay-station-backed provider serves its model ("warn" or "error")
    * @returns Promise resolving to success message
    */
-  async switchProviderConfig(config: ProviderConfig): Promise<string> {
+  async switchProviderConfig(config: ProviderConfig, modelCheck?: 'warn' | 'error'): Promise<string> {
     try {
-      return await invoke<string>("switch_provider_config", { config });
+      return await invoke<string>("switch_provider_config", { config, modelCheck });
     } catch (error) {
       console.error("Failed to switch provider config:", error);
       throw error;
@@ -2338,11 +2705,12 @@ export const api = {
 
   /**
    * Clears all provider-related environment variables
+   * @param dryRun - When true, only report which variables would be removed
    * @returns Promise resolving to success message
    */
-  async clearProviderConfig(): Promise<string> {
+  async clearProviderConfig(dryRun?: boolean): Promise<string> {
     try {
-      return await invoke<string>("clear_provider_config");
+      return await invoke<string>("clear_provider_config", { dryRun });
     } catch (error) {
       console.error("Failed to clear provider config:", error);
       throw error;
@@ -2350,19 +2718,157 @@ export const api = {
   },
 
   /**
-   * Tests connection to a provider endpoint
-   * @param baseUrl - The base URL to test
-   * @returns Promise resolving to test result message
+   * Lists the environment variables clearProviderConfig would remove, without changing anything
+   * @returns Promise resolving to the keys and their current values (credentials masked)
+   */
+  async clearProviderConfigPreview(): Promise<ClearedEnvVar[]> {
+    try {
+      return await invoke<ClearedEnvVar[]>("clear_provider_config_preview");
+    } catch (error) {
+      console.error("Failed to preview provider config clear:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Creates a provider from the ANTHROPIC_* env block of a Claude settings.json
+   * @param pathOrContent - Path to a settings.json file, or its JSON content
+   * @param name - Display name for the new provider
+   * @returns Promise resolving to the created provider; rejects if one with the same URL and credentials exists
+   */
+  async importProvidersFromSettingsJson(pathOrContent: string, name: string): Promise<ProviderConfig> {
+    try {
+      return await invoke<ProviderConfig>("import_providers_from_settings_json", { pathOrContent, name });
+    } catch (error) {
+      console.error("Failed to import provider from settings:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Upgrades records in an old providers.json and rewrites it; unrecoverable records are quarantined
+   * @returns Promise resolving to how many records were migrated and dropped
+   */
+  async repairProvidersConfig(): Promise<ProvidersRepairReport> {
+    try {
+      return await invoke<ProvidersRepairReport>("repair_providers_config");
+    } catch (error) {
+      console.error("Failed to repair providers config:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Checks whether settings.json parses, without changing it
+   * @returns Promise resolving to the parse result and whether a usable backup exists
+   */
+  async validateSettingsJson(): Promise<SettingsValidation> {
+    try {
+      return await invoke<SettingsValidation>("validate_settings_json");
+    } catch (error) {
+      console.error("Failed to validate settings.json:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Repairs a malformed settings.json from settings.json.bak, or by keeping the top-level keys that still parse.
+   * Fails rather than drop an env block that can't be recovered.
+   * @returns Promise resolving to the action taken
+   */
+  async repairSettingsJson(): Promise<SettingsRepairReport> {
+    try {
+      return await invoke<SettingsRepairReport>("repair_settings_json");
+    } catch (error) {
+      console.error("Failed to repair settings.json:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Saves a provider group, replacing one with the same name
+   * @param group - The group name and weighted members
+   * @returns Promise resolving to success message
+   */
+  async saveProviderGroup(group: ProviderGroup): Promise<string> {
+    try {
+      return await invoke<string>("save_provider_group", { group });
+    } catch (error) {
+      console.error("Failed to save provider group:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Lists all provider groups
+   * @returns Promise resolving to the saved groups
+   */
+  async listProviderGroups(): Promise<ProviderGroup[]> {
+    try {
+      return await invoke<ProviderGroup[]>("list_provider_groups");
+    } catch (error) {
+      console.error("Failed to list provider groups:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Deletes a provider group
+   * @param name - The group to delete
+   * @returns Promise resolving to success message
+   */
+  async deleteProviderGroup(name: string): Promise<string> {
+    try {
+      return await invoke<string>("delete_provider_group", { name });
+    } catch (error) {
+      console.error("Failed to delete provider group:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Picks a provider from a group by weight, skipping unhealthy ones, and applies it
+   * @param group - The group name
+   * @returns Promise resolving to the applied provider and any skipped members
+   */
+  async selectProviderWeighted(group: string): Promise<FallbackSwitchResult> {
+    try {
+      return await invoke<FallbackSwitchResult>("select_provider_weighted", { group });
+    } catch (error) {
+      console.error("Failed to select provider by weight:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Tests connection to a provider endpoint with its credentials
+   * @param config - The provider configuration to test
+   * @returns Promise resolving to the classified test result
    */
-  async testProviderConnection(baseUrl: string): Promise<string> {
+  async testProviderConnection(config: ProviderConfig): Promise<ProviderConnectionTestResult> {
     try {
-      return await invoke<string>("test_provider_connection", { baseUrl });
+      return await invoke<ProviderConnectionTestResult>("test_provider_connection", { config });
     } catch (error) {
       console.error("Failed to test provider connection:", error);
       throw error;
     }
   },
 
+  /**
+   * Validates a provider configuration before saving
+   * @param config - The provider configuration to check
+   * @param originalId - ID of the configuration being edited, excluded from the duplicate check
+   * @returns Promise resolving to field errors (empty when valid)
+   */
+  async validateProviderConfig(config: ProviderConfig, originalId?: string): Promise<ProviderFieldError[]> {
+    try {
+      return await invoke<ProviderFieldError[]>("validate_provider_config", { config, originalId });
+    } catch (error) {
+      console.error("Failed to validate provider config:", error);
+      throw error;
+    }
+  },
+
   /**
    * Adds a new provider configuration
    * @param config - The provider configuration to add
@@ -2444,6 +2950,34 @@ export const api = {
     }
   },
 
+  /**
+   * Compares the live settings.json env with what a provider would write
+   * @param providerId - The provider to compare against
+   * @returns Promise resolving to a per-variable diff with credentials redacted
+   */
+  async diffProviderAgainstCurrent(providerId: string): Promise<ProviderEnvDiff[]> {
+    try {
+      return await invoke<ProviderEnvDiff[]>("diff_provider_against_current", { providerId });
+    } catch (error) {
+      console.error("Failed to diff provider against current settings:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Detects the current provider and pings its endpoint to confirm the credentials work.
+   * Does network I/O; prefer detectCurrentProvider for routine checks.
+   * @returns Promise resolving to the verified detection or null if no provider is applied
+   */
+  async detectCurrentProviderVerified(): Promise<VerifiedProviderDetection | null> {
+    try {
+      return await invoke<VerifiedProviderDetection | null>("detect_current_provider_verified");
+    } catch (error) {
+      console.error("Failed to verify current provider:", error);
+      throw error;
+    }
+  },
+
   /**
    * Checks if a provider is currently applied (has non-default API configuration)
    * @returns Promise resolving to boolean indicating if provider is applied
@@ -2473,6 +3007,20 @@ export const api = {
     }
   },
 
+  /**
+   * Sets the backend language and saves it so it is restored on the next launch
+   * @param lang - Language code ('zh', 'en')
+   * @returns Promise resolving to the language code that was applied
+   */
+  async setLanguagePersisted(lang: string): Promise<string> {
+    try {
+      return await invoke<string>("set_language_persisted", { lang });
+    } catch (error) {
+      console.error("Failed to set persisted language:", error);
+      throw error;
+    }
+  },
+
   /**
    * Gets the current backend language
    * @returns Promise resolving to current language code
@@ -2490,11 +3038,13 @@ export const api = {
 
   /**
    * Lists all configured relay stations
+   * @param sortBy - Optional sort column; defaults to newest first
+   * @param order - Optional sort direction
    * @returns Promise resolving to array of relay stations
    */
-  async listRelayStations(): Promise<RelayStation[]> {
+  async listRelayStations(sortBy?: StationSortBy, order?: "asc" | "desc"): Promise<RelayStation[]> {
     try {
-      return await invoke<RelayStation[]>("list_relay_stations");
+      return await invoke<RelayStation[]>("list_relay_stations", { sortBy, order });
     } catch (error) {
       console.error("Failed to list relay stations:", error);
       throw error;
@@ -2545,33 +3095,348 @@ export const api = {
   },
 
   /**
-   * Deletes a relay station
-   * @param stationId - The ID of the relay station to delete
-   * @returns Promise resolving to success message
+   * Enables or disables several relay stations in one transaction
+   * @param stationIds - The stations to update; an unknown ID fails the whole call
+   * @param enabled - The new enabled state
+   * @returns Promise resolving to the number of stations updated
    */
-  async deleteRelayStation(stationId: string): Promise<string> {
+  async setStationsEnabled(stationIds: string[], enabled: boolean): Promise<number> {
     try {
-      return await invoke<string>("delete_relay_station", { stationId });
+      return await invoke<number>("set_stations_enabled", { stationIds, enabled });
     } catch (error) {
-      console.error("Failed to delete relay station:", error);
+      console.error("Failed to set stations enabled:", error);
       throw error;
     }
   },
 
   /**
-   * Gets station info (name, announcement, API address)
-   * @param stationId - The ID of the relay station
-   * @returns Promise resolving to station info
+   * Enables or disables every relay station with a tag
+   * @param tag - The tag to match (case-insensitive)
+   * @param enabled - The new enabled state
+   * @returns Promise resolving to the number of stations updated
    */
-  async getStationInfo(stationId: string): Promise<StationInfo> {
+  async setStationsEnabledByTag(tag: string, enabled: boolean): Promise<number> {
     try {
-      return await invoke<StationInfo>("get_station_info", { stationId });
+      return await invoke<number>("set_stations_enabled_by_tag", { tag, enabled });
+    } catch (error) {
+      console.error("Failed to set stations enabled by tag:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Deletes a relay station
+   * @param stationId - The ID of the relay station to delete
+   * @returns Promise resolving to success message
+   */
+  async deleteRelayStation(stationId: string): Promise<string> {
+    try {
+      return await invoke<string>("delete_relay_station", { stationId });
+    } catch (error) {
+      console.error("Failed to delete relay station:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Drops all cached relay station data so the next reads go to the stations
+   * @returns Promise resolving to success message
+   */
+  async clearAllCaches(): Promise<string> {
+    try {
+      return await invoke<string>("clear_all_caches");
+    } catch (error) {
+      console.error("Failed to clear caches:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Gets station info (name, announcement, API address)
+   * @param stationId - The ID of the relay station
+   * @returns Promise resolving to station info
+   */
+  async getStationInfo(stationId: string): Promise<StationInfo> {
+    try {
+      return await invoke<StationInfo>("get_station_info", { stationId });
     } catch (error) {
       console.error("Failed to get station info:", error);
       throw error;
     }
   },
 
+  /**
+   * Fetches station info from the station, bypassing the cache
+   * @param stationId - The ID of the relay station
+   * @returns Promise resolving to fresh station info
+   */
+  async refreshStationInfo(stationId: string): Promise<StationInfo> {
+    try {
+      return await invoke<StationInfo>("refresh_station_info", { stationId });
+    } catch (error) {
+      console.error("Failed to refresh station info:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Re-fetches a station's quota_per_unit and stores it in its adapter config
+   * @param stationId - The ID of the relay station
+   * @returns Promise resolving to the stored quota units per dollar
+   */
+  async refreshQuotaPerUnit(stationId: string): Promise<number> {
+    try {
+      return await invoke<number>("refresh_quota_per_unit", { stationId });
+    } catch (error) {
+      console.error("Failed to refresh quota per unit:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Reports whether the relay station manager is initialized
+   * @returns Promise resolving to the manager status, including the last init error
+   */
+  async relayManagerStatus(): Promise<RelayManagerStatus> {
+    try {
+      return await invoke<RelayManagerStatus>("relay_manager_status");
+    } catch (error) {
+      console.error("Failed to get relay manager status:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * (Re)opens the relay station database
+   * @returns Promise resolving to the manager status after initialization
+   */
+  async initRelayStationManager(): Promise<RelayManagerStatus> {
+    try {
+      return await invoke<RelayManagerStatus>("init_relay_station_manager");
+    } catch (error) {
+      console.error("Failed to initialize relay station manager:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Replaces a station's system token after the station accepts the new one
+   * @param stationId - The ID of the relay station
+   * @param newToken - The new system token
+   * @returns Promise resolving to a success message
+   */
+  async rotateSystemToken(stationId: string, newToken: string): Promise<string> {
+    try {
+      return await invoke<string>("rotate_system_token", { stationId, newToken });
+    } catch (error) {
+      console.error("Failed to rotate system token:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Swaps the system token and user ID of two relay stations in one transaction
+   * @param stationA - The ID of the first relay station
+   * @param stationB - The ID of the second relay station
+   * @returns Promise resolving to a success message; nothing changes if either station is missing
+   */
+  async swapStationCredentials(stationA: string, stationB: string): Promise<string> {
+    try {
+      return await invoke<string>("swap_station_credentials", { stationA, stationB });
+    } catch (error) {
+      console.error("Failed to swap station credentials:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Recreates a token on another station with the same name, quota, model limits and group
+   * @param sourceStationId - The station that owns the token
+   * @param tokenId - The token to copy
+   * @param destStationId - The station to create the copy on
+   * @returns Promise resolving to the newly created token (with its own key)
+   */
+  async cloneTokenToStation(sourceStationId: string, tokenId: string, destStationId: string): Promise<RelayStationToken> {
+    try {
+      return await invoke<RelayStationToken>("clone_token_to_station", { sourceStationId, tokenId, destStationId });
+    } catch (error) {
+      console.error("Failed to clone token to station:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Gets the page size used for logs and token lists when none is passed
+   * @returns Promise resolving to the default page size
+   */
+  async getDefaultPageSize(): Promise<number> {
+    try {
+      return await invoke<number>("get_default_page_size");
+    } catch (error) {
+      console.error("Failed to get default page size:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Sets the default page size for logs and token lists (1-200)
+   * @param pageSize - The new default page size
+   * @returns Promise resolving to the page size now in effect
+   */
+  async setDefaultPageSize(pageSize: number): Promise<number> {
+    try {
+      return await invoke<number>("set_default_page_size", { pageSize });
+    } catch (error) {
+      console.error("Failed to set default page size:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Gets the largest response body, in bytes, read from a station
+   * @returns Promise resolving to the current limit
+   */
+  async getMaxResponseSize(): Promise<number> {
+    try {
+      return await invoke<number>("get_max_response_size");
+    } catch (error) {
+      console.error("Failed to get max response size:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Sets the largest response body read from a station (default 16 MB, at least 64 KB)
+   * @param maxBytes - The new limit in bytes
+   * @returns Promise resolving to the limit now in effect
+   */
+  async setMaxResponseSize(maxBytes: number): Promise<number> {
+    try {
+      return await invoke<number>("set_max_response_size", { maxBytes });
+    } catch (error) {
+      console.error("Failed to set max response size:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Gets the request rates adapter calls are limited to
+   * @returns Promise resolving to the global and per-host requests per second
+   */
+  async getRelayRateLimits(): Promise<RateLimitConfig> {
+    try {
+      return await invoke<RateLimitConfig>("get_relay_rate_limits");
+    } catch (error) {
+      console.error("Failed to get relay rate limits:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Sets the request rates adapter calls are limited to (0 disables a limit)
+   * @param config - The global and per-host requests per second
+   * @returns Promise resolving to the applied limits
+   */
+  async setRelayRateLimits(config: RateLimitConfig): Promise<RateLimitConfig> {
+    try {
+      return await invoke<RateLimitConfig>("set_relay_rate_limits", { config });
+    } catch (error) {
+      console.error("Failed to set relay rate limits:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Sums spend across all enabled stations from their logs
+   * @param start - Range start (Unix seconds)
+   * @param end - Range end (Unix seconds, inclusive)
+   * @returns Promise resolving to per-station spend and the grand total
+   */
+  async getTotalSpend(start: number, end: number): Promise<TotalSpend> {
+    try {
+      return await invoke<TotalSpend>("get_total_spend", { start, end });
+    } catch (error) {
+      console.error("Failed to get total spend:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Computes a station's error rate from its recent logs
+   * @param stationId - The station ID
+   * @param window - Look-back window in seconds (defaults to one hour)
+   * @returns Promise resolving to error counts and a histogram of raw log types
+   */
+  async getStationErrorRate(stationId: string, window?: number): Promise<StationErrorRate> {
+    try {
+      return await invoke<StationErrorRate>("get_station_error_rate", { stationId, window });
+    } catch (error) {
+      console.error("Failed to get station error rate:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Computes response-time percentiles from a station's recent logs
+   * @param stationId - The station ID
+   * @param window - Look-back window in seconds (defaults to one day)
+   * @returns Promise resolving to p50/p90/p99 and max, overall and split by streaming
+   */
+  async getStationLatencyStats(stationId: string, window?: number): Promise<StationLatencyStats> {
+    try {
+      return await invoke<StationLatencyStats>("get_station_latency_stats", { stationId, window });
+    } catch (error) {
+      console.error("Failed to get station latency stats:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Gets per-day, per-model usage for a station
+   * @param stationId - The station ID
+   * @param days - Number of UTC days including today (defaults to 7, at most 90)
+   * @returns Promise resolving to daily totals and where they came from
+   */
+  async getUsageDashboard(stationId: string, days?: number): Promise<UsageDashboard> {
+    try {
+      return await invoke<UsageDashboard>("get_usage_dashboard", { stationId, days });
+    } catch (error) {
+      console.error("Failed to get usage dashboard:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Gets one token's quota and its usage from the station's logs, matched by token name
+   * @param stationId - The station ID
+   * @param tokenId - The token ID
+   * @param days - Number of UTC days including today (defaults to 7, at most 90)
+   * @returns Promise resolving to the token's usage summary and recent entries
+   */
+  async getTokenUsage(stationId: string, tokenId: string, days?: number): Promise<TokenUsage> {
+    try {
+      return await invoke<TokenUsage>("get_token_usage", { stationId, tokenId, days });
+    } catch (error) {
+      console.error("Failed to get token usage:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Gets balance snapshots recorded by balance checks, for charting credit burn
+   * @param stationId - The ID of the relay station
+   * @param since - Optional Unix timestamp (seconds) to start from
+   * @returns Promise resolving to samples, oldest first
+   */
+  async getStationQuotaTimeseries(stationId: string, since?: number): Promise<QuotaSample[]> {
+    try {
+      return await invoke<QuotaSample[]>("get_station_quota_timeseries", { stationId, since });
+    } catch (error) {
+      console.error("Failed to get station quota timeseries:", error);
+      throw error;
+    }
+  },
+
   /**
    * Lists all tokens for a relay station with pagination
    * @param stationId - The ID of the relay station
@@ -2592,11 +3457,12 @@ export const api = {
    * Adds a new token to a relay station
    * @param stationId - The ID of the relay station
    * @param tokenData - The token creation request data
+   * @param rejectDuplicateName - Fail with a `conflict` error if a token with the same name exists
    * @returns Promise resolving to the created token
    */
-  async addStationToken(stationId: string, tokenData: CreateTokenRequest): Promise<RelayStationToken> {
+  async addStationToken(stationId: string, tokenData: CreateTokenRequest, rejectDuplicateName?: boolean): Promise<RelayStationToken> {
     try {
-      return await invoke<RelayStationToken>("add_station_token", { stationId, tokenData });
+      return await invoke<RelayStationToken>("add_station_token", { stationId, tokenData, rejectDuplicateName });
     } catch (error) {
       console.error("Failed to add station token:", error);
       throw error;
@@ -2650,6 +3516,79 @@ export const api = {
     }
   },
 
+  /**
+   * Fetches every page of a station's tokens, up to 2000
+   * @param stationId - The ID of the relay station
+   * @returns Promise resolving to the tokens and whether the list was truncated
+   */
+  async listAllStationTokens(stationId: string): Promise<AllStationTokens> {
+    try {
+      return await invoke<AllStationTokens>("list_all_station_tokens", { stationId });
+    } catch (error) {
+      console.error("Failed to list all station tokens:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Fetches every token on the station and mirrors them into the local store
+   * @param stationId - The ID of the relay station
+   * @returns Promise resolving to added/updated/removed counts
+   */
+  async syncStationTokens(stationId: string): Promise<TokenSyncSummary> {
+    try {
+      return await invoke<TokenSyncSummary>("sync_station_tokens", { stationId });
+    } catch (error) {
+      console.error("Failed to sync station tokens:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Lists tokens from the last sync without contacting the station
+   * @param stationId - The ID of the relay station
+   * @returns Promise resolving to the cached tokens
+   */
+  async listCachedStationTokens(stationId: string): Promise<RelayStationToken[]> {
+    try {
+      return await invoke<RelayStationToken[]>("list_cached_station_tokens", { stationId });
+    } catch (error) {
+      console.error("Failed to list cached station tokens:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Lists synced tokens with less than `threshold` quota units remaining
+   * @param stationId - The ID of the relay station
+   * @param threshold - Remaining quota below which a token counts as low
+   * @returns Promise resolving to the low tokens, lowest first
+   */
+  async listLowQuotaTokens(stationId: string, threshold: number): Promise<RelayStationToken[]> {
+    try {
+      return await invoke<RelayStationToken[]>("list_low_quota_tokens", { stationId, threshold });
+    } catch (error) {
+      console.error("Failed to list low quota tokens:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Pauses or resumes a token on the station without changing its quota or limits
+   * @param stationId - The ID of the relay station
+   * @param tokenId - The ID of the token
+   * @param enabled - Whether the token should be enabled
+   * @returns Promise resolving to the updated token
+   */
+  async setTokenStatus(stationId: string, tokenId: string, enabled: boolean): Promise<RelayStationToken> {
+    try {
+      return await invoke<RelayStationToken>("set_token_status", { stationId, tokenId, enabled });
+    } catch (error) {
+      console.error("Failed to set token status:", error);
+      throw error;
+    }
+  },
+
   /**
    * Gets available token groups for a user
    * @param stationId - The ID of the relay station
@@ -2674,6 +3613,20 @@ export const api = {
     }
   },
 
+  /**
+   * Lists the groups a relay station offers for tokens, sorted by name (cached briefly)
+   * @param stationId - The ID of the relay station
+   * @returns Promise resolving to the station's groups and their ratios
+   */
+  async getStationGroups(stationId: string): Promise<GroupInfo[]> {
+    try {
+      return await invoke<GroupInfo[]>("get_station_groups", { stationId });
+    } catch (error) {
+      console.error("Failed to get station groups:", error);
+      throw error;
+    }
+  },
+
   /**
    * Gets user information for a relay station
    * @param stationId - The ID of the relay station
@@ -2695,28 +3648,146 @@ export const api = {
    * @param page - Page number for pagination (1-based)
    * @param pageSize - Number of log entries per page
    * @param filters - Optional filtering parameters
+   * @param cursor - `next_cursor` from the previous page, used instead of `page` when supported
+   * @param operationId - Optional id for cancelling the request with `cancelOperation`
    * @returns Promise resolving to paginated log response
    */
-  async getStationLogs(stationId: string, page?: number, pageSize?: number, filters?: any): Promise<LogPaginationResponse> {
+  async getStationLogs(stationId: string, page?: number, pageSize?: number, filters?: any, cursor?: string, operationId?: string): Promise<LogPaginationResponse> {
     try {
-      return await invoke<LogPaginationResponse>("get_station_logs", { stationId, page, pageSize, filters });
+      return await invoke<LogPaginationResponse>("get_station_logs", { stationId, page, pageSize, cursor, filters, operationId });
     } catch (error) {
       console.error("Failed to get station logs:", error);
       throw error;
     }
   },
 
+  /**
+   * Searches a station's recent logs for text in the message, model or token name (case-insensitive)
+   * @param stationId - The ID of the relay station
+   * @param query - Text to look for
+   * @param window - Seconds to look back (defaults to one day)
+   * @param page - Page of matches (1-based)
+   * @param pageSize - Matches per page
+   * @param maxScan - Most log entries to fetch and search (defaults to 10,000, at most 50,000)
+   * @param operationId - Optional id for cancelling the search with `cancelOperation`
+   * @returns Promise resolving to a page of matches
+   */
+  async searchStationLogs(
+    stationId: string,
+    query: string,
+    window?: number,
+    page?: number,
+    pageSize?: number,
+    maxScan?: number,
+    operationId?: string
+  ): Promise<LogSearchResponse> {
+    try {
+      return await invoke<LogSearchResponse>("search_station_logs", { stationId, query, window, page, pageSize, maxScan, operationId });
+    } catch (error) {
+      console.error("Failed to search station logs:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Cancels a request started with the given operation id; it then rejects with kind 'cancelled'
+   * @param operationId - The id passed to the request
+   * @returns Promise resolving to a status message
+   */
+  async cancelOperation(operationId: string): Promise<string> {
+    try {
+      return await invoke<string>("cancel_operation", { operationId });
+    } catch (error) {
+      console.error("Failed to cancel operation:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Cancels every cancellable request and live log stream, e.g. when leaving the relay views
+   * @returns Promise resolving to the number of operations and streams cancelled
+   */
+  async cancelAllOperations(): Promise<number> {
+    try {
+      return await invoke<number>("cancel_all_operations");
+    } catch (error) {
+      console.error("Failed to cancel operations:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Gets the features a relay station's adapter supports
+   * @param stationId - The ID of the relay station
+   * @returns Promise resolving to the capability flags
+   */
+  async getStationCapabilities(stationId: string): Promise<StationCapabilities> {
+    try {
+      return await invoke<StationCapabilities>("get_station_capabilities", { stationId });
+    } catch (error) {
+      console.error("Failed to get station capabilities:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Checks whether a relay station's system token has admin privileges
+   * @param stationId - The ID of the relay station
+   * @returns Promise resolving to the token scope
+   */
+  async checkTokenScope(stationId: string): Promise<TokenScope> {
+    try {
+      return await invoke<TokenScope>("check_token_scope", { stationId });
+    } catch (error) {
+      console.error("Failed to check token scope:", error);
+      throw error;
+    }
+  },
+
   /**
    * Tests connection to a relay station
    * @param stationId - The ID of the relay station
-   * @returns Promise resolving to test result
+   * @param timeoutSecs - Per-request timeout; defaults to the station timeout capped at 10s
+   * @param includeTiming - Also time DNS/TCP/TLS/first byte with a separate bare request to /api/status
+   * @returns Promise resolving to test result; `details.timeout_ms` holds the timeout used
    */
-  async testStationConnection(stationId: string): Promise<ConnectionTestResult> {
+  async testStationConnection(stationId: string, timeoutSecs?: number, includeTiming?: boolean): Promise<ConnectionTestResult> {
     try {
-      return await invoke<ConnectionTestResult>("test_station_connection", { stationId });
+      return await invoke<ConnectionTestResult>("test_station_connection", { stationId, timeoutSecs, includeTiming });
     } catch (error) {
       console.error("Failed to test station connection:", error);
       throw error;
     }
+  },
+
+  /**
+   * Tests a relay station with a proposed adapter_config without saving it or recording the result
+   * @param stationId - The ID of the relay station
+   * @param proposedConfig - Full adapter_config to try in place of the stored one
+   * @param timeoutSecs - Per-request timeout; defaults to the proposed config's timeout capped at 10s
+   * @returns Promise resolving to test result; an invalid proxy or CA rejects with `invalid_input`
+   */
+  async testAdapterConfig(stationId: string, proposedConfig: Record<string, any>, timeoutSecs?: number): Promise<ConnectionTestResult> {
+    try {
+      return await invoke<ConnectionTestResult>("test_adapter_config", { stationId, proposedConfig, timeoutSecs });
+    } catch (error) {
+      console.error("Failed to test adapter config:", error);
+      throw error;
+    }
+  },
+
+  /**
+   * Checks that a relay station accepts a token key
+   * @param stationId - The ID of the relay station
+   * @param tokenKey - The token key to test (not the station's system token)
+   * @returns Promise resolving to validity, remaining quota and latency
+   */
+  async testToken(stationId: string, tokenKey: string): Promise<TokenTestResult> {
+    try {
+      return await invoke<TokenTestResult>("test_token", { stationId, tokenKey });
+    } catch (error) {
+      console.error("Failed to test token:", error);
+      throw error;
+    }
   }
 };